@@ -0,0 +1,107 @@
+// Benchmarks of the splits and of the model loading, run with `cargo bench`.
+// `RSPLITTER_BENCH_CORPUS` selects a corpus file instead of the bundled one, so that a
+// custom corpus can be measured too.
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rsplitter::{DictionaryBackend, LanguageModel};
+
+const SHORT: &str = "rustisgreat";
+const LONG: &str = "thequickbrownfoxjumpsoverthelazydogandrunsbacktothewoodsbeforenightfalls";
+
+/// The corpus path of the benchmarks, empty for the bundled corpus
+fn corpus_path() -> String {
+    std::env::var("RSPLITTER_BENCH_CORPUS").unwrap_or_default()
+}
+
+fn model() -> LanguageModel {
+    let mut model = LanguageModel::new(corpus_path());
+    model.try_load().expect("the benchmark corpus loads");
+    model
+}
+
+fn bench_split(c: &mut Criterion) {
+    let mut model = model();
+    let mut group = c.benchmark_group("split");
+    for (name, text) in [("short", SHORT.to_string()), ("long", LONG.repeat(20))] {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function(name, |b| b.iter(|| model.split(black_box(&text))));
+    }
+    group.finish();
+}
+
+fn bench_backend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backend");
+    let text = LONG.repeat(20);
+    for (name, backend) in [
+        ("hash_map", DictionaryBackend::HashMap),
+        ("trie", DictionaryBackend::Trie),
+    ] {
+        let mut model = model();
+        model.backend = backend;
+        model.split("");
+        group.bench_function(name, |b| b.iter(|| model.split(black_box(&text))));
+    }
+    group.finish();
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let mut model = model();
+    let texts: Vec<String> = (0..1000)
+        .map(|i| if i % 2 == 0 { SHORT } else { LONG }.to_string())
+        .collect();
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(texts.len() as u64));
+    group.bench_function("split_many", |b| {
+        b.iter(|| model.split_many(black_box(&texts)))
+    });
+    group.finish();
+}
+
+fn bench_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load");
+    group.sample_size(10);
+    group.bench_function("try_load", |b| {
+        b.iter_batched(
+            || LanguageModel::new(corpus_path()),
+            |mut model| model.try_load().map(|()| model),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_load_and_split(c: &mut Criterion) {
+    let texts: Vec<String> = (0..1000)
+        .map(|i| if i % 2 == 0 { SHORT } else { LONG }.to_string())
+        .collect();
+    let mut group = c.benchmark_group("load_and_split");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.iter_batched(
+            || LanguageModel::new(corpus_path()),
+            |mut model| {
+                model.try_load().expect("the benchmark corpus loads");
+                let splits: Vec<String> = texts.iter().map(|text| model.split(text)).collect();
+                splits
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("load_and_split_many", |b| {
+        b.iter_batched(
+            || LanguageModel::new(corpus_path()),
+            |mut model| model.load_and_split_many(black_box(&texts)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_split,
+    bench_backend,
+    bench_batch,
+    bench_load,
+    bench_load_and_split
+);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rsplitter::SplitError;
+
+/// Map a split error to the matching Python exception
+fn to_py_err(err: SplitError) -> PyErr {
+    match err {
+        SplitError::Io(err) => err.into(),
+        err => PyValueError::new_err(err.to_string()),
+    }
+}
+
+/// Split a text with the bundled corpus
+#[pyfunction]
+fn split(py: Python<'_>, text: String) -> String {
+    // The dynamic program does not touch Python objects
+    py.allow_threads(|| rsplitter::split(text))
+}
+
+/// The words of a split with the bundled corpus, without the whitespace
+#[pyfunction]
+fn split_words(py: Python<'_>, text: String) -> Vec<String> {
+    py.allow_threads(|| rsplitter::split_words(&text))
+}
+
+/// A language model built from a corpus, see `rsplitter::LanguageModel`
+#[pyclass(name = "LanguageModel")]
+struct PyLanguageModel {
+    model: rsplitter::LanguageModel,
+}
+
+#[pymethods]
+impl PyLanguageModel {
+    /// A model of the bundled corpus
+    #[new]
+    fn new(py: Python<'_>) -> PyResult<Self> {
+        PyLanguageModel::from_corpus_file(py, String::new())
+    }
+
+    /// A model of a corpus file, one word per line from the most to the least frequent
+    #[staticmethod]
+    fn from_corpus_file(py: Python<'_>, path: String) -> PyResult<Self> {
+        let model = py
+            .allow_threads(|| rsplitter::LanguageModel::from_corpus(path))
+            .map_err(to_py_err)?;
+        Ok(PyLanguageModel { model })
+    }
+
+    fn split(&mut self, py: Python<'_>, text: String) -> String {
+        let model = &mut self.model;
+        py.allow_threads(|| model.split(text))
+    }
+
+    fn split_words(&mut self, py: Python<'_>, text: String) -> Vec<String> {
+        let model = &mut self.model;
+        py.allow_threads(|| model.split_words(text))
+    }
+
+    /// Add a word to the dictionary, or change its cost; the cheaper the more likely
+    fn add_word(&mut self, word: &str, cost: f32) -> PyResult<()> {
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return Err(PyValueError::new_err("a word is not empty and has no whitespace"));
+        }
+        self.model
+            .insert_word(word, cost)
+            .map(|_| ())
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "rsplitter")]
+fn rsplitter_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(split, m)?)?;
+    m.add_function(wrap_pyfunction!(split_words, m)?)?;
+    m.add_class::<PyLanguageModel>()?;
+    Ok(())
+}
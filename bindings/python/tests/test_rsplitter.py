@@ -0,0 +1,37 @@
+import pytest
+
+import rsplitter
+
+
+def test_split():
+    assert rsplitter.split("rustisgreat") == "rust is great"
+
+
+def test_split_words():
+    assert rsplitter.split_words("rustisgreat now") == ["rust", "is", "great", "now"]
+
+
+def test_language_model(tmp_path):
+    corpus = tmp_path / "corpus.txt"
+    corpus.write_text("the\ncat\nsat\n")
+    model = rsplitter.LanguageModel.from_corpus_file(str(corpus))
+    assert model.split("thecatsat") == "the cat sat"
+    model.add_word("mat", 1.0)
+    assert model.split("thecatsatmat") == "the cat sat mat"
+    assert model.split_words("thecat sat") == ["the", "cat", "sat"]
+
+
+def test_bundled_corpus():
+    assert rsplitter.LanguageModel().split("rustisgreat") == "rust is great"
+
+
+def test_missing_corpus():
+    with pytest.raises(OSError):
+        rsplitter.LanguageModel.from_corpus_file("/does/not/exist")
+
+
+def test_empty_corpus(tmp_path):
+    corpus = tmp_path / "empty.txt"
+    corpus.write_text("")
+    with pytest.raises(ValueError):
+        rsplitter.LanguageModel.from_corpus_file(str(corpus))
@@ -0,0 +1,53 @@
+use rsplitter::SplitError;
+use wasm_bindgen::prelude::*;
+
+/// Map a split error to a JavaScript `Error`
+fn to_js_err(err: SplitError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Split a text with the bundled corpus
+#[wasm_bindgen]
+pub fn split(text: &str) -> String {
+    rsplitter::split(text.to_string())
+}
+
+/// The words of a split with the bundled corpus, without the whitespace
+#[wasm_bindgen(js_name = splitWords)]
+pub fn split_words(text: &str) -> Vec<String> {
+    rsplitter::split_words(text)
+}
+
+/// A language model, see `rsplitter::LanguageModel`.
+/// There is no filesystem in the browser: a corpus is given as the bytes of its file, e.g.
+/// `new Uint8Array(await response.arrayBuffer())`.
+#[wasm_bindgen(js_name = LanguageModel)]
+pub struct WasmLanguageModel {
+    model: rsplitter::LanguageModel,
+}
+
+#[wasm_bindgen(js_class = LanguageModel)]
+impl WasmLanguageModel {
+    /// A model of the bundled corpus
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmLanguageModel, JsError> {
+        let model = rsplitter::LanguageModel::from_corpus(String::new()).map_err(to_js_err)?;
+        Ok(WasmLanguageModel { model })
+    }
+
+    /// A model of a UTF-8 corpus, one word per line from the most to the least frequent
+    #[wasm_bindgen(js_name = fromCorpus)]
+    pub fn from_corpus(corpus: &[u8]) -> Result<WasmLanguageModel, JsError> {
+        let model = rsplitter::LanguageModel::from_bytes(corpus).map_err(to_js_err)?;
+        Ok(WasmLanguageModel { model })
+    }
+
+    pub fn split(&mut self, text: &str) -> String {
+        self.model.split(text.to_string())
+    }
+
+    #[wasm_bindgen(js_name = splitWords)]
+    pub fn split_words(&mut self, text: &str) -> Vec<String> {
+        self.model.split_words(text.to_string())
+    }
+}
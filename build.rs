@@ -0,0 +1,87 @@
+//! Encode the bundled corpus into `OUT_DIR` with the `bundled-corpus` feature, it is
+//! embedded encoded and decoded on first use, see `default_corpus` and `src/huffman.rs` for
+//! the format.
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/corpus.txt");
+    #[cfg(feature = "bundled-corpus")]
+    {
+        let corpus = std::fs::read("src/corpus.txt").expect("the bundled corpus is readable");
+        let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+        std::fs::write(
+            std::path::Path::new(&out_dir).join("corpus.txt.huffman"),
+            encode(&corpus),
+        )
+        .expect("the encoded corpus is written");
+    }
+}
+
+/// The Huffman code length of every byte value of the data, 0 for the missing ones
+#[cfg(feature = "bundled-corpus")]
+fn code_lengths(data: &[u8]) -> [u8; 256] {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    // The cheapest two trees are merged until one is left, the bytes of a tree are one
+    // code longer after each merge
+    let mut trees: BinaryHeap<Reverse<(u64, Vec<u8>)>> = (0..=255u8)
+        .filter(|&byte| counts[byte as usize] > 0)
+        .map(|byte| Reverse((counts[byte as usize], vec![byte])))
+        .collect();
+    let mut lengths = [0u8; 256];
+    if trees.len() == 1 {
+        let Reverse((_, bytes)) = trees.pop().unwrap();
+        lengths[bytes[0] as usize] = 1;
+    }
+    while trees.len() > 1 {
+        let Reverse((first_count, mut first)) = trees.pop().unwrap();
+        let Reverse((second_count, second)) = trees.pop().unwrap();
+        first.extend(second);
+        for &byte in &first {
+            lengths[byte as usize] += 1;
+        }
+        trees.push(Reverse((first_count + second_count, first)));
+    }
+    lengths
+}
+
+/// The data encoded with the canonical Huffman code of its bytes
+#[cfg(feature = "bundled-corpus")]
+fn encode(data: &[u8]) -> Vec<u8> {
+    let lengths = code_lengths(data);
+    assert!(
+        lengths.iter().all(|&length| length <= 32),
+        "the codes fit in 32 bits"
+    );
+    // The canonical codes: by length, then by byte value
+    let mut order: Vec<u8> = (0..=255u8).filter(|&b| lengths[b as usize] > 0).collect();
+    order.sort_by_key(|&b| lengths[b as usize]);
+    let mut codes = [0u64; 256];
+    let (mut code, mut length) = (0u64, 0u8);
+    for byte in order {
+        code <<= lengths[byte as usize] - length;
+        length = lengths[byte as usize];
+        codes[byte as usize] = code;
+        code += 1;
+    }
+    let mut encoded = lengths.to_vec();
+    encoded.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    // The pending bits, most significant first
+    let (mut pending, mut pending_bits) = (0u64, 0u8);
+    for &byte in data {
+        let length = lengths[byte as usize];
+        pending = (pending << length) | codes[byte as usize];
+        pending_bits += length;
+        while pending_bits >= 8 {
+            pending_bits -= 8;
+            encoded.push((pending >> pending_bits) as u8);
+        }
+    }
+    if pending_bits > 0 {
+        encoded.push((pending << (8 - pending_bits)) as u8);
+    }
+    encoded
+}
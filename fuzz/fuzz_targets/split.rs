@@ -0,0 +1,20 @@
+// Split arbitrary text with the bundled corpus, run with `cargo +nightly fuzz run split`.
+// A split never panics and only adds or removes whitespace: the words of the split, and
+// every number of the text, are the non-space characters of the text in order. Runs are
+// cut on ASCII whitespace only, so any other space, e.g. U+00A0, is a character of a word.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let split = rsplitter::split(text);
+    let words = rsplitter::split_words(text);
+    let characters: String = text.split_ascii_whitespace().collect();
+    assert_eq!(split.split_ascii_whitespace().collect::<String>(), characters);
+    assert_eq!(words.concat(), characters);
+    for number in text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty())
+    {
+        assert!(words.iter().any(|word| word.contains(number)));
+    }
+});
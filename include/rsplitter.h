@@ -0,0 +1,54 @@
+/* C bindings of rsplitter, generated with `cbindgen --config cbindgen.toml --output include/rsplitter.h` */
+
+#ifndef RSPLITTER_H
+#define RSPLITTER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A language model, created by rsplitter_model_new */
+typedef struct LanguageModel LanguageModel;
+
+/*
+ * Split a text with the bundled corpus, in builds with the `bundled-corpus` feature only.
+ * Returns a string to release with `rsplitter_free`, or null if `text` is null or not UTF-8.
+ */
+char *rsplitter_split(const char *text);
+
+/* Release a string returned by this library, null is ignored. */
+void rsplitter_free(char *text);
+
+/*
+ * Create a language model from a corpus file, an empty path selects the bundled corpus.
+ * Returns a model to release with `rsplitter_model_free`, or null if the path is null or
+ * not UTF-8, or the corpus cannot be read or has no word.
+ */
+LanguageModel *rsplitter_model_new(const char *corpus_path);
+
+/*
+ * Create a language model from a corpus in memory, e.g. embedded in the calling program,
+ * one word per line like a corpus file; a model is shared between threads only with a lock.
+ * Returns a model to release with `rsplitter_model_free`, or null if `bytes` is null, or the
+ * corpus is not UTF-8 or has no word.
+ */
+LanguageModel *rsplitter_model_from_bytes(const uint8_t *bytes, size_t length);
+
+/*
+ * Split a text with a model.
+ * Returns a string to release with `rsplitter_free`, or null if an argument is null or
+ * the text is not UTF-8.
+ */
+char *rsplitter_model_split(LanguageModel *model, const char *text);
+
+/* Release a model, null is ignored. */
+void rsplitter_model_free(LanguageModel *model);
+
+#ifdef __cplusplus
+}  /* extern "C" */
+#endif
+
+#endif  /* RSPLITTER_H */
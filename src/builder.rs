@@ -0,0 +1,152 @@
+use crate::error::SplitError;
+use crate::language_model::{
+    CaseMode, CostModel, DictionaryBackend, Language, LanguageModel, OovPolicy, TieBreak, WordCost,
+};
+
+/// Configures a `LanguageModel` and builds its dictionary, see `LanguageModel::builder`.
+/// Every model built has its own settings, so differently configured models coexist in one
+/// process; the settings left out keep the defaults of `LanguageModel::new`.
+pub struct LanguageModelBuilder {
+    model: LanguageModel,
+    length_penalty: f32,
+}
+
+impl LanguageModelBuilder {
+    pub(crate) fn new() -> Self {
+        LanguageModelBuilder {
+            model: LanguageModel::default(),
+            length_penalty: 0.0,
+        }
+    }
+
+    /// The corpus file, the bundled corpus when left out
+    pub fn corpus_path(mut self, corpus_path: impl Into<String>) -> Self {
+        self.model.corpus_path = corpus_path.into();
+        self
+    }
+
+    /// See `LanguageModel::language`
+    pub fn language(mut self, language: Language) -> Self {
+        self.model.language = language;
+        self
+    }
+
+    pub fn cost_model(mut self, cost_model: CostModel) -> Self {
+        self.model.cost_model = cost_model;
+        self
+    }
+
+    pub fn case_mode(mut self, case_mode: CaseMode) -> Self {
+        self.model.case_mode = case_mode;
+        self
+    }
+
+    /// See `LanguageModel::max_word_len`
+    pub fn max_word_len(mut self, max_word_len: usize) -> Self {
+        self.model.max_word_len = Some(max_word_len);
+        self
+    }
+
+    /// See `LanguageModel::separator`
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.model.separator = separator.into();
+        self
+    }
+
+    /// See `LanguageModel::set_length_penalty`, a weight that is not finite fails `build`
+    pub fn length_penalty(mut self, weight: f32) -> Self {
+        self.length_penalty = weight;
+        self
+    }
+
+    /// See `LanguageModel::beam_width`, a beam of 0 fails `build`
+    pub fn beam_width(mut self, beam_width: usize) -> Self {
+        self.model.beam_width = Some(beam_width);
+        self
+    }
+
+    /// See `LanguageModel::units`, e.g. `UNITS`
+    pub fn units<I, U>(mut self, units: I) -> Self
+    where
+        I: IntoIterator<Item = U>,
+        U: Into<String>,
+    {
+        self.model.units = units.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.model.tie_break = tie_break;
+        self
+    }
+
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.model.case_sensitive = case_sensitive;
+        self
+    }
+
+    pub fn oov_policy(mut self, oov_policy: OovPolicy) -> Self {
+        self.model.oov_policy = oov_policy;
+        self
+    }
+
+    pub fn backend(mut self, backend: DictionaryBackend) -> Self {
+        self.model.backend = backend;
+        self
+    }
+
+    /// See `LanguageModel::spelling_correction`
+    pub fn spelling_correction(mut self, enabled: bool) -> Self {
+        self.model.spelling_correction = enabled;
+        self
+    }
+
+    /// See `LanguageModel::fuzzy_cost`, a premium that is negative or not finite fails
+    /// `build`
+    pub fn fuzzy_cost(mut self, premium: f32) -> Self {
+        self.model.fuzzy_cost = Some(premium);
+        self
+    }
+
+    /// See `LanguageModel::comment_prefix`
+    pub fn comment_prefix(mut self, comment_prefix: Option<char>) -> Self {
+        self.model.comment_prefix = comment_prefix;
+        self
+    }
+
+    /// See `LanguageModel::cache_capacity`
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.model.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// See `LanguageModel::cost_function`
+    pub fn cost_function(mut self, cost_function: impl WordCost + 'static) -> Self {
+        self.model.cost_function = Some(Box::new(cost_function));
+        self
+    }
+
+    /// Build the model and its dictionary
+    /// # Returns
+    /// An error if a setting is invalid, or if the corpus cannot be read or has no word, see
+    /// `LanguageModel::try_load`
+    pub fn build(mut self) -> Result<LanguageModel, SplitError> {
+        self.model.set_length_penalty(self.length_penalty)?;
+        if self
+            .model
+            .fuzzy_cost
+            .is_some_and(|premium| !premium.is_finite() || premium < 0.0)
+        {
+            return Err(SplitError::InvalidInput(String::from(
+                "the fuzzy premium is negative or not finite",
+            )));
+        }
+        if self.model.beam_width == Some(0) {
+            return Err(SplitError::InvalidInput(String::from(
+                "the beam keeps at least one hypothesis",
+            )));
+        }
+        self.model.try_load()?;
+        Ok(self.model)
+    }
+}
@@ -0,0 +1,206 @@
+use crate::error::SplitError;
+use crate::hasher::DictHasher;
+use crate::language_model::CostDict;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// A cost dictionary shared by the models of a corpus, with the corpus casings
+pub(crate) type SharedDict = (Arc<CostDict>, HashMap<String, String, DictHasher>);
+
+/// The dictionaries built by `LanguageModel::from_corpus_cached`, by canonical corpus path
+static CACHE: LazyLock<Mutex<HashMap<String, SharedDict>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The dictionary cached for `key`, built with `build` if there is none yet.
+/// The cache stays locked while building, so a corpus is built once even when several
+/// threads ask for it at the same time.
+pub(crate) fn cached_dict(
+    key: String,
+    build: impl FnOnce() -> Result<SharedDict, SplitError>,
+) -> Result<SharedDict, SplitError> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(shared) = cache.get(&key) {
+        return Ok(shared.clone());
+    }
+    let shared = build()?;
+    cache.insert(key, shared.clone());
+    Ok(shared)
+}
+
+/// Forget the dictionaries cached by `LanguageModel::from_corpus_cached`, e.g. after a corpus
+/// file changed. Models already built keep their dictionary.
+pub fn clear_dictionary_cache() {
+    CACHE.lock().unwrap_or_else(|err| err.into_inner()).clear();
+}
+
+/// How the split cache of a model is used, see `LanguageModel::cache_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheStats {
+    /// The splits answered from the cache
+    pub hits: u64,
+    /// The splits computed, the cache not having the text
+    pub misses: u64,
+    /// The texts in the cache
+    pub size: usize,
+    /// The texts dropped from a full cache for newer ones
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The share of the splits answered from the cache, 0 before the first split
+    pub fn hit_ratio(&self) -> f64 {
+        let splits = self.hits + self.misses;
+        if splits == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / splits as f64
+    }
+}
+
+/// No entry, at an end of the recency list of `ResultCache`
+const NIL: usize = usize::MAX;
+
+/// A cached split, between the entries used right after and right before it
+struct Entry {
+    text: String,
+    split: String,
+    newer: usize,
+    older: usize,
+}
+
+/// The splits of the texts used last by a model, see `LanguageModel::cache_capacity`.
+/// A full cache drops its least recently used text: the entries are linked from the most to
+/// the least recently used, so that a hit, an insertion and an eviction are a hash lookup
+/// and a few links each.
+pub(crate) struct ResultCache {
+    /// The entry of every cached text
+    index: HashMap<String, usize, DictHasher>,
+    entries: Vec<Entry>,
+    /// The entries of the evicted texts, reused before growing `entries`
+    free: Vec<usize>,
+    newest: usize,
+    oldest: usize,
+    /// The settings of the model the splits were made with, see `LanguageModel::split`
+    settings: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        ResultCache {
+            index: HashMap::default(),
+            entries: Vec::new(),
+            free: Vec::new(),
+            newest: NIL,
+            oldest: NIL,
+            settings: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+}
+
+impl ResultCache {
+    /// The split of a text, if cached with the same settings, counting a hit or a miss.
+    /// The cache is emptied when the settings changed, the counts are kept.
+    pub(crate) fn get(&mut self, text: &str, settings: u64) -> Option<String> {
+        if settings != self.settings {
+            *self = ResultCache {
+                settings,
+                hits: self.hits,
+                misses: self.misses,
+                evictions: self.evictions,
+                ..ResultCache::default()
+            };
+        }
+        let Some(&i) = self.index.get(text) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.unlink(i);
+        self.link_newest(i);
+        Some(self.entries[i].split.clone())
+    }
+
+    /// Cache the split of a text. The least recently used texts are dropped first, so the
+    /// cache never holds more than `capacity` texts, even after the capacity was lowered.
+    pub(crate) fn insert(&mut self, text: String, split: String, capacity: usize) {
+        if let Some(&i) = self.index.get(&text) {
+            self.entries[i].split = split;
+            self.unlink(i);
+            self.link_newest(i);
+            return;
+        }
+        while self.index.len() >= capacity && self.oldest != NIL {
+            let oldest = self.oldest;
+            self.unlink(oldest);
+            self.index.remove(&self.entries[oldest].text);
+            self.free.push(oldest);
+            self.evictions += 1;
+        }
+        if capacity == 0 {
+            return;
+        }
+        let entry = Entry {
+            text: text.clone(),
+            split,
+            newer: NIL,
+            older: NIL,
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.entries[i] = entry;
+                i
+            }
+            None => {
+                self.entries.push(entry);
+                self.entries.len() - 1
+            }
+        };
+        self.link_newest(i);
+        self.index.insert(text, i);
+    }
+
+    /// Take an entry out of the recency list
+    fn unlink(&mut self, i: usize) {
+        let (newer, older) = (self.entries[i].newer, self.entries[i].older);
+        match newer {
+            NIL => self.newest = older,
+            newer => self.entries[newer].older = older,
+        }
+        match older {
+            NIL => self.oldest = newer,
+            older => self.entries[older].newer = newer,
+        }
+    }
+
+    /// Link an entry taken out of the recency list at its front, as the most recently used
+    fn link_newest(&mut self, i: usize) {
+        self.entries[i].newer = NIL;
+        self.entries[i].older = self.newest;
+        match self.newest {
+            NIL => self.oldest = i,
+            newest => self.entries[newest].newer = i,
+        }
+        self.newest = i;
+    }
+
+    /// Forget the cached splits and the counts
+    pub(crate) fn clear(&mut self) {
+        *self = ResultCache::default();
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            size: self.index.len(),
+            evictions: self.evictions,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::SplitError;
+use crate::language_model::{Language, OovPolicy};
+use crate::pretokenize::{Alphabetic, Hashtag, Identifier, PreToken, PreTokenizer, Whitespace};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// The settings of a deployment read from a TOML file, see `LanguageModel::from_config`
+/// and `rsplitter --config`. Every key is optional and an unknown key is an error:
+///
+/// ```toml
+/// corpus_paths = ["domain_words.txt", "words.txt"]
+/// language = "English"
+/// oov_policy = { CharPenalty = 2.0 }
+/// pre_tokenizer = "Identifier"
+/// output = "JsonLines"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The corpus files merged like `LanguageModel::from_corpora`, the bundled corpus of
+    /// `language` when empty. A relative path is relative to the working directory.
+    pub corpus_paths: Vec<PathBuf>,
+    /// The bundled corpus used without `corpus_paths`, see `LanguageModel::language`
+    pub language: Language,
+    pub oov_policy: OovPolicy,
+    pub pre_tokenizer: PreTokenizerMode,
+    /// How the command line writes the splits, unused by the library
+    pub output: OutputFormat,
+}
+
+impl Config {
+    /// Parse the TOML text of a configuration
+    /// # Returns
+    /// An `InvalidInput` error naming the faulty key if the text is not a configuration
+    pub fn from_toml(text: &str) -> Result<Self, SplitError> {
+        toml::from_str(text).map_err(|err| SplitError::InvalidInput(err.message().to_string()))
+    }
+
+    /// Read a configuration file, see `from_toml`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SplitError> {
+        Config::from_toml(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// One of the pre-tokenizers of the crate, chosen by name in a `Config`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum PreTokenizerMode {
+    #[default]
+    Whitespace,
+    Alphabetic,
+    Identifier,
+    Hashtag,
+}
+
+impl PreTokenizer for PreTokenizerMode {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+        match self {
+            PreTokenizerMode::Whitespace => Whitespace.pre_tokenize(text),
+            PreTokenizerMode::Alphabetic => Alphabetic.pre_tokenize(text),
+            PreTokenizerMode::Identifier => Identifier.pre_tokenize(text),
+            PreTokenizerMode::Hashtag => Hashtag.pre_tokenize(text),
+        }
+    }
+}
+
+/// How `rsplitter` writes the splits
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum OutputFormat {
+    /// A line of text per input, like without `--json`
+    #[default]
+    Text,
+    /// A JSON object per input, like `--json`
+    Json,
+    /// A JSON object per line of a file or of the standard input, like `--json --lines`
+    JsonLines,
+}
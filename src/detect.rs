@@ -0,0 +1,100 @@
+use crate::language_model::Language;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// The corpus lines a profile is built from, the most frequent words of the language
+const PROFILE_WORDS: usize = 2000;
+
+/// The character trigrams of a language, by their log probability in its frequent words
+struct Profile {
+    language: Language,
+    trigrams: HashMap<[char; 3], f32>,
+    /// The log probability of a trigram the language does not have
+    unseen: f32,
+}
+
+impl Profile {
+    /// Count the trigrams of the words of a bundled corpus, every word weighing as much as
+    /// its Zipf frequency, `1 / rank`, and padded with a space on both sides
+    fn new(language: Language) -> Self {
+        let mut counts: HashMap<[char; 3], f32> = HashMap::new();
+        let mut total = 0.0;
+        let corpus = language.corpus().unwrap_or_default();
+        for (rank, word) in corpus.lines().take(PROFILE_WORDS).enumerate() {
+            let weight = 1.0 / (rank + 1) as f32;
+            for trigram in trigrams(&word.to_lowercase()) {
+                *counts.entry(trigram).or_insert(0.0) += weight;
+                total += weight;
+            }
+        }
+        let rarest = counts.values().copied().fold(f32::MAX, f32::min);
+        Profile {
+            language,
+            unseen: (rarest / total / 10.0).ln(),
+            trigrams: counts
+                .into_iter()
+                .map(|(trigram, count)| (trigram, (count / total).ln()))
+                .collect(),
+        }
+    }
+
+    /// The log likelihood of the trigrams of the text in the language
+    fn score(&self, text: &str) -> f32 {
+        text.split(|c: char| !c.is_alphabetic())
+            .flat_map(|run| trigrams(&run.to_lowercase()))
+            .map(|trigram| *self.trigrams.get(&trigram).unwrap_or(&self.unseen))
+            .sum()
+    }
+}
+
+/// The trigrams of a word padded with a space on both sides, none for an empty word
+fn trigrams(word: &str) -> Vec<[char; 3]> {
+    if word.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<char> = std::iter::once(' ')
+        .chain(word.chars())
+        .chain(std::iter::once(' '))
+        .collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// The profile of every bundled language, built on first use
+fn profiles() -> &'static [Profile] {
+    static PROFILES: OnceLock<Vec<Profile>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        Language::ALL
+            .iter()
+            .filter(|language| language.corpus().is_some())
+            .map(|language| Profile::new(*language))
+            .collect()
+    })
+}
+
+/// Guess the language of a text among the bundled ones, by the character trigrams of its
+/// letters against those of the frequent words of every language.
+/// Whitespace is not needed, the trigrams of an unspaced text are read across its words.
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The likeliest language, English for a text without letters or when no other language
+/// is bundled
+/// # Examples
+/// ```
+/// use rsplitter::{detect_language, Language};
+/// assert_eq!(detect_language("thequickbrownfox"), Language::English);
+/// ```
+pub fn detect_language(text: &str) -> Language {
+    profiles()
+        .iter()
+        .map(|profile| (profile.language, profile.score(text)))
+        // The first of the best, English on a tie
+        .fold(
+            None,
+            |best: Option<(Language, f32)>, (language, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((language, score)),
+            },
+        )
+        .map_or(Language::English, |(language, _)| language)
+}
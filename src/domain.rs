@@ -0,0 +1,147 @@
+use std::net::{IpAddr, Ipv6Addr};
+
+/// The second level labels under which a country code top level domain registers names,
+/// e.g. "co" in "bbc.co.uk"
+const SECOND_LEVEL_LABELS: [&str; 9] =
+    ["ac", "co", "com", "edu", "gov", "ltd", "net", "org", "plc"];
+
+/// The words of a domain name, see `LanguageModel::split_domain`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomainSplit {
+    /// The host, lowercase and with its punycode labels decoded, e.g. "www.choosespain.com"
+    pub host: String,
+    /// The words of every subdomain before the name, e.g. `[["www"]]`
+    pub subdomains: Vec<Vec<String>>,
+    /// The words of the name, the label before the suffix, e.g. `["choose", "spain"]`
+    pub words: Vec<String>,
+    /// The public suffix, e.g. "com" or "co.uk", empty for a host of a single label or an
+    /// IP address
+    pub suffix: String,
+}
+
+impl DomainSplit {
+    /// The words of the name joined with spaces, e.g. "choose spain"
+    pub fn name(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+/// The host of a URL or of a host name, without the scheme, the user, the port and the path.
+/// An IPv6 address keeps its brackets, e.g. "[::1]" for "http://[::1]:80/".
+pub(crate) fn host_of(url: &str) -> &str {
+    let url = url.trim();
+    if url.parse::<Ipv6Addr>().is_ok() {
+        return url;
+    }
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.starts_with('[') {
+        return host.find(']').map_or(host, |end| &host[..=end]);
+    }
+    host.split(':')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.')
+}
+
+/// Whether a host is an IP address, e.g. "192.168.0.1", "[::1]" or "::1", which has no name
+/// to split
+pub(crate) fn is_ip_literal(host: &str) -> bool {
+    let address = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    address.parse::<IpAddr>().is_ok()
+}
+
+/// How many labels at the end of a host are its public suffix: two for a second level label
+/// under a country code, e.g. "co.uk", one otherwise, none for a single label
+pub(crate) fn suffix_length(labels: &[String]) -> usize {
+    match labels {
+        [] | [_] => 0,
+        [.., _, second, country]
+            if labels.len() > 2
+                && country.len() == 2
+                && SECOND_LEVEL_LABELS.contains(&second.as_str()) =>
+        {
+            2
+        }
+        _ => 1,
+    }
+}
+
+/// A label lowercase and decoded when it is punycode, e.g. "münchen" for "xn--mnchen-3ya".
+/// A label that does not decode to a name, with a character beyond ASCII and no control
+/// character, is kept as it is.
+pub(crate) fn decode_label(label: &str) -> String {
+    let label = label.to_lowercase();
+    label
+        .strip_prefix("xn--")
+        .and_then(punycode_decode)
+        .filter(|name| !name.is_ascii() && !name.chars().any(char::is_control))
+        .unwrap_or(label)
+}
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Decode the punycode of a label without its "xn--" prefix, see RFC 3492
+fn punycode_decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(delimiter) => (&input[..delimiter], &input[delimiter + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut digits = extended.bytes().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                b @ b'a'..=b'z' => b - b'a',
+                b @ b'0'..=b'9' => b - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = k.saturating_sub(bias).clamp(T_MIN, T_MAX);
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(BASE - threshold)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        bias = adapt(i - old_i, length, old_i == 0);
+        n = n.checked_add(i / length)?;
+        i %= length;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+/// The bias of the next code point of `punycode_decode`
+fn adapt(delta: u32, length: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / length;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
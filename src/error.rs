@@ -0,0 +1,52 @@
+use std::fmt;
+
+/// Errors returned by the fallible parts of the API.
+#[derive(Debug)]
+pub enum SplitError {
+    /// Reading or writing a corpus or a model failed.
+    Io(std::io::Error),
+    /// The bytes are not a model written by `LanguageModel::to_writer`.
+    InvalidModel(String),
+    /// The corpus has no word, e.g. an empty file or only blank lines.
+    EmptyCorpus,
+    /// The text is longer than `LanguageModel::max_input_len`, both in bytes.
+    InputTooLong { length: usize, limit: usize },
+    /// A setting or an argument has a value the model cannot use, e.g. a NaN weight.
+    InvalidInput(String),
+    /// No corpus was given and the crate is built without the `bundled-corpus` feature.
+    NoCorpus,
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::Io(err) => write!(f, "io error: {}", err),
+            SplitError::InvalidModel(reason) => write!(f, "invalid model: {}", reason),
+            SplitError::EmptyCorpus => write!(f, "the corpus has no word"),
+            SplitError::InputTooLong { length, limit } => {
+                write!(f, "the text has {} bytes, more than {}", length, limit)
+            }
+            SplitError::InvalidInput(reason) => write!(f, "invalid input: {}", reason),
+            SplitError::NoCorpus => write!(f, "no corpus and no bundled corpus"),
+        }
+    }
+}
+
+impl std::error::Error for SplitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SplitError::Io(err) => Some(err),
+            SplitError::InvalidModel(_)
+            | SplitError::EmptyCorpus
+            | SplitError::InputTooLong { .. }
+            | SplitError::InvalidInput(_)
+            | SplitError::NoCorpus => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SplitError {
+    fn from(err: std::io::Error) -> Self {
+        SplitError::Io(err)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::error::SplitError;
+use crate::language_model::CostDict;
+use std::io::Write;
+
+/// How `LanguageModel::export` writes the cost dictionary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExportFormat {
+    /// A `word,cost` header then a line per word, a word with a comma, a quote or a line
+    /// break quoted
+    Csv,
+    /// An object with the `stats` of `DictionaryStats` and the `costs` by word
+    Json,
+}
+
+/// The size of the cost dictionary and how its costs are spread, see
+/// `LanguageModel::dictionary_stats`. The costs are 0 for an empty dictionary.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DictionaryStats {
+    /// The words of the dictionary, the casings of `case_sensitive` included
+    pub words: usize,
+    /// The length of the longest word, in characters
+    pub max_word_len: usize,
+    /// The cost of the cheapest word
+    pub min_cost: f32,
+    /// The cost half of the words are cheaper than
+    pub median_cost: f32,
+    pub mean_cost: f32,
+    /// The cost of the dearest word
+    pub max_cost: f32,
+}
+
+/// The words of a dictionary with their costs, the cheapest first and words of equal cost
+/// sorted, so an export is reproducible
+pub(crate) fn sorted_costs<S>(cost_dict: &CostDict<S>) -> Vec<(&str, f32)> {
+    let mut costs: Vec<(&str, f32)> = cost_dict
+        .0
+        .iter()
+        .map(|(word, cost)| (word.as_str(), *cost))
+        .collect();
+    costs.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+    costs
+}
+
+/// The stats of a dictionary from its `sorted_costs`
+pub(crate) fn dictionary_stats(costs: &[(&str, f32)], max_word: i32) -> DictionaryStats {
+    let Some(&(_, min_cost)) = costs.first() else {
+        return DictionaryStats::default();
+    };
+    let sum: f64 = costs.iter().map(|(_, cost)| *cost as f64).sum();
+    DictionaryStats {
+        words: costs.len(),
+        max_word_len: max_word.max(0) as usize,
+        min_cost,
+        median_cost: costs[costs.len() / 2].1,
+        mean_cost: (sum / costs.len() as f64) as f32,
+        max_cost: costs[costs.len() - 1].1,
+    }
+}
+
+/// Write the sorted costs of a dictionary, and its stats in JSON
+pub(crate) fn write_costs<W: Write>(
+    mut writer: W,
+    format: ExportFormat,
+    costs: &[(&str, f32)],
+    stats: &DictionaryStats,
+) -> Result<(), SplitError> {
+    match format {
+        ExportFormat::Csv => {
+            writeln!(writer, "word,cost")?;
+            for (word, cost) in costs {
+                writeln!(writer, "{},{}", csv_field(word), cost)?;
+            }
+        }
+        ExportFormat::Json => {
+            write!(
+                writer,
+                "{{\"stats\":{{\"words\":{},\"max_word_len\":{},\"min_cost\":{},\
+                 \"median_cost\":{},\"mean_cost\":{},\"max_cost\":{}}},\"costs\":{{",
+                stats.words,
+                stats.max_word_len,
+                json_number(stats.min_cost),
+                json_number(stats.median_cost),
+                json_number(stats.mean_cost),
+                json_number(stats.max_cost)
+            )?;
+            for (idx, (word, cost)) in costs.iter().enumerate() {
+                let comma = if idx == 0 { "" } else { "," };
+                write!(
+                    writer,
+                    "{}{}:{}",
+                    comma,
+                    json_string(word),
+                    json_number(*cost)
+                )?;
+            }
+            writeln!(writer, "}}}}")?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// A CSV field of a word, quoted if needed
+fn csv_field(word: &str) -> String {
+    if word.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", word.replace('"', "\"\""))
+    } else {
+        word.to_string()
+    }
+}
+
+/// A JSON number of a cost, `null` for an infinite or NaN one, which JSON has no number for
+fn json_number(cost: f32) -> String {
+    if cost.is_finite() {
+        cost.to_string()
+    } else {
+        String::from("null")
+    }
+}
+
+/// A JSON string literal of a word
+fn json_string(word: &str) -> String {
+    let mut result = String::with_capacity(word.len() + 2);
+    result.push('"');
+    for c in word.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
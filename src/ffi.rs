@@ -0,0 +1,100 @@
+// C bindings, the declarations are in `include/rsplitter.h`.
+// Every string crossing the boundary is NUL-terminated UTF-8; a null pointer or invalid
+// UTF-8 is answered with a null pointer instead of undefined behavior.
+use crate::LanguageModel;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+/// Borrow a C string as UTF-8, `None` for a null pointer or invalid UTF-8
+unsafe fn to_str<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// Hand a Rust string over to C, it is released with `rsplitter_free`
+fn into_c_string(text: String) -> *mut c_char {
+    // The split text only holds characters of the input, which has no NUL
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Split a text with the bundled corpus, in builds with the `bundled-corpus` feature only.
+/// Returns a string to release with `rsplitter_free`, or null if `text` is null or not UTF-8.
+/// # Safety
+/// `text` is null or a valid NUL-terminated string.
+#[cfg(feature = "bundled-corpus")]
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_split(text: *const c_char) -> *mut c_char {
+    match to_str(text) {
+        Some(text) => into_c_string(crate::split(String::from(text))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Release a string returned by this library, null is ignored.
+/// # Safety
+/// `text` is null or was returned by this library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Create a language model from a corpus file, an empty path selects the bundled corpus.
+/// Returns a model to release with `rsplitter_model_free`, or null if the path is null or
+/// not UTF-8, or the corpus cannot be read or has no word.
+/// # Safety
+/// `corpus_path` is null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_model_new(corpus_path: *const c_char) -> *mut LanguageModel {
+    to_str(corpus_path)
+        .and_then(|path| LanguageModel::from_corpus(path.to_string()).ok())
+        .map_or(ptr::null_mut(), |model| Box::into_raw(Box::new(model)))
+}
+
+/// Create a language model from a corpus in memory, e.g. embedded in the calling program,
+/// one word per line like a corpus file; a model is shared between threads only with a lock.
+/// Returns a model to release with `rsplitter_model_free`, or null if `bytes` is null, or the
+/// corpus is not UTF-8 or has no word.
+/// # Safety
+/// `bytes` is null or points to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_model_from_bytes(
+    bytes: *const u8,
+    length: usize,
+) -> *mut LanguageModel {
+    if bytes.is_null() {
+        return ptr::null_mut();
+    }
+    LanguageModel::from_bytes(std::slice::from_raw_parts(bytes, length))
+        .map_or(ptr::null_mut(), |model| Box::into_raw(Box::new(model)))
+}
+
+/// Split a text with a model.
+/// Returns a string to release with `rsplitter_free`, or null if an argument is null or
+/// the text is not UTF-8.
+/// # Safety
+/// `model` is null or was returned by `rsplitter_model_new` and not released yet,
+/// `text` is null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_model_split(
+    model: *mut LanguageModel,
+    text: *const c_char,
+) -> *mut c_char {
+    match (model.as_mut(), to_str(text)) {
+        (Some(model), Some(text)) => into_c_string(model.split(String::from(text))),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Release a model, null is ignored.
+/// # Safety
+/// `model` is null or was returned by `rsplitter_model_new` and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rsplitter_model_free(model: *mut LanguageModel) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
@@ -0,0 +1,63 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// The multiplier of the Fx hash, as used by rustc
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The Fx hash from rustc: a few instructions per word, no protection against HashDoS.
+/// The dictionary keys come from the corpus rather than from an adversary, and the dynamic
+/// program hashes millions of short candidate words, so speed wins here.
+#[derive(Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Builds `FxHasher`s, see `DictHasher`
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+/// The hasher of the cost dictionary: `FxBuildHasher` with the default `fxhash` feature,
+/// the standard SipHash `RandomState` without it.
+#[cfg(feature = "fxhash")]
+pub type DictHasher = FxBuildHasher;
+#[cfg(not(feature = "fxhash"))]
+pub type DictHasher = std::collections::hash_map::RandomState;
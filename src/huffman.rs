@@ -0,0 +1,48 @@
+//! The decoder of the bundled corpus, encoded by the build script with a Huffman code over
+//! its bytes: a corpus of lowercase words has few distinct bytes, so this alone halves it.
+//!
+//! The encoded corpus is, in order:
+//! * the length of the code of every byte value, 256 bytes, 0 for a byte that never occurs
+//! * the length of the corpus, 4 bytes little-endian
+//! * the codes of the bytes of the corpus, most significant bit first. The codes are
+//!   canonical: shorter codes first, then by byte value, so their lengths are enough to
+//!   rebuild them.
+
+/// The 256 code lengths then the length of the corpus
+const HEADER: usize = 260;
+
+/// The bytes encoded by the build script, `None` if the data is not a valid encoding
+pub(crate) fn decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    let lengths = encoded.get(..256)?;
+    let size = u32::from_le_bytes(encoded.get(256..HEADER)?.try_into().ok()?) as usize;
+    let max_length = *lengths.iter().max()? as usize;
+    // The number of codes of every length, and the byte values ordered like their codes
+    let mut counts = vec![0usize; max_length + 1];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    let mut symbols: Vec<u8> = (0..=255u8).filter(|&b| lengths[b as usize] > 0).collect();
+    symbols.sort_by_key(|&b| lengths[b as usize]);
+    let mut bits = encoded[HEADER..]
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1));
+    let mut decoded = Vec::with_capacity(size);
+    while decoded.len() < size {
+        // The code read so far, the first code of its length and the index of that code
+        let (mut code, mut first, mut index) = (0usize, 0usize, 0usize);
+        let mut length = 1;
+        loop {
+            code |= bits.next()? as usize;
+            let count = *counts.get(length)?;
+            if code - first < count {
+                decoded.push(symbols[index + code - first]);
+                break;
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+            length += 1;
+        }
+    }
+    Some(decoded)
+}
@@ -0,0 +1,28 @@
+// The `tracing` instrumentation of the pipeline, compiled away without the `tracing` feature.
+// The spans are at the debug level, the events of every position at the trace level.
+
+/// Enter a debug span until the end of the current scope
+macro_rules! enter_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+/// Emit a debug event
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+/// Emit a trace event
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+pub(crate) use {debug_event, enter_span, trace_event};
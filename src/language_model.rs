@@ -1,158 +1,4981 @@
+use crate::builder::LanguageModelBuilder;
+use crate::cache::{cached_dict, CacheStats, ResultCache};
+#[cfg(feature = "config")]
+use crate::config::Config;
+use crate::domain::{decode_label, host_of, is_ip_literal, suffix_length, DomainSplit};
+use crate::error::SplitError;
+use crate::export::{dictionary_stats, sorted_costs, write_costs, DictionaryStats, ExportFormat};
+use crate::hasher::DictHasher;
+#[cfg(feature = "rayon")]
+use crate::hasher::FxBuildHasher;
+use crate::instrument::{debug_event, enter_span, trace_event};
+#[cfg(feature = "mmap")]
+use crate::mapped::{write_mapped, MappedDictionary};
+use crate::pretokenize::{social_pieces, strip_sigil, PreToken, PreTokenizer};
+use crate::sentences::{group_sentences, SentenceRules};
+use crate::serialize::{read_dict, write_dict};
+use crate::shared::SharedModel;
+use crate::spelling::{nearest_word, DeletionIndex};
+use crate::stream::{SplitIter, SplitStream};
+use crate::trie::Trie;
+use crate::words::unicode_words;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     fs::File,
-    io::{BufRead, BufReader},
+    hash::{BuildHasher, DefaultHasher, Hash, Hasher},
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The cost of every word in the corpus and the length of the longest word.
+/// The words are hashed with `S`, `DictHasher` unless a model is built `with_hasher`.
+pub type CostDict<S = DictHasher> = (HashMap<String, f32, S>, i32);
+
+/// The shortest substring `LanguageModel::fuzzy_cost` reads as a word one edit away,
+/// shorter ones being one edit away from too many words.
+pub const FUZZY_MIN_LENGTH: usize = 4;
+
+/// Common units of measure, of data and of time, lowercase, for `LanguageModel::units`
+pub const UNITS: &[&str] = &[
+    "mg", "g", "kg", "t", "oz", "lb", "lbs", "mm", "cm", "m", "km", "in", "ft", "yd", "mi", "ml",
+    "cl", "dl", "l", "gal", "qt", "pt", "tsp", "tbsp", "ms", "s", "sec", "min", "mins", "h", "hr",
+    "hrs", "am", "pm", "mph", "kmh", "kph", "kb", "mb", "gb", "tb", "kbps", "mbps", "gbps", "mah",
+    "wh", "kwh", "w", "kw", "mw", "v", "mv", "ma", "hz", "khz", "mhz", "ghz", "db", "px", "°c",
+    "°f", "°", "%",
+];
+
+/// The cost of every character of an unknown word under `OovPolicy::KeepWhole`
+const KEEP_WHOLE_CHAR_COST: f32 = 2.0;
+
+/// Rewrites a run of text before it is split, see `LanguageModel::normalize`.
+pub type Normalizer = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The weight of the words starting at a character position, see
+/// `LanguageModel::position_weight`.
+pub type PositionWeight = Box<dyn Fn(usize) -> f32 + Send + Sync>;
+
+/// Scores the candidate words of the dynamic program, see `LanguageModel::cost_function`.
+/// The cost dictionary of the corpus is the default scoring: the Zipf or frequency cost of
+/// a word, `f32::MAX` for a word not in it.
+pub trait WordCost: Send + Sync {
+    /// The cost of a lowercased word, `f32::MAX` when it is not a word
+    fn cost(&self, word: &str) -> f32;
+
+    /// The number of words scored, for the cost of the unknown ones, `None` to count the
+    /// words of `cost_dict` instead
+    fn vocab_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<S: BuildHasher + Send + Sync> WordCost for HashMap<String, f32, S> {
+    fn cost(&self, word: &str) -> f32 {
+        self.get(word).map_or(f32::MAX, |x| *x)
+    }
+}
+
+/// How the entries of a corpus are turned into word costs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CostModel {
+    /// One word per line, ordered by frequency; the cost follows Zipf's law over the rank.
+    /// A line with whitespace inside it is a phrase, e.g. "new york": it takes a rank like a
+    /// word, matches the text without its whitespace and is written with it, "new york" for
+    /// "newyork". Its length, for the longest word of the dictionary, is its length without
+    /// whitespace.
+    #[default]
+    ZipfRank,
+    /// One `word count` pair per line; the cost is `ln(total / count)`,
+    /// so the actual frequencies are kept instead of being flattened into ranks.
+    /// The word may be a phrase, e.g. `new york 120`, see `ZipfRank`. A tag after the count
+    /// is ignored, so the `word count tag` lines of a jieba dictionary load as they are.
+    Frequency,
+}
+
+/// How the casing of the split output is rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseMode {
+    /// Keep the casing of the input.
+    #[default]
+    Original,
+    /// Uppercase the first letter of the first word, e.g. "The cat sat".
+    /// Nothing changes when the first word has no letter, e.g. "42 cats".
+    SentenceCase,
+    /// Write the words found in the corpus with another casing as the corpus does,
+    /// e.g. "visit London" for "visitlondon" when the corpus has "London".
+    /// A word keeps its first casing in the corpus; a compiled model has no casing.
+    Corpus,
+    /// Lowercase every word, e.g. "the cat sat" for "TheCatSat", like `split_lower`.
+    Lowercase,
+    /// Uppercase the first letter of every word, e.g. "The Quick Brown Fox", the rest of
+    /// the word as it is.
+    TitleCase,
+}
+
+/// The segmentation algorithm used by `LanguageModel::split_with_algorithm`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Algorithm {
+    /// The cheapest split with the word costs of the dictionary, like `split`.
+    #[default]
+    OptimalDp,
+    /// The longest dictionary word first, like `split_greedy`.
+    Greedy,
+    /// The cheapest split with the word pairs of `load_bigrams`, like `split_bigram`.
+    Bigram,
+}
+
+/// A span of unknown text replaced by a dictionary word when spelling correction is enabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Correction {
+    pub original: String,
+    pub corrected: String,
+}
+
+/// Where the dictionary lookups of the dynamic program are answered from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DictionaryBackend {
+    /// One hash lookup per candidate substring, without building anything more than
+    /// `cost_dict`: the model loads faster and takes less memory, but splits slower.
+    #[default]
+    HashMap,
+    /// One walk of a trie per position, stopping as soon as no dictionary word can match,
+    /// with no substring hashed. The trie is built from `cost_dict` when the model is
+    /// loaded, which takes longer than the dictionary itself; it pays off with many or long
+    /// texts to split.
+    Trie,
+}
+
+/// The language of a bundled corpus, see `LanguageModel::for_language`.
+/// English is always bundled, the other languages with their feature: `lang-fr`, `lang-de`
+/// and `lang-es`. Their corpora are short lists of the most frequent words; a full word list
+/// of the language, given as `corpus_path`, splits rarer words too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Language {
+    #[default]
+    English,
+    #[cfg(feature = "lang-fr")]
+    French,
+    #[cfg(feature = "lang-de")]
+    German,
+    #[cfg(feature = "lang-es")]
+    Spanish,
+}
+
+impl Language {
+    /// Every bundled language, English first
+    pub const ALL: &'static [Language] = &[
+        Language::English,
+        #[cfg(feature = "lang-fr")]
+        Language::French,
+        #[cfg(feature = "lang-de")]
+        Language::German,
+        #[cfg(feature = "lang-es")]
+        Language::Spanish,
+    ];
+
+    /// The bundled corpus of the language, one word per line from the most frequent,
+    /// `None` for English without the `bundled-corpus` feature
+    pub(crate) fn corpus(self) -> Option<&'static str> {
+        match self {
+            #[cfg(feature = "bundled-corpus")]
+            Language::English => Some(crate::default_corpus()),
+            #[cfg(not(feature = "bundled-corpus"))]
+            Language::English => None,
+            #[cfg(feature = "lang-fr")]
+            Language::French => Some(include_str!("corpus_fr.txt")),
+            #[cfg(feature = "lang-de")]
+            Language::German => Some(include_str!("corpus_de.txt")),
+            #[cfg(feature = "lang-es")]
+            Language::Spanish => Some(include_str!("corpus_es.txt")),
+        }
+    }
+}
+
+/// How the dynamic program scores a candidate word that is not in the dictionary, see
+/// `LanguageModel::oov_policy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OovPolicy {
+    /// An unknown word costs a little more than the rarest dictionary word for each of its
+    /// characters, so it is only chosen where no dictionary word fits and an unknown word
+    /// tends to be split into the short words it contains, e.g. "xy log raf machine" for
+    /// "xylografmachine". Where none fits, e.g. a symbol, a letter missing from the corpus
+    /// like "é" in "cafénoir" or a Chinese character missing from the dictionary, the
+    /// unknown characters are kept together and the words after them, "noir", are still
+    /// compared by their costs.
+    #[default]
+    Split,
+    /// An unknown word costs a little more than the rarest dictionary word, once, plus the
+    /// given cost for each of its characters: the lower the cost, the longer the unknown
+    /// words kept whole.
+    CharPenalty(f32),
+    /// `CharPenalty` with a cost per character low enough that an unknown word is kept as
+    /// one word, e.g. "xylograf machine", but not so low that it swallows the dictionary
+    /// words around it.
+    KeepWhole,
+}
+
+/// A corpus merged by `LanguageModel::with_corpora`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CorpusSource<'a> {
+    /// The bundled corpus of a language
+    Bundled(Language),
+    /// A corpus file, read like the corpus of `new`
+    File(PathBuf),
+    /// Words ranked like the lines of a corpus, the most frequent first
+    Words(&'a [&'a str]),
+}
+
+/// Which candidate the dynamic program keeps when several have the same cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreak {
+    /// Keep the shorter last word.
+    #[default]
+    ShorterWord,
+    /// Keep the longer last word, favoring fewer and longer words.
+    LongerWord,
+}
+
+/// A part of a split text, see `LanguageModel::split_tokens`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Token {
+    /// A word of the text
+    Word(String),
+    /// What is written between two words: `LanguageModel::separator` or the whitespace of
+    /// the text
+    Separator(String),
+}
+
+impl Token {
+    /// The text of the token
+    pub fn as_str(&self) -> &str {
+        match self {
+            Token::Word(text) | Token::Separator(text) => text,
+        }
+    }
+}
+
+/// A word the dynamic program considered, see `LanguageModel::explain`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candidate {
+    pub word: String,
+    /// The cost of the word alone, `f32::MAX` when it is not in the dictionary
+    pub word_cost: f32,
+    /// The cost of the best split ending with this word
+    pub total_cost: f32,
+}
+
+/// The candidate words ending at a character position, see `LanguageModel::explain`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionExplanation {
+    /// The position, in characters, right after the candidate words
+    pub position: usize,
+    /// The candidates from the shortest to the longest
+    pub candidates: Vec<Candidate>,
+    /// The index of the chosen candidate
+    pub winner: usize,
+}
+
+/// A split with the lattice it was chosen from, see `LanguageModel::debug_split`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugSplit {
+    /// The split text, like `LanguageModel::split`
+    pub split: String,
+    /// The candidate words at every non-whitespace character position, in order
+    pub lattice: Vec<PositionExplanation>,
+}
+
+/// How well a model recovers the words of spaced reference texts, see
+/// `LanguageModel::evaluate`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalReport {
+    /// The share of the predicted word boundaries that are in the references
+    pub precision: f64,
+    /// The share of the reference word boundaries that were predicted
+    pub recall: f64,
+    /// The harmonic mean of the precision and the recall
+    pub f1: f64,
+    /// The share of the texts split exactly like their reference
+    pub exact_match: f64,
+}
+
+/// How a split went, see `LanguageModel::split_with_stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitStats {
+    /// The wall time of the split
+    pub elapsed: Duration,
+    /// The character positions the dynamic program chose a best word for
+    pub positions: usize,
+    /// The candidate words looked up in the dictionary
+    pub lookups: usize,
+    /// The lookups that found a dictionary word
+    pub hits: usize,
+}
+
+impl SplitStats {
+    /// The share of the lookups that found a dictionary word, 0 without lookups
+    pub fn hit_ratio(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.lookups as f64
+    }
+}
+
+/// How the cost dictionary was last built from a corpus, see `LanguageModel::load_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoadStats {
+    /// The wall time of building the dictionary from the corpus lines, once they are read
+    pub elapsed: Duration,
+    /// The cleaned corpus lines, see `max_rank` and `min_frequency`
+    pub lines: usize,
+    /// The words of the dictionary built
+    pub words: usize,
+    /// The length of the longest word, in characters
+    pub max_word_len: usize,
+}
+
+/// The words of a split with their costs, see `LanguageModel::split_scored`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitResult {
+    /// The words of the split, in order
+    pub words: Vec<String>,
+    /// The sum of `word_costs`, the `tokenization_cost` of the words
+    pub total_cost: f32,
+    /// The cost of every word with the length penalty, an unknown word costing a little
+    /// more than the rarest dictionary word for each of its characters
+    pub word_costs: Vec<f32>,
+    /// How many of the words are neither dictionary words nor numbers
+    pub unknown_words: usize,
+}
+
+impl SplitResult {
+    /// The cost per word, 0 without words: the higher it is, the less likely the split is
+    /// right, e.g. to reject a text made of fragments not in the dictionary
+    pub fn mean_cost(&self) -> f32 {
+        if self.words.is_empty() {
+            return 0.0;
+        }
+        self.total_cost / self.words.len() as f32
+    }
+}
+
+/// Bounds on the work of one split, see `LanguageModel::split_limited`, e.g. to bound the
+/// latency of adversarial inputs. A limit left out keeps the one of the model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitLimits {
+    /// The longest word considered, in characters, see `LanguageModel::max_word_len`
+    pub max_word_len: Option<usize>,
+    /// Split with a beam search keeping this many hypotheses, see
+    /// `LanguageModel::beam_width`; a beam of 0 is one of 1
+    pub beam_width: Option<usize>,
+}
+
+/// The costs of the dynamic program over a text prefix, see `LanguageModel::prefix_costs`.
+#[derive(Clone, Debug)]
+pub struct PrefixCosts {
+    pub(crate) prefix: String,
+    /// The numbers and acronyms of the prefix
+    spans: Vec<(usize, usize, f32)>,
+    /// The dynamic program over the prefix, empty when it cannot be reused
+    pub(crate) lattice: Lattice,
+}
+
+impl PrefixCosts {
+    /// Whether the costs hold for a run: it starts with the prefix and no number or acronym
+    /// crosses the end of the prefix
+    fn applies_to(&self, run: &Run) -> bool {
+        let length = self.lattice.cost.len().saturating_sub(1);
+        !self.lattice.cost.is_empty()
+            && run.text.starts_with(&self.prefix)
+            && run
+                .spans
+                .iter()
+                .filter(|(start, _, _)| *start < length)
+                .eq(self.spans.iter())
+    }
+}
+
+/// What a split records besides its output
+pub(crate) struct Trace {
+    pub(crate) corrections: Vec<Correction>,
+    /// The accumulated cost of every prefix of the text split so far
+    pub(crate) cost: Vec<f32>,
+    /// The characters of the split words that are neither dictionary words nor numbers
+    pub(crate) unknown_chars: usize,
+}
+
+impl Trace {
+    pub(crate) fn new() -> Self {
+        Trace {
+            corrections: Vec::new(),
+            cost: vec![0.0],
+            unknown_chars: 0,
+        }
+    }
+
+    fn accumulated_cost(&self) -> f32 {
+        self.cost[self.cost.len() - 1]
+    }
+}
 
 /// Use custom corpus file if provided, otherwise use default corpus file.
-pub struct LanguageModel {
+///
+/// The dictionary is case-insensitive and the output keeps the casing of the input:
+/// the keys are the lowercased corpus words, see `dictionary_key`, and the first casing of a
+/// word with an uppercase letter is kept apart in `casings`. Every run of the text is
+/// lowercased once for all its lookups, while the words are sliced from the run as written;
+/// `case_mode` then decides whether the corpus casing replaces the input one.
+///
+/// A split is deterministic: the same model and text always give the same split, whatever
+/// the hasher and its seed. No result depends on the iteration order of a `HashMap`, the
+/// costs are compared with `total_cmp`, and candidates of the same cost are decided by
+/// `tie_break`, then by their order in the text.
+///
+/// `split` takes `&mut self`: it loads the dictionary on first use, see `new`, and reads
+/// and fills the result cache of `cache_capacity`. To split from several threads without a
+/// lock, load the model and share it with `into_shared`: `SharedModel::split` takes `&self`
+/// and does not use the result cache.
+pub struct LanguageModel<S = DictHasher> {
     pub corpus_path: String,
-    pub cost_dict: Option<(HashMap<String, f32>, i32)>,
+    /// Whose bundled corpus is used when `corpus_path` is empty, English by default
+    pub language: Language,
+    /// Shared by the models built with `from_corpus_cached` from the same corpus,
+    /// changing the dictionary of one model copies it first
+    pub cost_dict: Option<Arc<CostDict<S>>>,
+    pub cost_model: CostModel,
+    pub case_mode: CaseMode,
+    /// Replace unknown spans by the cheapest dictionary word one edit away.
+    /// Off by default, every unknown span is compared against the whole dictionary.
+    pub spelling_correction: bool,
+    pub backend: DictionaryBackend,
+    /// Use a beam search keeping this many hypotheses instead of the exact dynamic program.
+    /// A small beam is faster on long inputs but may miss the cheapest split, a beam of 1
+    /// greedily commits to the cheapest next word; a beam of 0 is one of 1.
+    pub beam_width: Option<usize>,
+    /// Corpus lines starting with this character, once trimmed, are comments and skipped.
+    /// `#` by default, `None` keeps every line.
+    pub comment_prefix: Option<char>,
+    pub tie_break: TieBreak,
+    /// Added to the cost of every word of a split, a positive weight favors fewer and longer
+    /// words and a negative one more and shorter words. 0 by default.
+    pub length_penalty: f32,
+    /// The characters allowed between the digits of a number, which is kept as one word,
+    /// e.g. "1,234.56". `,` and `.` by default, which also covers "1.234,56".
+    pub number_separators: Vec<char>,
+    /// The symbols kept with the number right after them, e.g. "$99". `$`, `€`, `£` and `¥`
+    /// by default.
+    pub currency_symbols: Vec<char>,
+    /// Keep a run of at least this many uppercase letters as one word, e.g. "UK" in
+    /// "visittheUKsoon", even when it is not in the dictionary. It costs as much as the
+    /// rarest dictionary word, so dictionary words still win when they explain the run
+    /// better. `None` by default.
+    pub acronym_min_length: Option<usize>,
+    /// The units kept as a word of their own right after a number, lowercase, e.g. "kg" in
+    /// "5kgofrice" for "5 kg of rice", even when they are not in the dictionary: after a
+    /// number, the longest unit the text goes on with costs half as much as the rarest
+    /// dictionary word, so that the dictionary words taking its letters may still win, e.g.
+    /// "1 gallon". Empty by default, see `UNITS` for a list of common ones.
+    pub units: Vec<String>,
+    /// Keep the casings of the corpus words apart: every word of the corpus is also a word
+    /// as written, at the cost of its own rank or count, e.g. "US" and "us", and a candidate
+    /// with uppercase letters costs as its casing in the corpus, or as the lowercase word
+    /// when the corpus does not have that casing. Off by default, every casing of a word
+    /// then costs as its most frequent one. Set before the dictionary is loaded.
+    pub case_sensitive: bool,
+    /// `is_valid_segmentation` accepts unknown segments shorter than this many characters.
+    /// 2 by default, so only single characters may be unknown.
+    pub unknown_threshold: usize,
+    /// The longest text the splitting methods take, in bytes, e.g. for untrusted input:
+    /// `split` and the other methods returning a text return a longer text unchanged, the
+    /// methods returning words return it as a single word, `split_strict` returns `None`,
+    /// `explain` an empty lattice and `try_split` rejects it with
+    /// `SplitError::InputTooLong`. `None`, unlimited, by default.
+    pub max_input_len: Option<usize>,
+    /// Write every run of whitespace of the output as a single space, e.g. "foo bar baz"
+    /// for "foo  barbaz". Off by default, the whitespace of the input is kept as it is.
+    pub collapse_whitespace: bool,
+    /// Written between the words a split inserts, a space by default. The whitespace of the
+    /// text is kept as it is, so e.g. `"|"` tells the inserted boundaries from the original
+    /// ones: "rust|is|great now" for "rustisgreat now".
+    pub separator: String,
+    /// Drop a single character that is not in the dictionary at this cost instead of
+    /// writing it, e.g. "hello world" for the OCR noise of "hello#world". Numbers and
+    /// acronyms are never dropped, and the cheaper the cost the more readily a character
+    /// is dropped rather than read as part of a word. `None`, keeping every character,
+    /// by default; `split_greedy` and `split_bigram` always keep them.
+    pub deletion_cost: Option<f32>,
+    /// Applied to every run of the text between whitespace before it is split, e.g.
+    /// `rsplitter::strip_accents`. When the words of the normalized run have as many
+    /// characters as the run, they are written as they are in the input; otherwise, e.g.
+    /// "oe" for "ö", the normalized words are written. `None`, splitting the text as it
+    /// is, by default.
+    pub normalize: Option<Normalizer>,
+    /// The number of lines the corpus is expected to have, to size the buffers of the
+    /// corpus and of the dictionary once instead of growing them while it is read.
+    /// `None` by default, the dictionary is then sized from the lines read.
+    pub capacity_hint: Option<usize>,
+    /// Let the dynamic program read a substring of at least `FUZZY_MIN_LENGTH` characters
+    /// that is not in the dictionary as a dictionary word one edit away, at the cost of that
+    /// word plus this premium, e.g. "the quick bronw fox" for "thequickbronwfox". An edit
+    /// is an insertion, a deletion, a substitution or a swap of adjacent characters.
+    /// The substring is written as it is, unlike `spelling_correction`. `None` by default,
+    /// as the index of the words one edit away from the dictionary takes several times the
+    /// memory of the dictionary and every candidate substring is looked up in it.
+    pub fuzzy_cost: Option<f32>,
+    /// Keep the splits of up to this many texts, so that `split` answers a repeated text
+    /// without splitting it again, e.g. the same glued strings of millions of log lines; a
+    /// full cache drops the text used least recently. The cache is emptied when the
+    /// dictionary or a setting changes, a public field included, see `clear_cache`. A
+    /// `SharedModel` splits without it. `None`, caching nothing, by default.
+    pub cache_capacity: Option<usize>,
+    /// Keep a run of the text between whitespace as it is when it costs no more than its
+    /// split, both scored with `tokenization_cost`, so that text already spaced right is not
+    /// split again. Off by default.
+    pub keep_cheaper_input: bool,
+    /// Merge consecutive unknown words shorter than this many characters into one, e.g.
+    /// "xqzwk" for "x q z w k", instead of writing the fragments of an unknown word apart.
+    /// Numbers are known words here. `None` (the default) keeps the fragments.
+    pub merge_unknown_shorter_than: Option<usize>,
+    /// How the dynamic program scores a candidate word that is not in the dictionary,
+    /// `OovPolicy::Split` by default.
+    pub oov_policy: OovPolicy,
+    /// Multiply the cost of every dictionary word, number or acronym of the dynamic program
+    /// by the weight of the character position it starts at in its run, e.g. `|p| 1.0 + p as
+    /// f32` to trust the first words more than the later ones. `None` (the default) weighs
+    /// every position the same.
+    pub position_weight: Option<PositionWeight>,
+    /// With `CostModel::Frequency`, drop the corpus words counted fewer times than this
+    /// before the costs are computed, so that rare words cannot match junk text.
+    /// `None`, keeping every word, by default.
+    pub min_frequency: Option<f64>,
+    /// With `CostModel::ZipfRank`, keep only this many corpus lines, the most frequent ones,
+    /// before the costs are computed. `None`, keeping every line, by default.
+    pub max_rank: Option<usize>,
+    /// Consider no word longer than this many characters, like `split_bounded` for every
+    /// split. Numbers and acronyms are still kept whole. `None`, the longest dictionary word,
+    /// by default.
+    pub max_word_len: Option<usize>,
+    /// Split only the runs of letters of the text and write every other character, digits
+    /// and punctuation, as it is and glued to its neighbors, e.g. "bank of jordan2023!" for
+    /// "bankofjordan2023!" and "bank-of-jordan" unchanged. Off by default, the dynamic program
+    /// then sees every character.
+    pub keep_punctuation: bool,
+    /// Interpolate the word pairs of `load_bigrams` with the word costs in `split_bigram`,
+    /// the cost of a word after another one being `-ln(w P(second | first) + (1 - w)
+    /// P(second))` for a weight `w` between 0 and 1, so a pair never seen costs more than
+    /// its word alone. `None` (the default) takes the lower of the pair and word costs.
+    pub bigram_weight: Option<f32>,
+    /// Score the candidate words with this instead of the cost dictionary, e.g. to penalize
+    /// one-letter words or to favor brand names. The candidates are still at most as long
+    /// as the longest dictionary word, or `max_word_len`, and the `Trie` backend is not used.
+    /// `None` (the default) scores them with `cost_dict`.
+    pub cost_function: Option<Box<dyn WordCost>>,
+    pub(crate) trie: Option<Trie>,
+    /// The index of `fuzzy_cost`, built from `cost_dict` when the model is loaded
+    pub(crate) fuzzy_index: Option<DeletionIndex>,
+    /// The highest cost of a dictionary word, found when the model is loaded
+    pub(crate) dearest_cost: Option<f32>,
+    pub(crate) result_cache: ResultCache,
+    pub(crate) load_stats: Option<LoadStats>,
+    /// Whether `cost_dict` is the empty dictionary left by a corpus that could not be read,
+    /// which is then read again on the next split
+    pub(crate) load_failed: bool,
+    /// The casing of the corpus words that are not all lowercase, and the phrases with their
+    /// whitespace, by dictionary key
+    pub(crate) casings: HashMap<String, String, S>,
+    /// The cost of a word after another one, `-ln P(second | first)`, by first then second
+    /// lowercased word
+    pub(crate) bigrams: HashMap<String, HashMap<String, f32, S>, S>,
+    /// The length in characters of every word of the texts given to `pin`, by dictionary key
+    pub(crate) pins: HashMap<String, Vec<usize>, S>,
+    /// Hashes the words of the cost dictionary built from the corpus
+    pub(crate) hash_builder: S,
+}
+
+impl<S: BuildHasher + Clone + Default> Default for LanguageModel<S> {
+    fn default() -> Self {
+        LanguageModel::with_hasher(String::new(), S::default())
+    }
+}
+
+impl LanguageModel {
+    /// Create a language model from a corpus file, an empty path selects the bundled corpus.
+    /// Without the `bundled-corpus` feature there is none: a model with an empty path fails
+    /// to load with `SplitError::NoCorpus`, see `try_load`.
+    pub fn new(corpus_path: String) -> Self {
+        LanguageModel {
+            corpus_path,
+            ..Default::default()
+        }
+    }
+
+    /// Create a language model from the bundled corpus of a language, see `Language`
+    /// # Examples
+    /// ```
+    /// use rsplitter::{Language, LanguageModel};
+    /// let mut lm = LanguageModel::for_language(Language::English);
+    /// assert_eq!(lm.split("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn for_language(language: Language) -> Self {
+        LanguageModel {
+            language,
+            ..Default::default()
+        }
+    }
+
+    /// Read a compiled model written by `to_writer`, e.g. from an embedded asset or a socket
+    /// # Arguments
+    /// * `reader` - Where the compiled model is read from
+    /// # Returns
+    /// A model with its cost dictionary loaded, no corpus is needed
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut buffer = Vec::new();
+    /// LanguageModel::new(String::new()).to_writer(&mut buffer).unwrap();
+    /// let mut lm = LanguageModel::from_reader(buffer.as_slice()).unwrap();
+    /// assert_eq!(lm.split("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SplitError> {
+        let cost_dict = read_dict(reader)?;
+        if cost_dict.0.is_empty() {
+            return Err(SplitError::EmptyCorpus);
+        }
+        Ok(LanguageModel {
+            cost_dict: Some(Arc::new(cost_dict)),
+            ..Default::default()
+        })
+    }
+
+    /// Configure a language model setting by setting, then build it
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::builder().max_word_len(3).separator("_").build().unwrap();
+    /// assert_eq!(lm.split("thedogran".to_string()), "the_dog_ran");
+    /// assert_eq!(lm.split_words("thedogran".to_string()), ["the", "dog", "ran"]);
+    /// ```
+    pub fn builder() -> LanguageModelBuilder {
+        LanguageModelBuilder::new()
+    }
+
+    /// Build a language model from raw documents, e.g. the notes of a domain, instead of a
+    /// ranked word list. The words of the documents, by the Unicode word boundaries, are
+    /// counted lowercased and cost like the lines of a `CostModel::Frequency` corpus.
+    /// # Arguments
+    /// * `documents` - The texts to count the words of
+    /// * `min_count` - Drop the words seen fewer times than this
+    /// * `max_vocab` - Keep at most this many words, the most frequent ones
+    /// # Returns
+    /// An error if no word is kept
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let notes = ["The patient has acute pain.", "Acute pain, no fever.", "Pain again."];
+    /// let mut lm = LanguageModel::train_from_text(notes, 1, None).unwrap();
+    /// assert_eq!(lm.split("acutepainnofever".to_string()), "acute pain no fever");
+    /// ```
+    pub fn train_from_text<I, T>(
+        documents: I,
+        min_count: u64,
+        max_vocab: Option<usize>,
+    ) -> Result<Self, SplitError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for document in documents {
+            for word in unicode_words(document.as_ref()) {
+                *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<(String, u64)> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= min_count.max(1))
+            .collect();
+        // The most frequent first, then by word so that the vocabulary is deterministic
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(max_vocab.unwrap_or(counts.len()));
+        let lines: Vec<String> = counts
+            .into_iter()
+            .map(|(word, count)| format!("{} {}", word, count))
+            .collect();
+        let mut model = LanguageModel {
+            cost_model: CostModel::Frequency,
+            ..Default::default()
+        };
+        model.load_lines(&lines)?;
+        model.ensure_loaded();
+        Ok(model)
+    }
+
+    /// Build a language model from a corpus embedded in the binary, e.g. with `include_bytes!`,
+    /// like `from_corpus` without reading any file
+    /// # Arguments
+    /// * `bytes` - The UTF-8 corpus, one entry per line like a corpus file
+    /// # Returns
+    /// An `Io` error of kind `InvalidData` if the bytes are not UTF-8, like reading such a
+    /// file, or `EmptyCorpus` if the corpus has no word
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// const CORPUS: &[u8] = b"ban\nkof\njordan\n";
+    /// let mut lm = LanguageModel::from_bytes(CORPUS).unwrap();
+    /// assert_eq!(lm.split("bankofjordan".to_string()), "ban kof jordan");
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SplitError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        LanguageModel::from_text(text)
+    }
+
+    /// Build a language model from a corpus read from any source, e.g. a download or a
+    /// decompressor, like `from_corpus` does from a file
+    /// # Arguments
+    /// * `reader` - Where the UTF-8 corpus is read from, one entry per line like a corpus file
+    /// # Returns
+    /// An error if reading fails or the corpus is not UTF-8, or `EmptyCorpus` if it has no
+    /// word
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let corpus = std::io::Cursor::new("ban\nkof\njordan\n");
+    /// let mut lm = LanguageModel::from_corpus_reader(corpus).unwrap();
+    /// assert_eq!(lm.split("bankofjordan"), "ban kof jordan");
+    /// ```
+    pub fn from_corpus_reader<R: Read>(mut reader: R) -> Result<Self, SplitError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        LanguageModel::from_text(&text)
+    }
+
+    /// Read a compiled model written by `save` from an async source, e.g. an object store
+    /// client, like `from_reader`
+    #[cfg(feature = "tokio")]
+    pub async fn from_reader_async<R: tokio::io::AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Self, SplitError> {
+        let mut bytes = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut bytes).await?;
+        LanguageModel::from_reader(bytes.as_slice())
+    }
+
+    /// Build a language model from a corpus read from an async source, like
+    /// `from_corpus_reader`. The dictionary is built once the whole corpus is read, on the
+    /// calling task; spawn it on a blocking thread for a large corpus.
+    #[cfg(feature = "tokio")]
+    pub async fn from_corpus_reader_async<R: tokio::io::AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Self, SplitError> {
+        let mut bytes = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut bytes).await?;
+        LanguageModel::from_bytes(&bytes)
+    }
+
+    /// Build a language model from a gzipped word list of wordninja, e.g. its
+    /// `wordninja_words.txt.gz`, the most frequent word first, one word per line. The
+    /// wordsegment unigrams are converted to such a list by sorting them by count.
+    /// # Arguments
+    /// * `path` - The gzip file
+    /// # Returns
+    /// An `InvalidModel` error if the file is not a valid gzip file, see `from_bytes` for
+    /// the other errors
+    #[cfg(feature = "wordninja")]
+    pub fn from_wordninja_gz(path: impl AsRef<Path>) -> Result<Self, SplitError> {
+        LanguageModel::from_bytes(&crate::wordninja::gunzip(&std::fs::read(path)?)?)
+    }
+
+    /// `from_bytes` once the corpus is text
+    fn from_text(text: &str) -> Result<Self, SplitError> {
+        // A byte order mark is not part of the first word, see `lines_of`
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let mut model = LanguageModel::default();
+        let lines = model.clean_lines(text.lines().map(String::from).collect());
+        model.load_lines(&lines)?;
+        model.ensure_loaded();
+        Ok(model)
+    }
+
+    /// Read a compiled model written by `save`, see `from_reader`.
+    /// Only the word costs are compiled: the corpus casing and the settings are not kept.
+    /// # Arguments
+    /// * `path` - The compiled model file
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let path = std::env::temp_dir().join("rsplitter_doc_model.bin");
+    /// LanguageModel::new(String::new()).save(&path).unwrap();
+    /// let mut lm = LanguageModel::load(&path).unwrap();
+    /// assert_eq!(lm.split("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SplitError> {
+        LanguageModel::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Split with a dictionary written by `save_mapped` and mapped in memory, see
+    /// `MappedDictionary`: the model loads in no time whatever the number of words, and
+    /// the processes splitting with the same file share its memory.
+    /// The dictionary is the `cost_function` of the model, so the corpus casing, the
+    /// spelling correction and the `Trie` backend are not used.
+    /// # Arguments
+    /// * `path` - The mapped dictionary file, which must not change while it is open
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let path = std::env::temp_dir().join("rsplitter_doc_model.map");
+    /// LanguageModel::new(String::new()).save_mapped(&path).unwrap();
+    /// let mut lm = LanguageModel::open_mapped(&path).unwrap();
+    /// assert_eq!(lm.split("rustisgreat"), "rust is great");
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn open_mapped(path: impl AsRef<Path>) -> Result<Self, SplitError> {
+        let dictionary = MappedDictionary::open(path)?;
+        Ok(LanguageModel {
+            cost_dict: Some(Arc::new((HashMap::default(), dictionary.max_word()))),
+            cost_function: Some(Box::new(dictionary)),
+            ..LanguageModel::default()
+        })
+    }
+
+    /// Create a language model and build its cost dictionary right away
+    /// # Arguments
+    /// * `corpus_path` - The corpus file, an empty path selects the bundled corpus
+    /// # Returns
+    /// An error if the corpus cannot be read or has no word, see `try_load`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// assert_eq!(lm.split("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn from_corpus(corpus_path: String) -> Result<Self, SplitError> {
+        let mut model = LanguageModel::new(corpus_path);
+        model.try_load()?;
+        Ok(model)
+    }
+
+    /// Create a language model like `from_corpus`, sharing the dictionary of the models
+    /// already built from the same corpus file by this function.
+    /// The dictionaries are cached for the whole process by canonical path; the cache is
+    /// never invalidated on its own, see `clear_dictionary_cache`.
+    /// # Arguments
+    /// * `corpus_path` - The corpus file, an empty path selects the bundled corpus
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let first = LanguageModel::from_corpus_cached(String::new()).unwrap();
+    /// let second = LanguageModel::from_corpus_cached(String::new()).unwrap();
+    /// assert!(std::sync::Arc::ptr_eq(
+    ///     first.cost_dict.as_ref().unwrap(),
+    ///     second.cost_dict.as_ref().unwrap()
+    /// ));
+    /// ```
+    pub fn from_corpus_cached(corpus_path: String) -> Result<Self, SplitError> {
+        let key = if corpus_path.is_empty() {
+            String::new()
+        } else {
+            std::fs::canonicalize(&corpus_path)?
+                .to_string_lossy()
+                .into_owned()
+        };
+        let mut model = LanguageModel::new(corpus_path);
+        let (cost_dict, casings) = cached_dict(key, || {
+            model.try_load()?;
+            Ok((model.cost_dict.clone().unwrap(), model.casings.clone()))
+        })?;
+        model.cost_dict = Some(cost_dict);
+        model.casings = casings;
+        Ok(model)
+    }
+
+    /// Create a language model from several corpora merged with a weight each, see
+    /// `add_corpus_weighted`: the costs of every corpus are computed on their own, then
+    /// divided by its weight, and a word in several corpora keeps the cheaper cost.
+    /// # Arguments
+    /// * `sources` - The corpora and their positive weights
+    /// # Returns
+    /// An error if a corpus file cannot be read, a weight is not positive and finite or the
+    /// corpora have no word at all
+    /// # Examples
+    /// ```
+    /// use rsplitter::{CorpusSource, Language, LanguageModel};
+    /// let products = ["rustacean", "ferris"];
+    /// let mut lm = LanguageModel::with_corpora(&[
+    ///     (CorpusSource::Bundled(Language::English), 1.0),
+    ///     (CorpusSource::Words(&products), 2.0),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(lm.split("ferristherustaceanisacrab"), "ferris the rustacean is a crab");
+    /// ```
+    pub fn with_corpora(sources: &[(CorpusSource, f32)]) -> Result<Self, SplitError> {
+        let mut model = LanguageModel {
+            cost_dict: Some(Arc::new((HashMap::default(), 0))),
+            ..LanguageModel::default()
+        };
+        for (source, weight) in sources {
+            check_weight(*weight)?;
+            let lines = match source {
+                CorpusSource::Bundled(language) => {
+                    let corpus = language.corpus().ok_or(SplitError::NoCorpus)?;
+                    model.clean_lines(corpus.lines().map(String::from).collect())
+                }
+                CorpusSource::File(path) => model.lines_of(path)?,
+                CorpusSource::Words(words) => {
+                    model.clean_lines(words.iter().map(|word| word.to_string()).collect())
+                }
+            };
+            model.merge_weighted(&lines, *weight)?;
+        }
+        if model.dict().0.is_empty() {
+            return Err(SplitError::EmptyCorpus);
+        }
+        Ok(model)
+    }
+
+    /// Create a language model from several corpus files merged together, see `load_corpora`
+    /// # Arguments
+    /// * `paths` - The corpus files, from the highest to the lowest priority
+    /// # Returns
+    /// An error if a corpus cannot be read or they have no word at all
+    pub fn from_corpora(paths: &[PathBuf]) -> Result<Self, SplitError> {
+        let mut model = LanguageModel::default();
+        model.load_corpora(paths)?;
+        Ok(model)
+    }
+
+    /// Create a language model with the corpora, the language and the OOV policy of a
+    /// configuration; its `pre_tokenizer` is given to `split_with`
+    /// # Returns
+    /// An error if a corpus cannot be read or they have no word at all
+    /// # Examples
+    /// ```
+    /// use rsplitter::{Config, LanguageModel};
+    /// let config = Config::from_toml("pre_tokenizer = \"Identifier\"").unwrap();
+    /// let mut lm = LanguageModel::from_config(&config).unwrap();
+    /// assert_eq!(lm.split_with(&config.pre_tokenizer, "parseHTTPResponse"), "parse HTTP Response");
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_config(config: &Config) -> Result<Self, SplitError> {
+        let mut model = LanguageModel {
+            language: config.language,
+            oov_policy: config.oov_policy,
+            ..LanguageModel::default()
+        };
+        if config.corpus_paths.is_empty() {
+            model.try_load()?;
+        } else {
+            model.load_corpora(&config.corpus_paths)?;
+        }
+        Ok(model)
+    }
 }
 
-impl LanguageModel {
-    fn best_match(&mut self, i: i32, text: String, cost: Vec<f32>) -> (f32, f32) {
-        if self.cost_dict.is_none() {
-            self.cost_dict = Some(self.set_cost_dict());
-        }
-        let cost_dict = self.cost_dict.as_ref().unwrap();
-        let max = vec![0, i - cost_dict.1].into_iter().max().unwrap() as usize;
-        let mut slice: Vec<f32> = cost[max..i as usize].to_vec();
-        slice.sort_by(|a, b| b.partial_cmp(a).unwrap());
-        let mut array_min: Vec<(f32, f32)> = Vec::new();
-        for (k, c) in slice.iter().enumerate() {
-            let word_cost = cost_dict
-                .0
-                .get(
-                    &text[(i - k as i32 - 1) as usize..i as usize]
-                        .to_string()
-                        .to_lowercase(),
-                )
-                .map_or(f32::MAX, |x| *x);
-            array_min.push((c + word_cost, k as f32 + 1.0));
-        }
-        return array_min
+impl<S: BuildHasher + Clone> LanguageModel<S> {
+    /// Create a language model whose cost dictionary hashes its words with `hash_builder`
+    /// # Arguments
+    /// * `corpus_path` - The corpus file, an empty path selects the bundled corpus
+    /// * `hash_builder` - Builds the hasher of the cost dictionary
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// use std::collections::hash_map::RandomState;
+    /// let mut lm = LanguageModel::with_hasher(String::new(), RandomState::new());
+    /// assert_eq!(lm.split("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn with_hasher(corpus_path: String, hash_builder: S) -> Self {
+        LanguageModel {
+            corpus_path,
+            language: Language::default(),
+            cost_dict: None,
+            cost_model: CostModel::default(),
+            case_mode: CaseMode::default(),
+            spelling_correction: false,
+            backend: DictionaryBackend::default(),
+            beam_width: None,
+            comment_prefix: Some('#'),
+            tie_break: TieBreak::default(),
+            length_penalty: 0.0,
+            number_separators: vec![',', '.'],
+            currency_symbols: vec!['$', '€', '£', '¥'],
+            acronym_min_length: None,
+            units: Vec::new(),
+            case_sensitive: false,
+            unknown_threshold: 2,
+            max_input_len: None,
+            collapse_whitespace: false,
+            separator: String::from(" "),
+            deletion_cost: None,
+            normalize: None,
+            capacity_hint: None,
+            fuzzy_cost: None,
+            cache_capacity: None,
+            keep_cheaper_input: false,
+            merge_unknown_shorter_than: None,
+            oov_policy: OovPolicy::default(),
+            position_weight: None,
+            cost_function: None,
+            min_frequency: None,
+            max_rank: None,
+            max_word_len: None,
+            keep_punctuation: false,
+            bigram_weight: None,
+            trie: None,
+            fuzzy_index: None,
+            dearest_cost: None,
+            result_cache: ResultCache::default(),
+            load_stats: None,
+            load_failed: false,
+            casings: HashMap::with_hasher(hash_builder.clone()),
+            bigrams: HashMap::with_hasher(hash_builder.clone()),
+            pins: HashMap::with_hasher(hash_builder.clone()),
+            hash_builder,
+        }
+    }
+
+    /// Write the compiled cost dictionary, building it from the corpus first if needed
+    /// # Arguments
+    /// * `writer` - Where the compiled model is written to
+    pub fn to_writer<W: Write>(&mut self, writer: W) -> Result<(), SplitError> {
+        self.try_ensure_loaded()?;
+        write_dict(writer, self.dict())
+    }
+
+    /// Write the compiled cost dictionary to a file, for `load` to read back without the
+    /// corpus, see `to_writer`
+    /// # Arguments
+    /// * `path` - The file written, replaced if it exists
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), SplitError> {
+        self.to_writer(BufWriter::new(File::create(path)?))
+    }
+
+    /// Write the cost dictionary to a file for `open_mapped`, building it from the corpus
+    /// first if needed. Unlike `save`, the words are indexed to be looked up in place.
+    /// # Arguments
+    /// * `path` - The file written, replaced if it exists
+    #[cfg(feature = "mmap")]
+    pub fn save_mapped(&mut self, path: impl AsRef<Path>) -> Result<(), SplitError> {
+        self.try_ensure_loaded()?;
+        write_mapped(BufWriter::new(File::create(path)?), self.dict())
+    }
+
+    /// Write the dictionary as a ranked corpus, the cheapest word first, one word per line.
+    /// A word is written with its corpus casing, if any, and words of equal cost are sorted,
+    /// so the output is reproducible. Loading the file with the Zipf cost model gives back
+    /// the same ranking of the words. The model is loaded first if it is not yet.
+    /// # Arguments
+    /// * `writer` - Where the corpus is written to
+    /// # Returns
+    /// An error if the corpus of the model cannot be read or the writer fails
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let mut corpus = Vec::new();
+    /// lm.write_corpus(&mut corpus).unwrap();
+    /// assert!(corpus.starts_with(b"the\n"));
+    /// ```
+    pub fn write_corpus<W: Write>(&mut self, mut writer: W) -> Result<(), SplitError> {
+        self.try_ensure_loaded()?;
+        let mut words: Vec<(&String, &f32)> = self.dict().0.iter().collect();
+        words.sort_by(|a, b| a.1.total_cmp(b.1).then(a.0.cmp(b.0)));
+        for (word, _) in words {
+            let word = self.casings.get(word).unwrap_or(word);
+            writer.write_all(word.as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the dictionary as a gzipped word list wordninja loads, like `write_corpus`
+    /// # Arguments
+    /// * `path` - The gzip file written, replaced if it exists
+    #[cfg(feature = "wordninja")]
+    pub fn save_wordninja_gz(&mut self, path: impl AsRef<Path>) -> Result<(), SplitError> {
+        let mut corpus = Vec::new();
+        self.write_corpus(&mut corpus)?;
+        std::fs::write(path, crate::wordninja::gzip(&corpus))?;
+        Ok(())
+    }
+
+    /// The weight added to the cost of every word of a split, see `set_length_penalty`
+    pub fn length_penalty(&self) -> f32 {
+        self.length_penalty
+    }
+
+    /// Set the weight added to the cost of every word of a split.
+    /// Word costs range from about 2.5 for the most frequent words to about 14 for the rarest
+    /// of the bundled corpus, so weights of that order visibly change the splits.
+    /// # Arguments
+    /// * `weight` - Positive for fewer and longer words, negative for more and shorter words
+    /// # Returns
+    /// An `InvalidInput` error for a weight that is not finite, the length penalty is then
+    /// kept
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let mut splits = Vec::new();
+    /// for weight in [0.0, 5.0, 20.0] {
+    ///     lm.set_length_penalty(weight).unwrap();
+    ///     splits.push(lm.split("itisatthe".to_string()));
+    /// }
+    /// assert_eq!(splits, ["it is at the", "itis at the", "itis atthe"]);
+    /// ```
+    pub fn set_length_penalty(&mut self, weight: f32) -> Result<(), SplitError> {
+        if !weight.is_finite() {
+            return Err(SplitError::InvalidInput(format!(
+                "the length penalty is finite, not {}",
+                weight
+            )));
+        }
+        self.length_penalty = weight;
+        self.result_cache.clear();
+        Ok(())
+    }
+
+    /// Release the spare capacity of the dictionary, e.g. after removing words from it
+    pub fn shrink_to_fit(&mut self) {
+        // A dictionary shared with other models is left as it is
+        if let Some(cost_dict) = self.cost_dict.as_mut().and_then(Arc::get_mut) {
+            cost_dict.0.shrink_to_fit();
+        }
+        self.casings.shrink_to_fit();
+        if let Some(trie) = self.trie.as_mut() {
+            trie.shrink_to_fit();
+        }
+    }
+
+    /// An approximation of the memory held by the dictionary, in bytes:
+    /// the hash table buckets, the bytes of every word, and the trie and the index of
+    /// `fuzzy_cost`, if any.
+    /// Nothing is counted before the model is loaded.
+    pub fn memory_estimate(&self) -> usize {
+        let dict = self.cost_dict.as_ref().map_or(0, |cost_dict| {
+            // Every bucket holds an entry and a control byte
+            let buckets = cost_dict.0.capacity() * std::mem::size_of::<(String, f32)>()
+                + cost_dict.0.capacity();
+            let words: usize = cost_dict.0.keys().map(|word| word.capacity()).sum();
+            buckets + words
+        });
+        let casings = self.casings.capacity() * (std::mem::size_of::<(String, String)>() + 1)
+            + self
+                .casings
+                .iter()
+                .map(|(word, casing)| word.capacity() + casing.capacity())
+                .sum::<usize>();
+        let indexes = self.trie.as_ref().map_or(0, |trie| trie.memory_estimate())
+            + self
+                .fuzzy_index
+                .as_ref()
+                .map_or(0, |index| index.memory_estimate());
+        dict + casings + indexes
+    }
+
+    /// Build the cost dictionary from the corpus, unless it is already built.
+    /// Unlike the lazy loading of `split`, which splits with an empty dictionary, an
+    /// unreadable corpus is reported and a corpus without any word is rejected with
+    /// `SplitError::EmptyCorpus`.
+    pub fn try_load(&mut self) -> Result<(), SplitError> {
+        if self.cost_dict.is_none() || self.load_failed {
+            let lines = self.lines_from_file()?;
+            self.load_lines(&lines)?;
+        }
+        self.try_ensure_loaded()
+    }
+
+    /// Build the cost dictionary again from the corpus, e.g. after its file was updated,
+    /// like `try_load` does the first time. The words added or removed since are dropped, and
+    /// so are the corpora merged by `load_corpora`: the dictionary is the one of
+    /// `corpus_path` alone, or of the bundled corpus when it is empty. A model shared by
+    /// threads is reloaded as a new one given to `ModelHandle::store`.
+    /// # Returns
+    /// An error if the corpus cannot be read or has no word, the current dictionary is then
+    /// kept
+    pub fn reload(&mut self) -> Result<(), SplitError> {
+        let lines = self.lines_from_file()?;
+        self.load_lines(&lines)?;
+        self.ensure_loaded();
+        Ok(())
+    }
+
+    /// Build the cost dictionary from cleaned corpus lines, see `clean_lines`,
+    /// replacing the current one
+    pub(crate) fn load_lines(&mut self, lines: &[String]) -> Result<(), SplitError> {
+        let start = Instant::now();
+        let cost_dict = self.set_cost_dict(lines);
+        self.load_cost_dict(cost_dict, lines, start)
+    }
+
+    /// `install_cost_dict` unless the dictionary has no word
+    fn load_cost_dict(
+        &mut self,
+        cost_dict: CostDict<S>,
+        lines: &[String],
+        start: Instant,
+    ) -> Result<(), SplitError> {
+        if cost_dict.0.is_empty() {
+            return Err(SplitError::EmptyCorpus);
+        }
+        self.install_cost_dict(cost_dict, lines, start);
+        Ok(())
+    }
+
+    /// Replace the dictionary by one built from corpus lines since `start`, with the casings
+    /// of the lines
+    fn install_cost_dict(&mut self, cost_dict: CostDict<S>, lines: &[String], start: Instant) {
+        self.casings = self.corpus_casings(lines);
+        self.load_failed = false;
+        self.load_stats = Some(LoadStats {
+            elapsed: start.elapsed(),
+            lines: lines.len(),
+            words: cost_dict.0.len(),
+            max_word_len: cost_dict.1 as usize,
+        });
+        self.cost_dict = Some(Arc::new(cost_dict));
+        self.invalidate_indexes();
+    }
+
+    /// How the dictionary was last built from a corpus, `None` before, e.g. for a model
+    /// read with `load`. Words added later, e.g. with `add_words`, are not counted.
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_bytes(b"rust\nis\ngreat\n").unwrap();
+    /// let stats = lm.load_stats().unwrap();
+    /// assert_eq!((stats.words, stats.max_word_len), (3, 5));
+    /// ```
+    pub fn load_stats(&self) -> Option<LoadStats> {
+        self.load_stats
+    }
+
+    /// The lines of an in-memory corpus, cleaned like the lines of a corpus file
+    pub(crate) fn clean_lines(&self, lines: Vec<String>) -> Vec<String> {
+        self.filter_lines(clean_lines(lines, self.comment_prefix))
+    }
+
+    /// Drop the cleaned corpus lines past `max_rank` or under `min_frequency`
+    fn filter_lines(&self, mut lines: Vec<String>) -> Vec<String> {
+        match (self.cost_model, self.max_rank, self.min_frequency) {
+            (CostModel::ZipfRank, Some(max_rank), _) => lines.truncate(max_rank),
+            (CostModel::Frequency, _, Some(min_frequency)) => lines.retain(|line| {
+                frequency_entry(line).is_some_and(|(_, count)| count >= min_frequency)
+            }),
+            _ => {}
+        }
+        lines
+    }
+
+    /// Build the cost dictionary from several corpus files, replacing the current one.
+    /// The files are merged in order: the words of the earlier files rank first and
+    /// a word already found in an earlier file keeps its earliest cost.
+    /// # Arguments
+    /// * `paths` - The corpus files, from the highest to the lowest priority
+    pub fn load_corpora(&mut self, paths: &[PathBuf]) -> Result<(), SplitError> {
+        let mut merged = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for path in paths {
+            for line in self.lines_of(path)? {
+                if seen.insert(dictionary_key(self.line_word(&line))) {
+                    merged.push(line);
+                }
+            }
+        }
+        self.load_lines(&merged)?;
+        self.ensure_loaded();
+        Ok(())
+    }
+
+    /// Add the words of another corpus file to the dictionary, with a priority.
+    /// The costs of the file are computed on their own, like for a model of that file alone,
+    /// then divided by `weight`: a weight above 1 makes its words cheaper than they would be,
+    /// so a domain corpus can outrank the general one, and a weight below 1 makes them dearer.
+    /// The costs are at least 0, so a free word stays free whatever the weight.
+    /// A word already in the dictionary keeps the cheaper of its two costs.
+    /// # Arguments
+    /// * `path` - The corpus file to add
+    /// * `weight` - The priority of the file, positive
+    /// # Returns
+    /// An `InvalidInput` error if the weight is not positive and finite, or an error if the
+    /// file cannot be read; the dictionary is then unchanged
+    /// # Examples
+    /// ```no_run
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// lm.add_corpus_weighted("medical_terms.txt", 2.0).unwrap();
+    /// ```
+    pub fn add_corpus_weighted(
+        &mut self,
+        path: impl AsRef<Path>,
+        weight: f32,
+    ) -> Result<(), SplitError> {
+        check_weight(weight)?;
+        let lines = self.lines_of(path.as_ref())?;
+        self.merge_weighted(&lines, weight)
+    }
+
+    /// Add words to the dictionary with a priority, ranked like the lines of a corpus of
+    /// their own, the most frequent first, see `add_corpus_weighted`
+    /// # Arguments
+    /// * `words` - The words to add, e.g. product names
+    /// * `priority` - The weight of the words, positive
+    /// # Returns
+    /// An `InvalidInput` error if the priority is not positive and finite, the dictionary is
+    /// then unchanged
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// lm.add_words(&["rustacean"], 1.0).unwrap();
+    /// assert_eq!(lm.split("hiarustaceanandyou"), "hi a rustacean and you");
+    /// ```
+    pub fn add_words(&mut self, words: &[&str], priority: f32) -> Result<(), SplitError> {
+        check_weight(priority)?;
+        let lines = self.clean_lines(words.iter().map(|word| word.to_string()).collect());
+        self.merge_weighted(&lines, priority)
+    }
+
+    /// Add a word to the dictionary with a cost, or change its cost, e.g. for a user name
+    /// found after loading; the cheaper the more likely. A word with uppercase letters or a
+    /// phrase is also written as given under `CaseMode::Corpus`.
+    /// Everything built from the dictionary is rebuilt on the next split.
+    /// # Arguments
+    /// * `word` - The word or phrase to add
+    /// * `cost` - Its cost, finite
+    /// # Returns
+    /// The previous cost of the word, if any, or an error for an empty word or a cost that
+    /// is not finite
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// lm.insert_word("AAPL", 5.0).unwrap();
+    /// assert_eq!(lm.split("buyaaplnow".to_string()), "buy aapl now");
+    /// ```
+    pub fn insert_word(&mut self, word: &str, cost: f32) -> Result<Option<f32>, SplitError> {
+        let key = dictionary_key(word);
+        if key.is_empty() {
+            return Err(SplitError::InvalidInput(String::from("the word is empty")));
+        }
+        if !cost.is_finite() {
+            return Err(SplitError::InvalidInput(String::from(
+                "the cost of a word is finite",
+            )));
+        }
+        self.try_ensure_loaded()?;
+        let length = key.chars().count() as i32;
+        self.casings.remove(&key);
+        self.casings
+            .extend(corpus_casings(&[word], self.hash_builder.clone()));
+        let cost_dict = self.dict_mut();
+        let previous = cost_dict.0.insert(key, cost);
+        cost_dict.1 = cost_dict.1.max(length);
+        self.invalidate_indexes();
+        Ok(previous)
+    }
+
+    /// Remove a word from the dictionary, the longest word being found again if it was
+    /// that one. Everything built from the dictionary is rebuilt on the next split.
+    /// # Arguments
+    /// * `word` - The word or phrase to remove
+    /// # Returns
+    /// The cost the word had, `None` if it was not in the dictionary
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.remove_word("throwback").is_some());
+    /// assert_ne!(lm.split("throwback".to_string()), "throwback");
+    /// ```
+    pub fn remove_word(&mut self, word: &str) -> Option<f32> {
+        self.ensure_loaded();
+        let key = dictionary_key(word);
+        let cost_dict = self.dict_mut();
+        let previous = cost_dict.0.remove(&key)?;
+        if key.chars().count() as i32 == cost_dict.1 {
+            cost_dict.1 = cost_dict
+                .0
+                .keys()
+                .map(|word| word.chars().count())
+                .max()
+                .unwrap_or(0) as i32;
+        }
+        self.casings.remove(&key);
+        self.invalidate_indexes();
+        Some(previous)
+    }
+
+    /// Always split a run of text between whitespace into the given words, before and
+    /// whatever the dictionary costs, e.g. for a brand name that must never split another
+    /// way. The run matches whatever its casing and its words keep the casing of the text.
+    /// The pins are not part of the dictionary: they are kept by `reload` and not saved.
+    /// # Arguments
+    /// * `text` - The glued words, without whitespace
+    /// * `words` - The approved split of `text`, replacing any earlier one
+    /// # Returns
+    /// An `InvalidInput` error if the text is empty or has whitespace, or if the words glued
+    /// together are not the text
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split("penisland"), "penis land");
+    /// lm.pin("penisland", &["pen", "island"]).unwrap();
+    /// assert_eq!(lm.split("visit PenIsland"), "visit Pen Island");
+    /// ```
+    pub fn pin(&mut self, text: &str, words: &[&str]) -> Result<(), SplitError> {
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return Err(SplitError::InvalidInput(String::from(
+                "a pinned text is a single run without whitespace",
+            )));
+        }
+        let lengths: Vec<usize> = words.iter().map(|word| word.chars().count()).collect();
+        if dictionary_key(&words.concat()) != dictionary_key(text)
+            || lengths.iter().sum::<usize>() != text.chars().count()
+            || lengths.contains(&0)
+        {
+            return Err(SplitError::InvalidInput(format!(
+                "the words {:?} are not a split of {:?}",
+                words, text
+            )));
+        }
+        self.pins.insert(dictionary_key(text), lengths);
+        self.result_cache.clear();
+        Ok(())
+    }
+
+    /// Split a text pinned with `pin` like any other again
+    /// # Returns
+    /// Whether the text was pinned
+    pub fn unpin(&mut self, text: &str) -> bool {
+        let pinned = self.pins.remove(&dictionary_key(text)).is_some();
+        if pinned {
+            self.result_cache.clear();
+        }
+        pinned
+    }
+
+    /// Merge the costs of corpus lines computed on their own and divided by `weight` into
+    /// the dictionary, see `add_corpus_weighted`. A cost below 0 would be made dearer by a
+    /// weight above 1, so the costs are clamped to 0 first.
+    fn merge_weighted(&mut self, lines: &[String], weight: f32) -> Result<(), SplitError> {
+        check_weight(weight)?;
+        let source = self.set_cost_dict(lines);
+        let casings = self.corpus_casings(lines);
+        self.try_ensure_loaded()?;
+        // A word already in the dictionary keeps its casing
+        let casings: Vec<(String, String)> = casings
+            .into_iter()
+            .filter(|(word, _)| !self.dict().0.contains_key(word))
+            .collect();
+        self.casings.extend(casings);
+        let cost_dict = self.dict_mut();
+        for (word, cost) in source.0 {
+            let cost = cost.max(0.0) / weight;
+            let entry = cost_dict.0.entry(word).or_insert(cost);
+            *entry = entry.min(cost);
+        }
+        cost_dict.1 = cost_dict.1.max(source.1);
+        self.invalidate_indexes();
+        self.build_indexes();
+        Ok(())
+    }
+
+    /// Load the word pairs used by `split_bigram`, replacing the current ones.
+    /// Every line of the file is `first second count`, the count of the pair in some text;
+    /// lines without a positive and finite count are skipped.
+    /// # Arguments
+    /// * `path` - The file of word pairs
+    /// # Returns
+    /// An error if the file cannot be read or has no pair
+    pub fn load_bigrams(&mut self, path: impl AsRef<Path>) -> Result<(), SplitError> {
+        let mut counts: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        for line in self.lines_of(path.as_ref())? {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if let [first, second, count] = parts[..] {
+                match count.parse::<f64>() {
+                    Ok(count) if count > 0.0 && count.is_finite() => {
+                        *counts
+                            .entry(dictionary_key(first))
+                            .or_default()
+                            .entry(dictionary_key(second))
+                            .or_insert(0.0) += count;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if counts.is_empty() {
+            return Err(SplitError::EmptyCorpus);
+        }
+        let mut bigrams = HashMap::with_hasher(self.hash_builder.clone());
+        for (first, nexts) in counts {
+            let total: f64 = nexts.values().sum();
+            let mut costs =
+                HashMap::with_capacity_and_hasher(nexts.len(), self.hash_builder.clone());
+            costs.extend(
+                nexts
+                    .into_iter()
+                    .map(|(second, count)| (second, (total / count).ln() as f32)),
+            );
+            bigrams.insert(first, costs);
+        }
+        self.bigrams = bigrams;
+        Ok(())
+    }
+
+    /// The word of a corpus line, the line itself for a ranked list
+    fn line_word<'l>(&self, line: &'l str) -> &'l str {
+        match self.cost_model {
+            CostModel::ZipfRank => line,
+            CostModel::Frequency => frequency_entry(line).map_or_else(
+                || {
+                    line.rsplit_once(char::is_whitespace)
+                        .map_or(line, |(phrase, _)| phrase.trim_end())
+                },
+                |(word, _)| word,
+            ),
+        }
+    }
+
+    /// Build the cost dictionary from the corpus, unless it is already built, then the
+    /// indexes of the dictionary
+    /// # Returns
+    /// An error if the corpus cannot be read
+    pub(crate) fn try_ensure_loaded(&mut self) -> Result<(), SplitError> {
+        if self.cost_dict.is_none() || self.load_failed {
+            let lines = self.lines_from_file()?;
+            let start = Instant::now();
+            let cost_dict = self.set_cost_dict(&lines);
+            self.install_cost_dict(cost_dict, &lines, start);
+        }
+        self.build_indexes();
+        Ok(())
+    }
+
+    /// `try_ensure_loaded` for the methods that cannot fail: a corpus that cannot be read
+    /// leaves an empty dictionary, so every character is an unknown word, and is read again
+    /// on the next call. `try_load` reports the error.
+    pub(crate) fn ensure_loaded(&mut self) {
+        if self.try_ensure_loaded().is_err() {
+            let empty = HashMap::with_hasher(self.hash_builder.clone());
+            self.cost_dict = Some(Arc::new((empty, 0)));
+            self.load_failed = true;
+            self.invalidate_indexes();
+            self.build_indexes();
+        }
+    }
+
+    /// The dictionary and its longest word, `ensure_loaded` having been called
+    fn dict_mut(&mut self) -> &mut CostDict<S> {
+        let hash_builder = &self.hash_builder;
+        let cost_dict = self
+            .cost_dict
+            .get_or_insert_with(|| Arc::new((HashMap::with_hasher(hash_builder.clone()), 0)));
+        Arc::make_mut(cost_dict)
+    }
+
+    /// Build the trie and the fuzzy index of the dictionary if they are used and missing,
+    /// and find its highest cost
+    fn build_indexes(&mut self) {
+        if self.dearest_cost.is_none() {
+            self.dearest_cost = self
+                .dict()
+                .0
+                .values()
+                .copied()
+                .filter(|cost| cost.is_finite())
+                .reduce(f32::max);
+        }
+        if self.backend == DictionaryBackend::Trie && self.trie.is_none() {
+            enter_span!("build_trie");
+            self.trie = Some(Trie::new(&self.dict().0));
+        }
+        if self.fuzzy_cost.is_some() && self.fuzzy_index.is_none() {
+            enter_span!("build_fuzzy_index");
+            self.fuzzy_index = Some(DeletionIndex::new(&self.dict().0));
+        }
+    }
+
+    /// Forget everything built from the dictionary, after it was replaced or changed
+    pub(crate) fn invalidate_indexes(&mut self) {
+        self.trie = None;
+        self.fuzzy_index = None;
+        self.dearest_cost = None;
+        self.result_cache.clear();
+    }
+
+    /// The cost dictionary, `ensure_loaded` must have been called
+    fn dict(&self) -> &CostDict<S> {
+        self.cost_dict
+            .as_ref()
+            .expect("the cost dictionary is loaded before splitting")
+    }
+
+    /// Return a Vec containing all the lines of the corpus, trimmed.
+    /// Blank lines and comments are skipped, trimming also drops the `\r` of Windows line endings.
+    pub(crate) fn lines_from_file(&self) -> Result<Vec<String>, SplitError> {
+        if self.corpus_path.is_empty() {
+            let corpus = self.language.corpus().ok_or(SplitError::NoCorpus)?;
+            return Ok(self.filter_lines(clean_lines(corpus.lines(), self.comment_prefix)));
+        }
+        self.lines_of(Path::new(&self.corpus_path))
+    }
+
+    /// The lines of a corpus file, cleaned like `lines_from_file`
+    fn lines_of(&self, path: &Path) -> Result<Vec<String>, SplitError> {
+        // Read corpus file and split into lines
+        let file = File::open(path)?;
+        let buf_reader = BufReader::new(file);
+        let mut lines: Vec<String> = Vec::with_capacity(self.capacity_hint.unwrap_or(0));
+        for line in buf_reader.lines() {
+            lines.push(line?);
+        }
+        // Some editors start UTF-8 files with a byte order mark, it is not part of the first word
+        if let Some(first) = lines.first_mut() {
+            if let Some(stripped) = first.strip_prefix('\u{feff}') {
+                *first = stripped.to_string();
+            }
+        }
+        Ok(self.clean_lines(lines))
+    }
+
+    /// Calculate the cost of each word in the corpus
+    /// Return a Tuple containing a HashMap of words and their costs
+    /// as values and the maximum cost as second value
+    fn set_cost_dict(&self, lines: &[String]) -> CostDict<S> {
+        enter_span!("load_dictionary", lines = lines.len(), cost_model = ?self.cost_model);
+        let hash_builder = self.hash_builder.clone();
+        let mut cost_dict = match self.cost_model {
+            CostModel::ZipfRank => zipf_costs(lines, hash_builder),
+            CostModel::Frequency => frequency_costs(lines, hash_builder, self.capacity_hint),
+        };
+        self.add_cased_costs(&mut cost_dict, lines);
+        debug_event!(
+            words = cost_dict.0.len(),
+            max_word = cost_dict.1,
+            "dictionary built"
+        );
+        cost_dict
+    }
+
+    /// Give every word of the corpus as written the cost of its own rank or count, see
+    /// `case_sensitive`. A lowercase word then costs as its own line instead of its most
+    /// frequent casing, the other casings become words of their own.
+    fn add_cased_costs(&self, cost_dict: &mut CostDict<S>, lines: &[String]) {
+        if !self.case_sensitive {
+            return;
+        }
+        let mut cased: HashMap<String, f32> = HashMap::new();
+        match self.cost_model {
+            CostModel::ZipfRank => {
+                for (idx, line) in lines.iter().enumerate() {
+                    let word: String = line.split_whitespace().collect();
+                    cased
+                        .entry(word)
+                        .or_insert_with(|| zipf_cost(idx, lines.len()));
+                }
+            }
+            CostModel::Frequency => {
+                let entries: Vec<(&str, f64)> = lines
+                    .iter()
+                    .filter_map(|line| frequency_entry(line))
+                    .collect();
+                let total: f64 = entries.iter().map(|(_, count)| count).sum();
+                let mut counts: HashMap<String, f64> = HashMap::new();
+                for (word, count) in entries {
+                    *counts
+                        .entry(word.split_whitespace().collect())
+                        .or_insert(0.0) += count;
+                }
+                cased.extend(
+                    counts
+                        .into_iter()
+                        .map(|(word, count)| (word, (total / count).ln() as f32)),
+                );
+            }
+        }
+        cost_dict.0.extend(cased);
+    }
+
+    /// The casing of the corpus words that are not all lowercase, see `CaseMode::Corpus`
+    fn corpus_casings(&self, lines: &[String]) -> HashMap<String, String, S> {
+        let words: Vec<&str> = lines.iter().map(|line| self.line_word(line)).collect();
+        corpus_casings(&words, self.hash_builder.clone())
+    }
+
+    // Returns the best match for a word in the corpus.
+    /// A word is considered to be a match if it is within `max_distance` of the start of the word.
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Returns
+    /// A String object containing the split text
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new("src/corpus.txt".to_string());
+    /// let text = "rustisgreat";
+    /// let result = lm.split(text);
+    /// assert_eq!(result, "rust is great");
+    /// ```
+    /// Result: "This is a test"
+    pub fn split(&mut self, text: impl AsRef<str>) -> String {
+        let text = text.as_ref();
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        self.ensure_loaded();
+        let Some(capacity) = self.cache_capacity else {
+            return self.split_traced(text, &mut Trace::new());
+        };
+        let settings = self.settings_key();
+        if let Some(split) = self.result_cache.get(text, settings) {
+            return split;
+        }
+        let split = self.split_traced(text, &mut Trace::new());
+        self.result_cache
+            .insert(text.to_string(), split.clone(), capacity);
+        split
+    }
+
+    /// Split the glued words of a paragraph: only the tokens between whitespace that are not
+    /// in the dictionary are split, the words already written apart and the whitespace and
+    /// newlines between them are kept as they are
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new("src/corpus.txt".to_string());
+    /// let text = "please visitbankofjordan today\nthanks";
+    /// assert_eq!(lm.split_text(text), "please visit bank of jordan today\nthanks");
+    /// ```
+    pub fn split_text(&mut self, text: impl AsRef<str>) -> String {
+        let text = text.as_ref();
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        self.ensure_loaded();
+        self.split_unknown_tokens(text)
+    }
+
+    /// Split a text into its words and the separators between them, so that an editor can
+    /// tell the words apart from the whitespace it inserted or kept
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The tokens in order, their texts concatenated being the text returned by `split`
+    /// # Examples
+    /// ```
+    /// use rsplitter::{LanguageModel, Token};
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let tokens = lm.split_tokens("rustis  great".to_string());
+    /// assert_eq!(tokens[1], Token::Separator(" ".to_string()));
+    /// assert_eq!(tokens[3], Token::Separator("  ".to_string()));
+    /// ```
+    pub fn split_tokens(&mut self, text: String) -> Vec<Token> {
+        self.ensure_loaded();
+        self.text_tokens(&text)
+    }
+
+    /// `split_tokens` with the loaded dictionary
+    pub(crate) fn text_tokens(&self, text: &str) -> Vec<Token> {
+        if self.is_too_long(text) {
+            return vec![Token::Word(text.to_string())];
+        }
+        let mut tokens = self.run_tokens(text, &mut Trace::new(), |run, trace| {
+            self.split_kept(run, trace)
+        });
+        case_tokens(self.case_mode, &mut tokens);
+        tokens
+    }
+
+    /// Forget the splits cached by `split` and reset the counts of `cache_stats`
+    pub fn clear_cache(&mut self) {
+        self.result_cache.clear();
+    }
+
+    /// A hash of everything a split depends on besides the text, the cached splits being
+    /// those of another key after a public field changed. The closures are told apart by
+    /// their address, the dictionary by the address of its `Arc`.
+    fn settings_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let bits = |value: Option<f32>| value.map(f32::to_bits);
+        self.cost_dict.as_ref().map(Arc::as_ptr).hash(&mut hasher);
+        std::mem::discriminant(&self.case_mode).hash(&mut hasher);
+        std::mem::discriminant(&self.backend).hash(&mut hasher);
+        std::mem::discriminant(&self.tie_break).hash(&mut hasher);
+        (self.spelling_correction, self.case_sensitive).hash(&mut hasher);
+        (self.collapse_whitespace, self.keep_cheaper_input).hash(&mut hasher);
+        self.keep_punctuation.hash(&mut hasher);
+        (self.beam_width, self.acronym_min_length, self.max_input_len).hash(&mut hasher);
+        (self.unknown_threshold, self.merge_unknown_shorter_than).hash(&mut hasher);
+        self.max_word_len.hash(&mut hasher);
+        self.length_penalty.to_bits().hash(&mut hasher);
+        (bits(self.deletion_cost), bits(self.fuzzy_cost)).hash(&mut hasher);
+        bits(self.bigram_weight).hash(&mut hasher);
+        match self.oov_policy {
+            OovPolicy::Split => 0,
+            OovPolicy::CharPenalty(char_cost) => 1 + u64::from(char_cost.to_bits()),
+            OovPolicy::KeepWhole => u64::MAX,
+        }
+        .hash(&mut hasher);
+        (&self.number_separators, &self.currency_symbols).hash(&mut hasher);
+        (&self.units, &self.separator).hash(&mut hasher);
+        if let Some(normalize) = self.normalize.as_deref() {
+            std::ptr::hash(normalize, &mut hasher);
+        }
+        if let Some(weight) = self.position_weight.as_deref() {
+            std::ptr::hash(weight, &mut hasher);
+        }
+        if let Some(cost_function) = self.cost_function.as_deref() {
+            std::ptr::hash(cost_function, &mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// How the split cache was used since the model was loaded or `clear_cache` was called
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// lm.cache_capacity = Some(1000);
+    /// lm.split("rustisgreat".to_string());
+    /// lm.split("rustisgreat".to_string());
+    /// assert_eq!(lm.cache_stats().hits, 1);
+    /// ```
+    pub fn cache_stats(&self) -> CacheStats {
+        self.result_cache.stats()
+    }
+
+    /// The cost of a tokenization of a text, the sum of the costs of its words and of the
+    /// length penalty for each: a dictionary word costs its cost, a number nothing and an
+    /// unknown word a little more than the rarest dictionary word for each of its characters.
+    /// # Arguments
+    /// * `tokens` - The words of the tokenization
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.tokenization_cost(&["rust", "is", "great"]) < lm.tokenization_cost(&["rustisgreat"]));
+    /// ```
+    pub fn tokenization_cost(&mut self, tokens: &[&str]) -> f32 {
+        self.ensure_loaded();
+        self.tokens_cost(tokens)
+    }
+
+    /// Whether a word is in the dictionary, whatever its casing unless `case_sensitive`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.contains("Jordan"));
+    /// assert!(!lm.contains("xqzwk"));
+    /// ```
+    pub fn contains(&mut self, word: &str) -> bool {
+        self.cost(word).is_some()
+    }
+
+    /// The cost of a word in the dictionary, the one the dynamic program gives it: the
+    /// cheaper the more frequent, see `CostModel`. `cost_function` decides when it is set.
+    /// # Returns
+    /// `None` for a word that is not in the dictionary
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.cost("the").unwrap() < lm.cost("jordan").unwrap());
+    /// assert_eq!(lm.cost("xqzwk"), None);
+    /// ```
+    pub fn cost(&mut self, word: &str) -> Option<f32> {
+        self.ensure_loaded();
+        if self.case_sensitive && self.cost_function.is_none() {
+            if let Some(cost) = self.dict().0.get(word) {
+                return Some(*cost);
+            }
+        }
+        match self.word_cost(&dictionary_key(word)) {
+            f32::MAX => None,
+            cost => Some(cost),
+        }
+    }
+
+    /// The probability of a word, `e^-cost`: its Zipf probability `1 / (rank * ln(words))`
+    /// under `CostModel::ZipfRank` and its share of the counts under `CostModel::Frequency`
+    /// # Returns
+    /// `None` for a word that is not in the dictionary
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.probability("the").unwrap() > lm.probability("jordan").unwrap());
+    /// ```
+    pub fn probability(&mut self, word: &str) -> Option<f32> {
+        self.cost(word).map(|cost| (-cost).exp())
+    }
+
+    /// The number of words in the dictionary, the casings of `case_sensitive` included
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert!(lm.vocab_size() > 100_000);
+    /// ```
+    pub fn vocab_size(&mut self) -> usize {
+        self.ensure_loaded();
+        self.word_count()
+    }
+
+    /// The number of words of the dictionary, or of `cost_function` if it counts them
+    fn word_count(&self) -> usize {
+        self.cost_function
+            .as_ref()
+            .and_then(|cost_function| cost_function.vocab_size())
+            .unwrap_or_else(|| self.dict().0.len())
+    }
+
+    /// The size of the cost dictionary and how its costs are spread, e.g. to compare the
+    /// models of two releases. A `cost_function` is not looked at.
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::from_bytes(b"rust\nis\ngreat\n").unwrap();
+    /// let stats = lm.dictionary_stats();
+    /// assert_eq!((stats.words, stats.max_word_len), (3, 5));
+    /// assert!(stats.min_cost < stats.max_cost);
+    /// ```
+    pub fn dictionary_stats(&mut self) -> DictionaryStats {
+        self.ensure_loaded();
+        dictionary_stats(&sorted_costs(self.dict()), self.dict().1)
+    }
+
+    /// Write the cost of every dictionary word, the cheapest first, to a file, e.g. to
+    /// audit why a split was preferred or to diff two models, see `export_to`
+    /// # Arguments
+    /// * `path` - The file written, replaced if it exists
+    /// * `format` - CSV for the costs only, JSON for the costs and the `dictionary_stats`
+    pub fn export(
+        &mut self,
+        path: impl AsRef<Path>,
+        format: ExportFormat,
+    ) -> Result<(), SplitError> {
+        self.export_to(BufWriter::new(File::create(path)?), format)
+    }
+
+    /// Write the cost of every dictionary word, the cheapest first and words of equal cost
+    /// sorted, so the output is reproducible. The words are the lowercased dictionary keys.
+    /// # Arguments
+    /// * `writer` - Where the costs are written to
+    /// * `format` - CSV for the costs only, JSON for the costs and the `dictionary_stats`
+    /// # Examples
+    /// ```
+    /// use rsplitter::{ExportFormat, LanguageModel};
+    /// let mut lm = LanguageModel::from_bytes(b"rust\nis\ngreat\n").unwrap();
+    /// let mut csv = Vec::new();
+    /// lm.export_to(&mut csv, ExportFormat::Csv).unwrap();
+    /// assert!(csv.starts_with(b"word,cost\nrust,"));
+    /// ```
+    pub fn export_to<W: Write>(
+        &mut self,
+        writer: W,
+        format: ExportFormat,
+    ) -> Result<(), SplitError> {
+        self.try_ensure_loaded()?;
+        let costs = sorted_costs(self.dict());
+        let stats = dictionary_stats(&costs, self.dict().1);
+        write_costs(writer, format, &costs, &stats)
+    }
+
+    /// Split a text like `split` into its words, without the whitespace and separators
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Returns
+    /// The words of the split, in order
+    pub fn split_words(&mut self, text: impl AsRef<str>) -> Vec<String> {
+        self.ensure_loaded();
+        self.words_of(text.as_ref())
+    }
+
+    /// `split_words` with the loaded dictionary
+    pub(crate) fn words_of(&self, text: &str) -> Vec<String> {
+        self.text_tokens(text)
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word),
+                Token::Separator(_) => None,
+            })
+            .collect()
+    }
+
+    /// Split a text like `split` into its words and their costs, from the one forward pass
+    /// of the dynamic program: the costs are those of the chosen words, nothing is split
+    /// again
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let result = lm.split_scored("rustisgreat".to_string());
+    /// assert_eq!(result.words, ["rust", "is", "great"]);
+    /// assert_eq!(result.word_costs.len(), 3);
+    /// assert_eq!(result.total_cost, lm.tokenization_cost(&["rust", "is", "great"]));
+    /// assert_eq!(result.unknown_words, 0);
+    /// ```
+    pub fn split_scored(&mut self, text: String) -> SplitResult {
+        self.ensure_loaded();
+        let words = self.words_of(&text);
+        let word_costs: Vec<f32> = words
+            .iter()
+            .map(|word| self.token_cost(word) + self.length_penalty)
+            .collect();
+        SplitResult {
+            total_cost: word_costs.iter().sum(),
+            unknown_words: words
+                .iter()
+                .filter(|word| !self.is_known(word) && !self.is_number(word))
+                .count(),
+            words,
+            word_costs,
+        }
+    }
+
+    /// The `k` cheapest splits of a text, by the cost the dynamic program minimizes, e.g. to
+    /// offer the alternatives of an ambiguous search query. Every position keeps its `k`
+    /// cheapest paths instead of one, and the runs between whitespace are combined.
+    /// The words are written as in the text: normalization, spelling and the case and
+    /// merge settings are ignored.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `k` - How many splits to return at most
+    /// # Returns
+    /// The splits with their costs, the cheapest first; the first one has the words of
+    /// `minimal_cost`, and splits with the same words are only returned once
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let splits = lm.split_top_k("rustisgreat", 3);
+    /// assert_eq!(splits.len(), 3);
+    /// assert_eq!(splits[0].0, ["rust", "is", "great"]);
+    /// assert!(splits[0].1 <= splits[1].1 && splits[1].1 <= splits[2].1);
+    /// ```
+    pub fn split_top_k(&mut self, text: &str, k: usize) -> Vec<(Vec<String>, f32)> {
+        self.ensure_loaded();
+        if k == 0 {
+            return Vec::new();
+        }
+        if self.is_too_long(text) {
+            return vec![(vec![text.to_string()], 0.0)];
+        }
+        let mut splits: Vec<(Vec<String>, f32)> = vec![(Vec::new(), 0.0)];
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            if run.is_empty() {
+                continue;
+            }
+            let run_splits = self.run_top_k(&self.prepare_run(run), k);
+            let mut combined: Vec<(Vec<String>, f32)> = Vec::with_capacity(k * run_splits.len());
+            for (words, cost) in &splits {
+                for (run_words, run_cost) in &run_splits {
+                    let mut words = words.clone();
+                    words.extend(run_words.iter().cloned());
+                    combined.push((words, cost + run_cost));
+                }
+            }
+            // Stable, so that ties keep the order of the paths
+            combined.sort_by(|a, b| a.1.total_cmp(&b.1));
+            combined.truncate(k);
+            splits = combined;
+        }
+        splits
+    }
+
+    /// Split a text like `split`, also returning how long it took and how many dictionary
+    /// lookups the dynamic program made. The lookups are counted in a second pass once the
+    /// split is timed, so that `split` itself counts nothing.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let (split, stats) = lm.split_with_stats("rustisgreat".to_string());
+    /// assert_eq!(split, "rust is great");
+    /// assert_eq!(stats.positions, 11);
+    /// assert!(stats.hit_ratio() > 0.0);
+    /// ```
+    pub fn split_with_stats(&mut self, text: String) -> (String, SplitStats) {
+        if self.is_too_long(&text) {
+            return (text, SplitStats::default());
+        }
+        self.ensure_loaded();
+        let start = Instant::now();
+        let split = self.split_traced(&text, &mut Trace::new());
+        let mut stats = SplitStats {
+            elapsed: start.elapsed(),
+            ..SplitStats::default()
+        };
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            let normalized = match &self.normalize {
+                Some(normalize) => normalize(run),
+                None => run.to_string(),
+            };
+            let run = self.prepare_run(&normalized);
+            let max_word = self.max_word(&run).max(0) as usize;
+            for i in 1..(run.length + 1) {
+                let costs = self.dictionary_costs(&run, i, i.min(max_word));
+                stats.positions += 1;
+                stats.lookups += costs.len();
+                stats.hits += costs.iter().filter(|cost| **cost != f32::MAX).count();
+            }
+        }
+        (split, stats)
+    }
+
+    /// Split a social media text: the sigil of a hashtag or a handle is dropped, e.g.
+    /// "i love rust" for "#iloverust" and "bank of jordan" for "@bank_of_jordan", and only
+    /// the runs of letters are split. Digits and emoji are words of their own, e.g.
+    /// "throwback 2019 🔥" for "#throwback2019🔥", and underscores separate words.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `keep_sigil` - Write the `#` or `@` before the first word, e.g. "#i love rust"
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split_hashtag("so #iloverust", false), "so i love rust");
+    /// assert_eq!(lm.split_hashtag("@bankofjordan", true), "@bank of jordan");
+    /// ```
+    pub fn split_hashtag(&mut self, text: &str, keep_sigil: bool) -> String {
+        self.ensure_loaded();
+        self.join_words(text, &mut Trace::new(), |run, trace| {
+            self.hashtag_words(run, keep_sigil, trace)
+        })
+    }
+
+    /// The words of a run of `split_hashtag`
+    fn hashtag_words(&self, run: &str, keep_sigil: bool, trace: &mut Trace) -> Vec<String> {
+        let (sigil, body) = strip_sigil(run);
+        let mut words = self.pieces_words(&social_pieces(body, false), trace);
+        if keep_sigil && !sigil.is_empty() {
+            match words.first_mut() {
+                Some(first) => first.insert_str(0, sigil),
+                None => words.push(sigil.to_string()),
+            }
+        }
+        words
+    }
+
+    /// Split the identifiers of source code: a run is first cut where the casing changes,
+    /// e.g. "parse HTTP Response" for "parseHTTPResponse", around digits and at underscores,
+    /// which are dropped, then the chunks in lowercase or capitalized are split with the
+    /// model, e.g. "get user id" for "get_userid". An acronym is kept as it is.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(
+    ///     lm.split_identifier("parseHTTPResponse2JSON"),
+    ///     "parse HTTP Response 2 JSON"
+    /// );
+    /// assert_eq!(lm.split_identifier("max_wordlength"), "max word length");
+    /// ```
+    pub fn split_identifier(&mut self, text: &str) -> String {
+        self.ensure_loaded();
+        self.join_words(text, &mut Trace::new(), |run, trace| {
+            self.identifier_words(run, trace)
+        })
+    }
+
+    /// The words of a run of `split_identifier`
+    fn identifier_words(&self, run: &str, trace: &mut Trace) -> Vec<String> {
+        self.pieces_words(&social_pieces(run, true), trace)
+    }
+
+    /// The words of the pieces of a run, the `PreToken::Split` ones split with
+    /// `split_normalized`; the separators are left out
+    fn pieces_words(&self, pieces: &[PreToken], trace: &mut Trace) -> Vec<String> {
+        let mut words = Vec::new();
+        for piece in pieces {
+            match piece {
+                PreToken::Split(piece) => words.extend(self.split_normalized(piece, trace)),
+                PreToken::Keep(piece) => words.push(piece.to_string()),
+                PreToken::Separator(_) => {}
+            }
+        }
+        words
+    }
+
+    /// Split a text cut into pieces by a `PreTokenizer` first: the `PreToken::Split` pieces
+    /// are split with the dictionary like the runs of `split`, the `PreToken::Keep` ones are
+    /// words as they are and the separators are written between the words instead of
+    /// `separator`. A text longer than `max_input_len` is returned unchanged, like by `split`.
+    /// # Arguments
+    /// * `tokenizer` - The pre-tokenizer, e.g. `Identifier` or one of a new domain
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Examples
+    /// ```
+    /// use rsplitter::{Alphabetic, Hashtag, Identifier, LanguageModel};
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split_with(&Identifier, "parseHTTPResponse"), "parse HTTP Response");
+    /// assert_eq!(lm.split_with(&Hashtag, "#iloverust"), "i love rust");
+    /// assert_eq!(lm.split_with(&Alphabetic, "rustv2isgreat"), "rust v 2 is great");
+    /// ```
+    pub fn split_with<T: PreTokenizer + ?Sized>(
+        &mut self,
+        tokenizer: &T,
+        text: impl AsRef<str>,
+    ) -> String {
+        let text = text.as_ref();
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        self.ensure_loaded();
+        let mut trace = Trace::new();
+        let mut result = String::new();
+        // Whether the next word follows a separator, or the start of the text
+        let mut separated = true;
+        for piece in tokenizer.pre_tokenize(text) {
+            let words = match piece {
+                PreToken::Separator(separator) => {
+                    result.push_str(separator);
+                    separated = true;
+                    continue;
+                }
+                PreToken::Split(run) => self.split_kept(run, &mut trace),
+                PreToken::Keep(word) => vec![word.to_string()],
+            };
+            for word in words {
+                if !separated {
+                    result.push_str(&self.separator);
+                }
+                result.push_str(&word);
+                separated = false;
+            }
+        }
+        apply_case_mode(self.case_mode, result)
+    }
+
+    /// Split the name of a domain, e.g. "choose spain" for "https://www.choosespain.com/".
+    /// The scheme, the port and the path of a URL are dropped, the public suffix and the
+    /// subdomains are set apart and every label is split on its own, its hyphens dropped.
+    /// A punycode label is decoded first, e.g. "bücher" for "xn--bcher-kva". An IP address,
+    /// e.g. "192.168.0.1" or "[::1]", is the host alone, with no word and no suffix.
+    /// # Arguments
+    /// * `url` - A URL or a host name
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let domain = lm.split_domain("https://www.choosespain.com/about");
+    /// assert_eq!(domain.name(), "choose spain");
+    /// assert_eq!(domain.subdomains, [["www"]]);
+    /// assert_eq!(domain.suffix, "com");
+    /// ```
+    pub fn split_domain(&mut self, url: &str) -> DomainSplit {
+        self.ensure_loaded();
+        self.domain_of(url)
+    }
+
+    /// `split_domain` with a loaded model
+    pub(crate) fn domain_of(&self, url: &str) -> DomainSplit {
+        let host = host_of(url);
+        if is_ip_literal(host) {
+            return DomainSplit {
+                host: host.to_lowercase(),
+                ..DomainSplit::default()
+            };
+        }
+        let mut labels: Vec<String> = host
+            .split('.')
+            .filter(|label| !label.is_empty())
+            .map(decode_label)
+            .collect();
+        let host = labels.join(".");
+        let suffix = labels
+            .split_off(labels.len() - suffix_length(&labels))
+            .join(".");
+        let label_words = |label: &str| {
+            label
+                .split('-')
+                .flat_map(|part| self.words_of(part))
+                .collect::<Vec<String>>()
+        };
+        let words = labels
+            .pop()
+            .map(|name| label_words(&name))
+            .unwrap_or_default();
+        DomainSplit {
+            host,
+            subdomains: labels.iter().map(|label| label_words(label)).collect(),
+            words,
+            suffix,
+        }
+    }
+
+    /// Split a text like `split` and write every word lowercase, whatever `case_mode`,
+    /// e.g. for a bag of words
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split_lower("RustIsGreat".to_string()), "rust is great");
+    /// ```
+    pub fn split_lower(&mut self, text: String) -> String {
+        self.ensure_loaded();
+        self.join_words(&text, &mut Trace::new(), |run, trace| {
+            let words = self.split_normalized(run, trace);
+            words.iter().map(|word| word.to_lowercase()).collect()
+        })
+    }
+
+    /// Split a text like `split`, rejecting a text longer than `max_input_len` and reporting
+    /// a corpus that cannot be loaded instead of panicking like `split`
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text, `SplitError::InputTooLong`, or the error of `try_load`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// lm.max_input_len = Some(8);
+    /// assert_eq!(lm.try_split("isgreat".to_string()).unwrap(), "is great");
+    /// assert!(lm.try_split("rustisgreat".to_string()).is_err());
+    /// ```
+    pub fn try_split(&mut self, text: String) -> Result<String, SplitError> {
+        if let Some(limit) = self.max_input_len.filter(|_| self.is_too_long(&text)) {
+            return Err(SplitError::InputTooLong {
+                length: text.len(),
+                limit,
+            });
+        }
+        self.try_load()?;
+        Ok(self.split_traced(&text, &mut Trace::new()))
+    }
+
+    /// Whether a text is longer than `max_input_len`
+    pub(crate) fn is_too_long(&self, text: &str) -> bool {
+        self.max_input_len.is_some_and(|limit| text.len() > limit)
+    }
+
+    /// Split a text greedily: from left to right, take the longest dictionary word
+    /// starting at the current position, or a single character when none does.
+    /// Much faster than `split` but it never backtracks, so a long word swallowing the start
+    /// of the next one wins, e.g. "theyoungman" becomes "they ou ng man".
+    /// The numbers, acronyms, beam and spelling settings are ignored.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text, whitespace is kept as it is like for `split`, or the text unchanged
+    /// if it is longer than `max_input_len`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split_greedy("rustisgreat".to_string()), "rust is great");
+    /// ```
+    pub fn split_greedy(&mut self, text: String) -> String {
+        if self.is_too_long(&text) {
+            return text;
+        }
+        self.ensure_loaded();
+        self.greedy_text(&text)
+    }
+
+    /// Split a text with a Viterbi decoder over the word pairs of `load_bigrams`.
+    /// A word after another one costs the lower of its dictionary cost and its cost given
+    /// the word before it, so a pair seen in the bigram file can beat a split made of cheaper
+    /// words. Without word pairs, it is the split of `split` but for the unknown words, which
+    /// cost a little more than the rarest word for each of their characters.
+    /// The beam and spelling settings are ignored.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text, whitespace is kept as it is like for `split`
+    pub fn split_bigram(&mut self, text: String) -> String {
+        self.ensure_loaded();
+        self.bigram_text(&text)
+    }
+
+    /// Split a text into dictionary words only, without any unknown fallback.
+    /// Among the splits made only of dictionary words, the cheapest one is returned.
+    /// Unlike `split`, an unknown fragment is never cut into single characters and a number
+    /// is not kept whole unless it is a dictionary word, e.g. to check that a product code
+    /// is made of known parts.
+    /// # Arguments
+    /// * `text` - The text to be split, whitespace separates words
+    /// # Returns
+    /// The words, or `None` when some part of the text is not a sequence of dictionary words
+    /// or the text is longer than `max_input_len`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(
+    ///     lm.split_strict("rustisgreat".to_string()),
+    ///     Some(vec!["rust".to_string(), "is".to_string(), "great".to_string()])
+    /// );
+    /// assert_eq!(lm.split_strict("rustisgreat!".to_string()), None);
+    /// ```
+    pub fn split_strict(&mut self, text: String) -> Option<Vec<String>> {
+        if self.is_too_long(&text) {
+            return None;
+        }
+        self.ensure_loaded();
+        let mut words = Vec::new();
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            words.extend(self.strict_words(run)?);
+        }
+        Some(words)
+    }
+
+    /// Split a text with a tighter bound on the word length for this call only, e.g. for a
+    /// text known to have no long words. Numbers and acronyms are still kept whole.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `max_word` - The longest word considered, in characters, at least 1
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let split = lm.split_bounded("rustisgreat".to_string(), 3);
+    /// assert!(split.split(' ').all(|word| word.len() <= 3));
+    /// ```
+    pub fn split_bounded(&mut self, text: String, max_word: i32) -> String {
+        self.ensure_loaded();
+        let max_word = max_word.max(1);
+        self.join_runs(&text, &mut Trace::new(), |run, trace| {
+            let mut run = self.prepare_run(run);
+            run.max_word = Some(max_word);
+            self.split_run(run, trace)
+        })
+    }
+
+    /// Split a text with limits on the work of this call only: the inner loop of the dynamic
+    /// program looks at `max_word_len` candidates per character, the longest dictionary word
+    /// by default, and a beam search bounds the hypotheses kept. Numbers and acronyms are
+    /// still kept whole. The split cache is not used.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `limits` - The limits, the ones left out being those of the model
+    /// # Examples
+    /// ```
+    /// use rsplitter::{LanguageModel, SplitLimits};
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let limits = SplitLimits {
+    ///     max_word_len: Some(12),
+    ///     beam_width: Some(8),
+    /// };
+    /// assert_eq!(lm.split_limited("thequickbrownfox", limits), "the quick brown fox");
+    /// ```
+    pub fn split_limited(&mut self, text: impl AsRef<str>, limits: SplitLimits) -> String {
+        self.ensure_loaded();
+        let text = text.as_ref();
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        self.join_runs(text, &mut Trace::new(), |run, trace| {
+            let mut run = self.prepare_run(run);
+            if let Some(max_word_len) = limits.max_word_len {
+                run.max_word = Some(max_word_len.max(1) as i32);
+            }
+            run.beam_width = limits.beam_width;
+            self.split_run(run, trace)
+        })
+    }
+
+    /// Compute the costs of the dynamic program over a prefix once, for `split_with_prefix`
+    /// to reuse with every text starting with it, e.g. the URLs of one host.
+    /// The costs hold for the current dictionary and settings only. A prefix with
+    /// whitespace or longer than `max_input_len` is never reused.
+    /// # Arguments
+    /// * `prefix` - The prefix shared by the texts
+    pub fn prefix_costs(&mut self, prefix: &str) -> PrefixCosts {
+        self.ensure_loaded();
+        let lattice =
+            if prefix.contains(|c: char| c.is_ascii_whitespace()) || self.is_too_long(prefix) {
+                Lattice {
+                    cost: Vec::new(),
+                    back: Vec::new(),
+                }
+            } else {
+                self.build_cost_array(&self.prepare_run(prefix))
+            };
+        PrefixCosts {
+            prefix: prefix.to_string(),
+            spans: self.prepare_run(prefix).spans,
+            lattice,
+        }
+    }
+
+    /// Split a text like `split`, starting from the costs of its prefix when it has it
+    /// # Arguments
+    /// * `prefix` - The costs of a prefix, see `prefix_costs`
+    /// * `text` - The whole text to be split, prefix included
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let prefix = lm.prefix_costs("examplecom");
+    /// let text = "examplecomfoo".to_string();
+    /// assert_eq!(lm.split_with_prefix(&prefix, text), "example com foo");
+    /// ```
+    pub fn split_with_prefix(&mut self, prefix: &PrefixCosts, text: String) -> String {
+        self.ensure_loaded();
+        let first = std::cell::Cell::new(true);
+        self.join_runs(&text, &mut Trace::new(), |run, trace| {
+            let run = self.prepare_run(run);
+            if first.replace(false) && self.beam_width.is_none() && prefix.applies_to(&run) {
+                self.split_run_from(run, trace, prefix.lattice.clone())
+            } else {
+                self.split_run(run, trace)
+            }
+        })
+    }
+
+    /// Split a text with the chosen algorithm
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `algorithm` - How the split is searched
+    /// # Examples
+    /// ```
+    /// use rsplitter::{Algorithm, LanguageModel};
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let text = "rustisgreat".to_string();
+    /// assert_eq!(lm.split_with_algorithm(text, Algorithm::Greedy), "rust is great");
+    /// ```
+    pub fn split_with_algorithm(&mut self, text: String, algorithm: Algorithm) -> String {
+        self.ensure_loaded();
+        match algorithm {
+            Algorithm::OptimalDp => self.split_traced(&text, &mut Trace::new()),
+            Algorithm::Greedy => self.greedy_text(&text),
+            Algorithm::Bigram => self.bigram_text(&text),
+        }
+    }
+
+    /// Split a text into its words, each with the slice of the text it comes from
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The words of the split, as `split` writes them, and their slices of the text, in the
+    /// same order. A slice runs up to the next word, so the whitespace and the dropped
+    /// characters after a word are in its slice and the slices concatenated give back the
+    /// text exactly. A run whose words do not have its length, e.g. after a spelling
+    /// correction, is the slice of its first word, the others having empty slices.
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let (words, slices) = lm.split_retaining_case_map("RustIs great");
+    /// assert_eq!(words, ["Rust", "Is", "great"]);
+    /// assert_eq!(slices, ["Rust", "Is ", "great"]);
+    /// ```
+    pub fn split_retaining_case_map<'a>(&mut self, text: &'a str) -> (Vec<String>, Vec<&'a str>) {
+        if self.is_too_long(text) {
+            return (vec![text.to_string()], vec![text]);
+        }
+        self.ensure_loaded();
+        let mut words = Vec::new();
+        let mut starts = Vec::new();
+        let mut run_start = 0;
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            let run_words = self.split_normalized(run, &mut Trace::new());
+            let bounds: Vec<usize> = run
+                .char_indices()
+                .map(|(idx, _)| idx)
+                .chain([run.len()])
+                .collect();
+            let length: usize = run_words.iter().map(|word| word.chars().count()).sum();
+            let mut chars = 0;
+            for (position, word) in run_words.iter().enumerate() {
+                let start = match (length + 1 == bounds.len(), position) {
+                    (true, _) => bounds[chars],
+                    (false, 0) => 0,
+                    (false, _) => run.len(),
+                };
+                starts.push(run_start + start);
+                chars += word.chars().count();
+            }
+            words.extend(run_words);
+            // The runs are separated by a single ASCII whitespace byte
+            run_start += run.len() + 1;
+        }
+        if let Some(first) = starts.first_mut() {
+            // The whitespace before the first word belongs to it
+            *first = 0;
+        }
+        let ends = starts.iter().skip(1).copied().chain([text.len()]);
+        let slices = starts
+            .iter()
+            .zip(ends)
+            .map(|(start, end)| &text[*start..end])
+            .collect();
+        (words, slices)
+    }
+
+    /// Load the model and share it behind an `Arc`, e.g. across the workers of a server:
+    /// the handle clones in constant time and splits with `&self`, see `SharedModel`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let shared = LanguageModel::new(String::new()).into_shared();
+    /// let handles: Vec<_> = ["rustisgreat", "throwbackthursday"]
+    ///     .into_iter()
+    ///     .map(|text| {
+    ///         let model = shared.clone();
+    ///         std::thread::spawn(move || model.split(text))
+    ///     })
+    ///     .collect();
+    /// let splits: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    /// assert_eq!(splits, ["rust is great", "throwback thursday"]);
+    /// ```
+    pub fn into_shared(mut self) -> SharedModel<S> {
+        self.ensure_loaded();
+        SharedModel::new(self)
+    }
+
+    /// The words of a split as slices of the text, like `split_ref`, yielded lazily: a run
+    /// between whitespace is split when its first word is reached, so stopping early skips
+    /// the runs after it, see `SplitIter`
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let text = "thequickbrownfox jumpsoverthelazydog";
+    /// let words: Vec<&str> = lm.split_iter(text).take(3).collect();
+    /// assert_eq!(words, ["the", "quick", "brown"]);
+    /// assert_eq!(lm.split_iter(text).count(), 9);
+    /// ```
+    pub fn split_iter<'t>(&mut self, text: &'t str) -> SplitIter<'_, 't, S> {
+        self.ensure_loaded();
+        SplitIter::new(self, text)
+    }
+
+    /// Split a stream of characters, e.g. a huge OCR output without whitespace, into its
+    /// words as they arrive, holding only a window of the text at a time, see `SplitStream`
+    /// # Arguments
+    /// * `chars` - The characters of the text
+    /// # Returns
+    /// An iterator over the words of the text, the ones of `split_words` but for the
+    /// words of a long run that straddle the window
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let text = "thequickbrownfoxjumpsoverthelazydog".repeat(1000);
+    /// let mut words = lm.split_stream(text.chars());
+    /// assert_eq!(words.next().unwrap(), "the");
+    /// assert_eq!(words.count(), 9 * 1000 - 1);
+    /// ```
+    pub fn split_stream<I: IntoIterator<Item = char>>(
+        &mut self,
+        chars: I,
+    ) -> SplitStream<'_, I::IntoIter, S> {
+        self.ensure_loaded();
+        SplitStream::new(self, chars.into_iter())
+    }
+
+    /// The longest word the dynamic program considers, in characters
+    pub(crate) fn longest_candidate(&self) -> usize {
+        self.max_word_len.unwrap_or(self.dict().1.max(0) as usize)
+    }
+
+    /// The byte offsets of the words of a split in the text
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Returns
+    /// The `(start, end)` byte offsets of every word of `split_words`, a word that is not in
+    /// the text, e.g. a spelling correction, spanning the characters it replaces
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.split_spans("rust isgreat"), [(0, 4), (5, 7), (7, 12)]);
+    /// ```
+    pub fn split_spans(&mut self, text: impl AsRef<str>) -> Vec<(usize, usize)> {
+        self.ensure_loaded();
+        self.word_spans(text.as_ref())
+    }
+
+    /// The byte ranges of the words of a split, see `split_spans`, to slice them from the
+    /// text without allocating them.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Panics
+    /// If the model is not loaded, unlike `split_spans` which loads it
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// let text = "rust isgreat";
+    /// let ranges = lm.split_ranges(text);
+    /// assert_eq!(ranges, [0..4, 5..7, 7..12]);
+    /// assert_eq!(&text[ranges[2].clone()], "great");
+    /// ```
+    pub fn split_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.word_spans(text)
+            .into_iter()
+            .map(|(start, end)| start..end)
+            .collect()
+    }
+
+    /// The words of a split as slices of the text, without allocating them.
+    /// The words are the ones of `split_spans`, as written in the text: phrases, pins and
+    /// every setting apply like in `split`, but not `case_mode`.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Panics
+    /// If the model is not loaded, unlike `split_words` which loads it
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// assert_eq!(lm.split_ref("Rustisgreat"), ["Rust", "is", "great"]);
+    /// ```
+    pub fn split_ref<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        self.word_spans(text)
+            .into_iter()
+            .map(|(start, end)| &text[start..end])
+            .collect()
+    }
+
+    /// The byte offsets of the words of a split, see `split_spans`
+    pub(crate) fn word_spans(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans = Vec::new();
+        if self.is_too_long(text) {
+            // The text is a single word, like in `text_tokens`
+            spans.extend((!text.is_empty()).then_some((0, text.len())));
+            return spans;
+        }
+        let mut start = 0;
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            self.run_spans(run, start, &mut spans);
+            // The runs are separated by a single ASCII whitespace byte
+            start += run.len() + 1;
+        }
+        spans
+    }
+
+    /// Push the byte offsets of the words of a run without whitespace starting at `start`,
+    /// the words `split` gives the run
+    pub(crate) fn run_spans(
+        &self,
+        run: &str,
+        start: usize,
+        spans: &mut impl Extend<(usize, usize)>,
+    ) {
+        let words: Vec<String> = self
+            .split_kept(run, &mut Trace::new())
+            .into_iter()
+            .filter(|word| !word.is_empty())
+            .collect();
+        let lengths: Vec<usize> = words.iter().map(|word| word.chars().count()).collect();
+        let mut bounds = run.char_indices().map(|(idx, _)| idx).chain([run.len()]);
+        if lengths.iter().sum::<usize>() == run.chars().count() {
+            // Every character of the run is in a word, whatever its case
+            let mut end = bounds.next().unwrap_or(0);
+            for length in lengths {
+                let word_start = end;
+                end = bounds.nth(length - 1).unwrap_or(run.len());
+                spans.extend([(start + word_start, start + end)]);
+            }
+            return;
+        }
+        spans.extend(
+            self.matched_spans(run, &words)
+                .into_iter()
+                .map(|(word_start, word_end)| (start + word_start, start + word_end)),
+        );
+    }
+
+    /// The byte offsets in a run of its words when they are not all its characters: the
+    /// characters dropped by `deletion_cost` are skipped, and a word that is not in the run
+    /// whatever its case, e.g. a spelling correction or a word changed by `normalize`,
+    /// spans the characters up to the next word
+    fn matched_spans(&self, run: &str, words: &[String]) -> Vec<(usize, usize)> {
+        let mut matched: Vec<Option<(usize, usize)>> = Vec::with_capacity(words.len());
+        let mut end = 0;
+        for word in words {
+            // A word after one that is not in the run starts anywhere after it
+            let skips = self.deletion_cost.is_some() || matched.last().is_some_and(Option::is_none);
+            let found = run.char_indices().map(|(idx, _)| idx).find_map(|idx| {
+                if idx < end || (idx > end && !skips) {
+                    return None;
+                }
+                matched_len(&run[idx..], word).map(|len| (idx, idx + len))
+            });
+            if let Some((_, word_end)) = found {
+                end = word_end;
+            }
+            matched.push(found);
+        }
+        let mut spans = Vec::with_capacity(words.len());
+        let mut end = 0;
+        for (idx, span) in matched.iter().enumerate() {
+            let span = span.unwrap_or_else(|| {
+                let next = matched[idx + 1..]
+                    .iter()
+                    .find_map(|span| span.map(|(start, _)| start))
+                    .unwrap_or(run.len());
+                (end, next)
+            });
+            spans.push(span);
+            end = span.1;
+        }
+        spans
+    }
+
+    /// The character offsets of the words of a split in the text, see `split_spans`
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The `(start, end)` code point offsets of every word, the same as the byte offsets of
+    /// `split_spans` for an ASCII text
+    pub fn split_char_spans(&mut self, text: impl AsRef<str>) -> Vec<(usize, usize)> {
+        let text = text.as_ref();
+        let spans = self.split_spans(text);
+        char_spans(text, &spans)
+    }
+
+    /// Split a text, also returning the spelling corrections that were applied
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text and the corrections, empty unless `spelling_correction` is enabled
+    pub fn split_with_corrections(&mut self, text: String) -> (String, Vec<Correction>) {
+        self.ensure_loaded();
+        let mut trace = Trace::new();
+        let result = self.split_traced(&text, &mut trace);
+        (result, trace.corrections)
+    }
+
+    /// Split a text, also returning whether the model recognized all of it
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text and whether every word of it is in the dictionary or a number
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let (split, covered) = lm.split_with_coverage("rustisgreat".to_string());
+    /// assert_eq!(split, "rust is great");
+    /// assert!(covered);
+    /// ```
+    pub fn split_with_coverage(&mut self, text: String) -> (String, bool) {
+        self.ensure_loaded();
+        let mut trace = Trace::new();
+        let result = self.split_traced(&text, &mut trace);
+        (result, trace.unknown_chars == 0)
+    }
+
+    /// Count the characters of a text that end up in words the model does not know, the
+    /// words that make `split_with_coverage` report an incomplete coverage
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.oov_char_count("rustisgreat".to_string()), 0);
+    /// ```
+    pub fn oov_char_count(&mut self, text: String) -> usize {
+        self.ensure_loaded();
+        let mut trace = Trace::new();
+        self.split_traced(&text, &mut trace);
+        trace.unknown_chars
+    }
+
+    /// Split a text, also returning the cost array of the dynamic program
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The split text and the accumulated cost of every prefix of the text, one entry per
+    /// character plus the empty prefix; whitespace adds nothing to the cost
+    pub fn split_debug(&mut self, text: String) -> (String, Vec<f32>) {
+        self.ensure_loaded();
+        let mut trace = Trace::new();
+        let result = self.split_traced(&text, &mut trace);
+        (result, trace.cost)
+    }
+
+    /// Split a text and group its words into sentences
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// * `rules` - When a sentence ends, see `SentenceRules`
+    /// # Returns
+    /// A Vec of sentences, each sentence being a Vec of words
+    /// # Examples
+    /// ```
+    /// use rsplitter::{LanguageModel, SentenceRules};
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let sentences = lm.split_sentences("Rustisgreat.Pythonisslow".to_string(), &SentenceRules::default());
+    /// assert_eq!(sentences.len(), 2);
+    /// ```
+    pub fn split_sentences(&mut self, text: String, rules: &SentenceRules) -> Vec<Vec<String>> {
+        self.ensure_loaded();
+        let words = self.text_words(&text, &mut Trace::new());
+        group_sentences(words, rules, |word| self.is_known(word))
+    }
+
+    /// Split a text into words at the Unicode word boundaries first, then split the words
+    /// that are glued together.
+    /// Whitespace and punctuation between words are dropped and the boundaries given by the
+    /// Unicode word segmentation are always kept. Only the words made of ASCII letters go
+    /// through the dynamic program, the other ones, e.g. "can't", "3.14" or "мир", are kept
+    /// as they are.
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// The words of the text
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let words = lm.split_unicode_words("Rustisgreat, Привет мир!");
+    /// assert_eq!(words, ["Rust", "is", "great", "Привет", "мир"]);
+    /// ```
+    pub fn split_unicode_words(&mut self, text: &str) -> Vec<String> {
+        self.ensure_loaded();
+        self.split_unicode_text(text)
+    }
+
+    pub(crate) fn split_unicode_text(&self, text: &str) -> Vec<String> {
+        let mut trace = Trace::new();
+        let too_long = self.is_too_long(text);
+        unicode_words(text)
+            .into_iter()
+            .flat_map(|word| {
+                if !too_long && word.bytes().all(|b| b.is_ascii_alphabetic()) {
+                    self.text_words(word, &mut trace)
+                } else {
+                    vec![word.to_string()]
+                }
+            })
+            .collect()
+    }
+
+    /// Count the words of a split without joining them
+    /// # Arguments
+    /// * `text` - The text to be split
+    /// # Returns
+    /// How many words `split_words` would return, every setting of the split applied
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// assert_eq!(lm.segment_count("rustisgreat".to_string()), 3);
+    /// ```
+    pub fn segment_count(&mut self, text: String) -> usize {
+        self.ensure_loaded();
+        self.count_words(&text)
+    }
+
+    /// `segment_count` with the loaded dictionary: the words of `split_words` are counted,
+    /// phrases, pins and the handling of punctuation and unknown words included
+    pub(crate) fn count_words(&self, text: &str) -> usize {
+        self.text_tokens(text)
+            .iter()
+            .filter(|token| matches!(token, Token::Word(_)))
+            .count()
+    }
+
+    /// List, at every position of a text, the candidate words the dynamic program considered
+    /// # Arguments
+    /// * `text` - The text to be explained
+    /// # Returns
+    /// One `PositionExplanation` per non-whitespace character
+    pub fn explain(&mut self, text: String) -> Vec<PositionExplanation> {
+        self.ensure_loaded();
+        self.explain_text(&text)
+    }
+
+    /// Split a text and list the whole lattice of the dynamic program with it, to inspect
+    /// why a split came out as it did: every candidate word at every position, its cost
+    /// and the one chosen, like `explain`
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let debug = lm.debug_split("rustisgreat");
+    /// assert_eq!(debug.split, "rust is great");
+    /// let last = debug.lattice.last().unwrap();
+    /// assert_eq!(last.candidates[last.winner].word, "great");
+    /// ```
+    pub fn debug_split(&mut self, text: impl AsRef<str>) -> DebugSplit {
+        let text = text.as_ref();
+        self.ensure_loaded();
+        DebugSplit {
+            split: self.split_traced(text, &mut Trace::new()),
+            lattice: self.explain_text(text),
+        }
+    }
+
+    pub(crate) fn explain_text(&self, text: &str) -> Vec<PositionExplanation> {
+        let mut explanations = Vec::new();
+        if self.is_too_long(text) {
+            return explanations;
+        }
+        let mut offset = 0;
+        for run in text.split(|c: char| c.is_ascii_whitespace()) {
+            let run = self.prepare_run(run);
+            let lattice = self.build_cost_array(&run);
+            for i in 1..(run.length + 1) {
+                let scored = self.candidates(i, &run, &lattice.cost);
+                // A number or acronym candidate comes last, see `candidates`
+                let span = run.span_through(i);
+                let words = scored.len() - span.is_some() as usize;
+                let mut word_costs = self.candidate_costs(&run, i, words);
+                word_costs.extend(span.map(|(_, span_cost)| span_cost));
+                let winner = (0..scored.len())
+                    .min_by(|a, b| self.compare_candidates(&scored[*a], &scored[*b]))
+                    .unwrap();
+                let candidates = scored
+                    .iter()
+                    .zip(word_costs)
+                    .map(|((total_cost, k), word_cost)| Candidate {
+                        word: run.slice(i - *k as usize, i).to_string(),
+                        word_cost,
+                        total_cost: *total_cost,
+                    })
+                    .collect();
+                explanations.push(PositionExplanation {
+                    position: offset + i,
+                    candidates,
+                    winner,
+                });
+            }
+            offset += run.length + 1;
+        }
+        explanations
+    }
+
+    /// Check a split made elsewhere against the dictionary
+    /// # Arguments
+    /// * `segments` - The words of the split
+    /// # Returns
+    /// Whether every segment is in the dictionary or shorter than `unknown_threshold`.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Panics
+    /// If the model is not loaded
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// assert!(lm.is_valid_segmentation(&["rust", "is", "great"]));
+    /// assert!(!lm.is_valid_segmentation(&["rustis", "great"]));
+    /// ```
+    pub fn is_valid_segmentation(&self, segments: &[&str]) -> bool {
+        segments.iter().all(|segment| {
+            segment.chars().count() < self.unknown_threshold || self.is_known(segment)
+        })
+    }
+
+    /// Compare the splits of glued texts with their correctly spaced references.
+    /// A boundary is the character position of the end of a word, the last one excluded.
+    /// Without any boundary to predict or to find, the precision and recall are 1.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Arguments
+    /// * `pairs` - The `(glued text, reference)` pairs
+    /// # Panics
+    /// If the model is not loaded
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// let report = lm.evaluate(&[("rustisgreat", "rust is great")]);
+    /// assert_eq!(report.f1, 1.0);
+    /// ```
+    pub fn evaluate(&self, pairs: &[(&str, &str)]) -> EvalReport {
+        let (mut found, mut predicted, mut expected, mut exact) = (0, 0, 0, 0);
+        for (text, reference) in pairs {
+            let words = self.text_words(text, &mut Trace::new());
+            let reference: Vec<&str> = reference.split_whitespace().collect();
+            let (split_boundaries, reference_boundaries) =
+                (word_boundaries(&words), word_boundaries(&reference));
+            found += split_boundaries
+                .iter()
+                .filter(|boundary| reference_boundaries.contains(boundary))
+                .count();
+            predicted += split_boundaries.len();
+            expected += reference_boundaries.len();
+            exact += usize::from(words == reference);
+        }
+        let ratio = |part: usize, whole: usize| match whole {
+            0 => 1.0,
+            _ => part as f64 / whole as f64,
+        };
+        let (precision, recall) = (ratio(found, predicted), ratio(found, expected));
+        let f1 = match precision + recall {
+            sum if sum > 0.0 => 2.0 * precision * recall / sum,
+            _ => 0.0,
+        };
+        EvalReport {
+            precision,
+            recall,
+            f1,
+            exact_match: ratio(exact, pairs.len()),
+        }
+    }
+
+    /// Check that the model splits the concatenation of words back into the same words,
+    /// e.g. in a test of a corpus extended with domain words.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Arguments
+    /// * `words` - The known segmentation, glued together before the split
+    /// # Returns
+    /// The words of the split if they differ from `words`
+    /// # Panics
+    /// If the model is not loaded
+    /// # Examples
+    /// ```
+    /// use rsplitter::LanguageModel;
+    /// let lm = LanguageModel::from_corpus(String::new()).unwrap();
+    /// assert_eq!(lm.verify(&["rust", "is", "great"]), Ok(()));
+    /// assert!(lm.verify(&["rus", "tis", "great"]).is_err());
+    /// ```
+    pub fn verify<W: AsRef<str>>(&self, words: &[W]) -> Result<(), Vec<String>> {
+        let text: String = words.iter().map(AsRef::as_ref).collect();
+        let split = self.text_words(&text, &mut Trace::new());
+        if split
+            .iter()
+            .map(String::as_str)
+            .eq(words.iter().map(AsRef::as_ref))
+        {
+            Ok(())
+        } else {
+            Err(split)
+        }
+    }
+
+    /// A proptest strategy generating segmentations of the most frequent words of the
+    /// dictionary, for `verify` to check, e.g.
+    /// `proptest!(|(words in lm.word_strategy(1000, 2..6))| assert_eq!(lm.verify(&words), Ok(())))`.
+    /// The words are sampled uniformly among the `top` cheapest ones.
+    /// The model must be loaded, e.g. with `from_corpus` or `try_load`.
+    /// # Arguments
+    /// * `top` - How many of the cheapest words are sampled from, all of them if larger
+    /// * `count` - The range of the number of words of a segmentation
+    /// # Panics
+    /// If the dictionary is empty
+    #[cfg(feature = "proptest")]
+    pub fn word_strategy(
+        &self,
+        top: usize,
+        count: impl Into<proptest::collection::SizeRange>,
+    ) -> impl proptest::strategy::Strategy<Value = Vec<String>> {
+        let mut words: Vec<(&String, &f32)> = self.dict().0.iter().collect();
+        words.sort_by(|a, b| a.1.total_cmp(b.1).then(a.0.cmp(b.0)));
+        let words: Vec<String> = words
+            .into_iter()
+            .take(top)
+            .map(|(word, _)| word.clone())
+            .collect();
+        proptest::collection::vec(proptest::sample::select(words), count)
+    }
+
+    /// Whether a word is in the dictionary
+    fn is_known(&self, word: &str) -> bool {
+        self.dict().0.contains_key(&dictionary_key(word))
+    }
+
+    /// Whether a word is a number kept whole by the dynamic program, e.g. "$1,000"
+    fn is_number(&self, word: &str) -> bool {
+        word.contains(|c: char| c.is_ascii_digit())
+            && word.chars().all(|c| {
+                c.is_ascii_digit()
+                    || self.number_separators.contains(&c)
+                    || self.currency_symbols.contains(&c)
+            })
+    }
+
+    /// Split a text with the loaded dictionary.
+    /// ASCII whitespace is a hard boundary: only the runs between whitespace go through the
+    /// dynamic program, the whitespace itself is emitted verbatim.
+    pub(crate) fn split_traced(&self, text: &str, trace: &mut Trace) -> String {
+        self.join_runs(text, trace, |run, trace| self.split_kept(run, trace))
+    }
+
+    /// `split_traced` keeping the runs that are dictionary words, see `split_text`
+    pub(crate) fn split_unknown_tokens(&self, text: &str) -> String {
+        self.join_runs(text, &mut Trace::new(), |run, trace| {
+            if self.is_known(run) {
+                vec![run.to_string()]
+            } else {
+                self.split_kept(run, trace)
+            }
+        })
+    }
+
+    /// Split a run with `split_normalized`, or keep it with `keep_cheaper_input`
+    fn split_kept(&self, run: &str, trace: &mut Trace) -> Vec<String> {
+        let words = if self.keep_punctuation {
+            self.split_letters(run, trace)
+        } else {
+            self.split_normalized(run, trace)
+        };
+        if self.keep_cheaper_input
+            && !run.is_empty()
+            && self.tokens_cost(&[run]) <= self.tokens_cost(&words)
+        {
+            return vec![run.to_string()];
+        }
+        words
+    }
+
+    /// Split the runs of letters of a run with `split_normalized`, the other characters glued
+    /// to the words next to them, see `keep_punctuation`
+    fn split_letters(&self, run: &str, trace: &mut Trace) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        // Whether the last word ends with a character kept as it is
+        let mut glued = false;
+        let mut start = 0;
+        while start < run.len() {
+            let letters = run[start..].starts_with(char::is_alphabetic);
+            let end = run[start..]
+                .find(|c: char| c.is_alphabetic() != letters)
+                .map_or(run.len(), |end| start + end);
+            let piece = &run[start..end];
+            if letters {
+                let mut split = self.split_normalized(piece, trace).into_iter();
+                if let (Some(last), true) = (words.last_mut(), glued) {
+                    last.push_str(&split.next().unwrap_or_default());
+                }
+                words.extend(split);
+                glued = false;
+            } else {
+                match words.last_mut() {
+                    Some(last) => last.push_str(piece),
+                    None => words.push(piece.to_string()),
+                }
+                glued = true;
+            }
+            start = end;
+        }
+        words
+    }
+
+    /// Split a run with `split_piece`, its symbols, e.g. emoji, being words of their own
+    /// that cut it apart, so that an unknown word before a symbol leaves the split of the
+    /// words after it alone. A symbol is a grapheme cluster starting with a character that
+    /// is neither a letter, a digit, an apostrophe nor ASCII, and is not a currency symbol
+    /// or a number separator of the model: ASCII punctuation is part of phrases and numbers.
+    fn split_normalized(&self, run: &str, trace: &mut Trace) -> Vec<String> {
+        if let Some(words) = self.pinned_words(run) {
+            for word in &words {
+                // No word ends inside a pinned word, like in a symbol cluster
+                let offset = trace.accumulated_cost();
+                trace
+                    .cost
+                    .extend(vec![f32::INFINITY; word.chars().count() - 1]);
+                trace.cost.push(offset + self.tokens_cost(&[word.as_str()]));
+            }
+            return words;
+        }
+        if run.is_ascii() {
+            return self.split_piece(run, trace);
+        }
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (idx, cluster) in graphemes(run) {
+            let first = cluster.chars().next().unwrap();
+            if first.is_alphanumeric()
+                || first.is_ascii()
+                || is_apostrophe(first)
+                || self.currency_symbols.contains(&first)
+                || self.number_separators.contains(&first)
+                || self.units.iter().any(|unit| unit.starts_with(first))
+            {
+                continue;
+            }
+            if start < idx {
+                words.extend(self.split_piece(&run[start..idx], trace));
+            }
+            let length = cluster.chars().count();
+            // No word ends inside the cluster, like in `best_match`
+            let offset = trace.accumulated_cost();
+            trace.cost.extend(vec![f32::INFINITY; length - 1]);
+            trace.cost.push(offset + self.unknown_char_cost());
+            words.push(cluster.to_string());
+            start = idx + cluster.len();
+        }
+        if start < run.len() || start == 0 {
+            words.extend(self.split_piece(&run[start..], trace));
+        }
+        words
+    }
+
+    /// The words of a run given to `pin`, sliced from the run as written
+    fn pinned_words(&self, run: &str) -> Option<Vec<String>> {
+        if self.pins.is_empty() {
+            return None;
+        }
+        let lengths = self.pins.get(&dictionary_key(run))?;
+        if lengths.iter().sum::<usize>() != run.chars().count() {
+            return None;
+        }
+        let mut chars = run.chars();
+        Some(
+            lengths
+                .iter()
+                .map(|&length| chars.by_ref().take(length).collect())
+                .collect(),
+        )
+    }
+
+    /// Split a piece of a run with `split_run` once normalized, see `normalize`
+    fn split_piece(&self, run: &str, trace: &mut Trace) -> Vec<String> {
+        let Some(normalize) = &self.normalize else {
+            return self.split_run(self.prepare_run(run), trace);
+        };
+        let normalized = normalize(run);
+        let words = self.split_run(self.prepare_run(&normalized), trace);
+        let length: usize = words.iter().map(|word| word.chars().count()).sum();
+        if length != run.chars().count() {
+            return words;
+        }
+        // The same characters of the input, word after word
+        let mut chars = run.chars();
+        words
+            .iter()
+            .map(|word| chars.by_ref().take(word.chars().count()).collect())
+            .collect()
+    }
+
+    /// Split every run of a text between whitespace with `split_run` and join the words.
+    /// The words of a run are joined with `separator`, the whitespace between the runs is
+    /// written as it is, or as a single space with `collapse_whitespace`.
+    fn join_runs(
+        &self,
+        text: &str,
+        trace: &mut Trace,
+        split_run: impl Fn(&str, &mut Trace) -> Vec<String>,
+    ) -> String {
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        apply_case_mode(self.case_mode, self.join_words(text, trace, split_run))
+    }
+
+    /// `join_runs` without `case_mode`
+    fn join_words(
+        &self,
+        text: &str,
+        trace: &mut Trace,
+        split_run: impl Fn(&str, &mut Trace) -> Vec<String>,
+    ) -> String {
+        if self.is_too_long(text) {
+            return text.to_string();
+        }
+        let mut result = String::with_capacity(text.len());
+        for token in self.run_tokens(text, trace, split_run) {
+            result.push_str(token.as_str());
+        }
+        result
+    }
+
+    /// The words of every run of a text split with `split_run` and the separators written
+    /// between them by `join_words`, both `separator` and the whitespace between the runs.
+    /// Separators next to each other are a single token.
+    fn run_tokens(
+        &self,
+        text: &str,
+        trace: &mut Trace,
+        split_run: impl Fn(&str, &mut Trace) -> Vec<String>,
+    ) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut run_start = 0;
+        for (idx, c) in text.char_indices() {
+            if c.is_ascii_whitespace() {
+                let run = &text[run_start..idx];
+                self.push_words(&mut tokens, split_run(run, trace));
+                if !self.collapse_whitespace {
+                    push_separator(&mut tokens, c.encode_utf8(&mut [0; 4]));
+                } else if run_start == 0 || !run.is_empty() {
+                    // The first whitespace of a run of whitespace
+                    push_separator(&mut tokens, " ");
+                }
+                trace.cost.push(trace.accumulated_cost());
+                run_start = idx + c.len_utf8();
+            }
+        }
+        self.push_words(&mut tokens, split_run(&text[run_start..], trace));
+        tokens
+    }
+
+    /// Push the words of a run, with `separator` between them
+    fn push_words(&self, tokens: &mut Vec<Token>, words: Vec<String>) {
+        for (idx, word) in words.into_iter().enumerate() {
+            if idx > 0 {
+                push_separator(tokens, &self.separator);
+            }
+            if !word.is_empty() {
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    /// Split a text with the loaded dictionary into its words, whitespace is dropped
+    fn text_words(&self, text: &str, trace: &mut Trace) -> Vec<String> {
+        if self.is_too_long(text) {
+            return vec![text.to_string()];
+        }
+        text.split(|c: char| c.is_ascii_whitespace())
+            .flat_map(|run| self.split_normalized(run, trace))
+            .collect()
+    }
+
+    /// Split a text with `greedy_words`
+    pub(crate) fn greedy_text(&self, text: &str) -> String {
+        self.join_runs(text, &mut Trace::new(), |run, _| self.greedy_words(run))
+    }
+
+    /// Split a text with `viterbi`
+    pub(crate) fn bigram_text(&self, text: &str) -> String {
+        self.join_runs(text, &mut Trace::new(), |run, _| {
+            let words = self
+                .viterbi(&self.prepare_run(run))
+                .into_iter()
+                .rev()
+                .collect();
+            self.apply_corpus_casing(words)
+        })
+    }
+
+    /// The longest dictionary word at every position of a run of text without whitespace
+    fn greedy_words(&self, run: &str) -> Vec<String> {
+        let (dict, max_word) = self.dict();
+        let lowered = lowercase_for_lookup(run);
+        // The byte offset of every character, and of the end of the run
+        let bounds: Vec<usize> = run
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain([run.len()])
+            .collect();
+        let mut words = Vec::new();
+        let mut start = 0;
+        while start + 1 < bounds.len() {
+            let longest = (1..(*max_word).max(1) as usize + 1)
+                .take_while(|k| start + k < bounds.len())
+                .filter(|k| dict.contains_key(&lowered[bounds[start]..bounds[start + k]]))
+                .last()
+                .unwrap_or(1);
+            words.push(run[bounds[start]..bounds[start + longest]].to_string());
+            start += longest;
+        }
+        self.apply_corpus_casing(words)
+    }
+
+    /// The cheapest split of a run of text without whitespace into dictionary words, if any
+    fn strict_words(&self, run: &str) -> Option<Vec<String>> {
+        let (dict, max_word) = self.dict();
+        let lowered = lowercase_for_lookup(run);
+        // The byte offset of every character, and of the end of the run
+        let bounds: Vec<usize> = run
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain([run.len()])
+            .collect();
+        // The cost of the cheapest cover of every prefix and the length of its last word
+        let mut covers: Vec<Option<(f32, usize)>> = vec![None; bounds.len()];
+        covers[0] = Some((0.0, 0));
+        for end in 1..bounds.len() {
+            covers[end] = (1..((*max_word).max(1) as usize).min(end) + 1)
+                .filter_map(|k| {
+                    let (cost, _) = covers[end - k]?;
+                    let word_cost = dict.get(&lowered[bounds[end - k]..bounds[end]])?;
+                    Some((cost + word_cost + self.length_penalty, k))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        let mut words = Vec::new();
+        let mut end = bounds.len() - 1;
+        while end > 0 {
+            let (_, k) = covers[end]?;
+            words.push(run[bounds[end - k]..bounds[end]].to_string());
+            end -= k;
+        }
+        words.reverse();
+        Some(self.apply_corpus_casing(words))
+    }
+
+    /// Prepare a run of text that contains no whitespace, with its numbers and acronyms
+    fn prepare_run<'a>(&self, text: &'a str) -> Run<'a> {
+        let mut run = Run::new(text, &self.number_separators, &self.currency_symbols)
+            .with_units(&self.units, self.rarest_cost() / 2.0)
+            .with_symbol_clusters(self.unknown_char_cost());
+        run.max_word = self.max_word_len.map(|len| len.max(1) as i32);
+        match self.acronym_min_length {
+            Some(min_length) => run.with_acronyms(min_length, self.rarest_cost()),
+            None => run,
+        }
+    }
+
+    /// Split a run of text that contains no whitespace
+    pub(crate) fn split_run(&self, run: Run, trace: &mut Trace) -> Vec<String> {
+        self.split_run_from(run, trace, Lattice::new())
+    }
+
+    /// `split_run` with the dynamic program over a prefix of the run, see
+    /// `extend_cost_array`
+    fn split_run_from(&self, run: Run, trace: &mut Trace, lattice: Lattice) -> Vec<String> {
+        let words = self.segment_run(run, trace, lattice);
+        let words = if self.spelling_correction {
+            self.correct_unknown_spans(words, trace)
+        } else {
+            words
+        };
+        let words = match self.merge_unknown_shorter_than {
+            Some(threshold) => self.merge_short_unknowns(words, threshold),
+            None => words,
+        };
+        trace.unknown_chars += words
+            .iter()
+            .filter(|word| !self.is_known(word) && !self.is_number(word))
+            .map(|word| word.chars().count())
+            .sum::<usize>();
+        self.expand_phrases(self.apply_corpus_casing(words))
+    }
+
+    /// Merge the consecutive unknown words shorter than `threshold`, see
+    /// `merge_unknown_shorter_than`
+    fn merge_short_unknowns(&self, words: Vec<String>, threshold: usize) -> Vec<String> {
+        let mut result: Vec<String> = Vec::with_capacity(words.len());
+        let mut last_mergeable = false;
+        for word in words {
+            let mergeable =
+                word.chars().count() < threshold && !self.is_known(&word) && !self.is_number(&word);
+            match result.last_mut() {
+                Some(last) if mergeable && last_mergeable => last.push_str(&word),
+                _ => result.push(word),
+            }
+            last_mergeable = mergeable;
+        }
+        result
+    }
+
+    /// Write every phrase of the corpus found in the words as its own words, e.g. "New",
+    /// "York" for "NewYork", see `CostModel::ZipfRank`
+    fn expand_phrases(&self, words: Vec<String>) -> Vec<String> {
+        if self.casings.is_empty() {
+            return words;
+        }
+        let mut result = Vec::with_capacity(words.len());
+        for word in words {
+            // With `CaseMode::Corpus`, a phrase is already written as the corpus does
+            if word.contains(' ') {
+                result.extend(word.split(' ').map(String::from));
+                continue;
+            }
+            match self.casings.get(&dictionary_key(&word)) {
+                Some(phrase) if phrase.contains(' ') => {
+                    let mut chars = word.chars();
+                    for part in phrase.split(' ') {
+                        result.push(chars.by_ref().take(part.chars().count()).collect());
+                    }
+                }
+                _ => result.push(word),
+            }
+        }
+        result
+    }
+
+    /// The words of a run as written, before any spelling correction, in order
+    fn segment_run(&self, run: Run, trace: &mut Trace, lattice: Lattice) -> Vec<String> {
+        let offset = trace.accumulated_cost();
+        let texts = match run.beam_width.or(self.beam_width) {
+            None => {
+                let lattice = self.extend_cost_array(&run, lattice);
+                trace
+                    .cost
+                    .extend(lattice.cost[1..].iter().map(|c| offset + c));
+                self.minimal_cost(&run, &lattice)
+            }
+            Some(beam_width) => {
+                let (boundaries, texts) = self.beam_search(&run, beam_width);
+                // Only the word boundaries of the chosen path have a cost
+                for pair in boundaries.windows(2) {
+                    let (start, start_cost) = pair[0];
+                    let end = pair[1].0;
+                    trace
+                        .cost
+                        .extend(vec![offset + start_cost; end - start - 1]);
+                    trace.cost.push(offset + pair[1].1);
+                }
+                texts
+            }
+        };
+        texts.into_iter().rev().collect()
+    }
+
+    /// Write the words with their corpus casing under `CaseMode::Corpus`
+    fn apply_corpus_casing(&self, words: Vec<String>) -> Vec<String> {
+        match self.case_mode {
+            CaseMode::Corpus if !self.casings.is_empty() => words
+                .into_iter()
+                .map(|word| match self.casings.get(&dictionary_key(&word)) {
+                    Some(casing) => casing.clone(),
+                    None => word,
+                })
+                .collect(),
+            _ => words,
+        }
+    }
+
+    /// Replace every span of consecutive unknown words by the nearest dictionary word, if any
+    fn correct_unknown_spans(&self, words: Vec<String>, trace: &mut Trace) -> Vec<String> {
+        let mut result: Vec<String> = Vec::new();
+        let mut unknown: Vec<String> = Vec::new();
+        for word in words.into_iter().map(Some).chain([None]) {
+            if let Some(word) = &word {
+                if !self.is_known(word) {
+                    unknown.push(word.to_string());
+                    continue;
+                }
+            }
+            if !unknown.is_empty() {
+                let original = unknown.concat();
+                match nearest_word(&self.dict().0, &dictionary_key(&original)) {
+                    Some(corrected) => {
+                        result.push(corrected.clone());
+                        trace.corrections.push(Correction {
+                            original,
+                            corrected,
+                        });
+                    }
+                    None => result.append(&mut unknown),
+                }
+                unknown.clear();
+            }
+            result.extend(word);
+        }
+        result
+    }
+
+    /// `dictionary_costs` with the cost of the unknown candidates set by `oov_policy`
+    fn candidate_costs(&self, run: &Run, end: usize, window: usize) -> Vec<f32> {
+        let mut costs = self.dictionary_costs(run, end, window);
+        match self.oov_costs() {
+            Some((word_cost, char_cost)) => {
+                for (k, cost) in costs.iter_mut().enumerate() {
+                    if *cost == f32::MAX {
+                        *cost = word_cost + char_cost * (k + 1) as f32;
+                    }
+                }
+            }
+            // Every prefix after an unknown character, a symbol, a letter missing from the
+            // corpus like "é" or one of a script without spaces, would otherwise cost
+            // `f32::MAX` too, and the rest of the run would fall apart into letters.
+            // An unknown word costs more than the dictionary words it contains, and less than
+            // its letters alone.
+            None => {
+                self.mark_costs(run, end, &mut costs);
+                let char_cost = self.unknown_char_cost();
+                for (k, cost) in costs.iter_mut().enumerate() {
+                    if *cost == f32::MAX {
+                        *cost = 1.0 + char_cost * (k + 1) as f32;
+                    }
+                }
+            }
+        }
+        costs
+    }
+
+    /// Give the unknown candidates ending with the combining marks of a grapheme cluster the
+    /// cost of the candidate without them plus the cost of an unknown symbol, e.g. "cafe"
+    /// and an accent for "cafe\u{301}": such a candidate no longer falls apart at the mark,
+    /// which is not a word of its own inside a cluster, see `best_match`
+    fn mark_costs(&self, run: &Run, end: usize, costs: &mut [f32]) {
+        let marks = (1..end)
+            .take_while(|k| {
+                run.is_inside_cluster(end - k)
+                    && !run
+                        .slice(end - k, end - k + 1)
+                        .starts_with(char::is_alphanumeric)
+            })
+            .count();
+        if marks == 0 || marks >= costs.len() {
+            return;
+        }
+        let bases = self.dictionary_costs(run, end - marks, costs.len() - marks);
+        for (cost, base) in costs[marks..].iter_mut().zip(bases) {
+            if *cost == f32::MAX && base != f32::MAX {
+                *cost = base + self.unknown_char_cost();
+            }
+        }
+    }
+
+    /// The cost of an unknown word under `oov_policy`, once and for every character, `None`
+    /// for `OovPolicy::Split`, see `candidate_costs`
+    fn oov_costs(&self) -> Option<(f32, f32)> {
+        match self.oov_policy {
+            OovPolicy::Split => None,
+            OovPolicy::CharPenalty(char_cost) => Some((self.unknown_char_cost(), char_cost)),
+            OovPolicy::KeepWhole => Some((self.unknown_char_cost(), KEEP_WHOLE_CHAR_COST)),
+        }
+    }
+
+    /// Costs of the candidate words ending at `end` in the run, the k-th one being
+    /// its last k + 1 characters; unknown candidates cost `f32::MAX`
+    fn dictionary_costs(&self, run: &Run, end: usize, window: usize) -> Vec<f32> {
+        let mut costs = match (&self.trie, &self.cost_function) {
+            (Some(trie), None) if run.ascii => {
+                trie.ascii_suffix_costs(&run.lowered.as_bytes()[..end], window)
+            }
+            (Some(trie), None) => trie.suffix_costs(run.lowered_slice(0, end), window),
+            _ => (0..window)
+                .map(|k| self.word_cost(run.lowered_slice(end - k - 1, end)))
+                .collect(),
+        };
+        if self.case_sensitive && self.cost_function.is_none() && run.text != run.lowered {
+            for (k, cost) in costs.iter_mut().enumerate() {
+                let word = run.slice(end - k - 1, end);
+                if word.chars().any(char::is_uppercase) {
+                    if let Some(cased) = self.dict().0.get(word) {
+                        *cost = *cased;
+                    }
+                }
+            }
+        }
+        if let (Some(premium), Some(index)) = (self.fuzzy_cost, &self.fuzzy_index) {
+            for (k, cost) in costs.iter_mut().enumerate() {
+                if *cost == f32::MAX && k + 1 >= FUZZY_MIN_LENGTH {
+                    let word = run.lowered_slice(end - k - 1, end);
+                    if let Some(word_cost) = index.cheapest_within_one_edit(&self.dict().0, word) {
+                        *cost = word_cost + premium;
+                    }
+                }
+            }
+        }
+        costs
+    }
+
+    /// The cost of a word starting at `start` with `position_weight`, an unknown word still
+    /// costing `f32::MAX`
+    fn weighted(&self, word_cost: f32, start: usize) -> f32 {
+        match &self.position_weight {
+            Some(weight) if word_cost != f32::MAX => word_cost * weight(start),
+            _ => word_cost,
+        }
+    }
+
+    /// The `(cost, length)` of the best split of the first `i` characters of the run, its
+    /// last word being `length` characters long
+    fn best_match(&self, i: usize, run: &Run, cost: &[f32]) -> (f32, f32) {
+        // No word ends inside a grapheme cluster, and none starts there at this cost
+        if run.is_inside_cluster(i) {
+            return (f32::INFINITY, 1.0);
+        }
+        self.candidates(i, run, cost)
             .into_iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+            .min_by(|a, b| self.compare_candidates(a, b))
+            .expect("a word ends at every position after the start")
+    }
+
+    /// Order two `(cost, length)` candidates, the cheapest first and the tie break deciding
+    /// between candidates of the same cost
+    fn compare_candidates(&self, a: &(f32, f32), b: &(f32, f32)) -> Ordering {
+        let by_length = match self.tie_break {
+            TieBreak::ShorterWord => a.1.total_cmp(&b.1),
+            TieBreak::LongerWord => b.1.total_cmp(&a.1),
+        };
+        // A total order, so that even a NaN cost cannot panic
+        a.0.total_cmp(&b.0).then(by_length)
+    }
+
+    /// The `(cost, length)` of every candidate word ending at `i` that `best_match` chooses from
+    /// The k-th candidate is the last k + 1 characters, after the best split of the
+    /// characters before them.
+    fn candidates(&self, i: usize, run: &Run, cost: &[f32]) -> Vec<(f32, f32)> {
+        let window = i.min(self.max_word(run).max(1) as usize);
+        let word_costs = self.candidate_costs(run, i, window);
+        let mut array_min: Vec<(f32, f32)> = Vec::with_capacity(window + 1);
+        for (k, word_cost) in word_costs.into_iter().enumerate() {
+            let start = i - k - 1;
+            let word_cost = self.weighted(word_cost, start);
+            array_min.push((
+                cost[start] + word_cost + self.length_penalty,
+                k as f32 + 1.0,
+            ));
+        }
+        if let Some(deletion_cost) = self.deletion(run, i - 1, i) {
+            array_min[0] = (cost[i - 1] + deletion_cost, 1.0);
+        }
+        // A number is a word, whatever its length; it costs nothing as it is not in the
+        // dictionary anyway and only competes with splits of the number itself.
+        // Its prefixes are numbers too, which keeps the cost array increasing through it.
+        // An acronym is kept whole the same way, at its own cost.
+        if let Some((start, span_cost)) = run.span_through(i) {
+            array_min.push((
+                cost[start] + self.weighted(span_cost, start) + self.length_penalty,
+                (i - start) as f32,
+            ));
+        }
+        array_min
     }
 
-    /// Calculate the best match for a given text
+    /// Run the dynamic program over a run of text
     /// # Arguments
-    /// * `text` - The text to be matched
-    /// * `cost` - The cost of each word in the corpus
-    /// * `text_length` - The length of the text
+    /// * `run` - The text to be matched
     /// # Returns
-    /// A Vec of f32 containing the best match costs for each word in the corpus
-    fn build_cost_array(&mut self, text_length: u32, text: String, cost: Vec<f32>) -> Vec<f32> {
-        let mut cost = cost.clone();
-        for i in 1..(text_length + 1) {
-            let (c, _k) = self.best_match(i as i32, text.clone(), cost.clone());
-            cost.push(c);
-        }
-        return cost;
+    /// The cost of the best split of every prefix of the run and the length of its last word
+    fn build_cost_array(&self, run: &Run) -> Lattice {
+        self.extend_cost_array(run, Lattice::new())
     }
 
-    /// Return a Vec containing all the words in the corpus
-    fn lines_from_file(&mut self) -> Vec<String> {
-        // Read corpus file and split into lines
-        let file = File::open(&self.corpus_path).unwrap();
-        let buf_reader = BufReader::new(file);
-        let mut lines: Vec<String> = Vec::new();
-        for line in buf_reader.lines() {
-            lines.push(line.unwrap());
+    /// `build_cost_array` from the dynamic program over a prefix of the run, the empty
+    /// prefix first
+    pub(crate) fn extend_cost_array(&self, run: &Run, mut lattice: Lattice) -> Lattice {
+        enter_span!("forward_pass", run = run.text, from = lattice.cost.len());
+        lattice.cost.reserve(run.length + 1);
+        lattice.back.reserve(run.length + 1);
+        for i in lattice.cost.len()..(run.length + 1) {
+            let (c, k) = self.best_match(i, run, &lattice.cost);
+            trace_event!(
+                position = i,
+                cost = c,
+                word = run.slice(i - (k as usize).min(i), i),
+                "best word ending here"
+            );
+            lattice.cost.push(c);
+            lattice.back.push(k as usize);
         }
-        return lines;
+        lattice
     }
 
-    /// Calculate the optimal cost of a text
+    /// The words of the best split of a run, following the last word of every prefix back
+    /// from the end of the run
     /// # Arguments
-    /// * `text` - The text to calculate the cost of
-    /// * `cost` - The cost of each word in the corpus
-    /// * `text_length` - The length of the text
+    /// * `run` - The text that was split
+    /// * `lattice` - The dynamic program built by `build_cost_array`
     /// # Returns
-    /// A Vec of strings containing the minimum costing words
-    fn minimal_cost(&mut self, text: String, cost: Vec<f32>, text_length: u32) -> Vec<String> {
+    /// The words of the cheapest split, last word first
+    pub(crate) fn minimal_cost(&self, run: &Run, lattice: &Lattice) -> Vec<String> {
+        enter_span!("backtrack", run = run.text);
         let mut result: Vec<String> = Vec::new();
-        let mut i = text_length;
+        let mut i = run.length;
         while i > 0 {
-            let (_c, k) = self.best_match(i as i32, text.clone(), cost.clone());
-            let mut new_token: bool = true;
-            if text[(i - k as u32) as usize..i as usize] != "'".to_string() {
-                let result_length = result.len();
-                if result_length > 0 {
-                    if &result[&result.len() - 1] == &"'s".to_string()
-                        || (text[(i - k as u32) as usize..i as usize]
-                            .to_string()
-                            .chars()
-                            .next()
-                            .unwrap()
-                            .is_digit(10)
-                            && result[result.len() - 1]
-                                .chars()
-                                .next()
-                                .unwrap()
-                                .is_digit(10))
-                    {
-                        let mut test = text[(i - k as u32) as usize..i as usize].to_string();
-                        test.push_str(&result[result_length - 1].to_string());
-                        result[result_length - 1] = test;
-                        new_token = false;
+            let k = lattice.backtrack_length(i);
+            if self.deletion(run, i - k, i).is_none() {
+                push_token(&mut result, run.slice(i - k, i));
+            }
+            i -= k;
+        }
+        debug_event!(words = ?result, "best split");
+        result
+    }
+
+    /// Split a run with a beam search instead of the exact dynamic program.
+    /// Hypotheses are extended one word at a time and only the `beam_width` cheapest
+    /// are kept after each extension, hypotheses reaching the same position being merged.
+    /// # Arguments
+    /// * `run` - The text to be split
+    /// * `beam_width` - How many hypotheses survive each extension
+    /// # Returns
+    /// The accumulated cost at each word boundary and the words, last word first
+    fn beam_search(&self, run: &Run, beam_width: usize) -> (Boundaries, Vec<String>) {
+        let text_length = run.length;
+        let max_word = self.max_word(run).max(1) as usize;
+        // (cost, position, word boundaries so far)
+        let mut beam: Vec<(f32, usize, Boundaries)> = vec![(0.0, 0, vec![(0, 0.0)])];
+        let mut best: Option<(f32, Boundaries)> = None;
+        while !beam.is_empty() {
+            let mut extended: HashMap<usize, (f32, Boundaries)> = HashMap::new();
+            for (cost, position, boundaries) in beam {
+                let span = run.span_starting_at(position);
+                let ends = (1..(max_word.min(text_length - position) + 1))
+                    .map(|k| position + k)
+                    .chain(span.map(|(end, _)| end));
+                for end in ends {
+                    // A whole number or acronym costs its own cost, like in `candidates`
+                    let word_cost = match span {
+                        Some((span_end, span_cost)) if span_end == end => {
+                            span_cost + self.length_penalty
+                        }
+                        _ => match self.deletion(run, position, end) {
+                            Some(deletion_cost) => deletion_cost,
+                            None => {
+                                self.word_cost(run.lowered_slice(position, end))
+                                    + self.length_penalty
+                            }
+                        },
+                    };
+                    let total = cost + word_cost;
+                    let mut path = boundaries.clone();
+                    path.push((end, total));
+                    if end == text_length {
+                        if best.as_ref().is_none_or(|(c, _)| total < *c) {
+                            best = Some((total, path));
+                        }
+                    } else if extended.get(&end).is_none_or(|(c, _)| total < *c) {
+                        extended.insert(end, (total, path));
                     }
                 }
             }
-            if new_token {
-                result.push(text[(i - k as u32) as usize..i as usize].to_string());
+            let mut next: Vec<(f32, usize, Boundaries)> = extended
+                .into_iter()
+                .map(|(position, (cost, path))| (cost, position, path))
+                .collect();
+            // Cheapest first, the furthest position breaking ties
+            next.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+            next.truncate(beam_width.max(1));
+            beam = next;
+        }
+        let boundaries = best.map_or(vec![(0, 0.0)], |(_, path)| path);
+        let mut result = Vec::new();
+        for pair in boundaries.windows(2).rev() {
+            if self.deletion(run, pair[0].0, pair[1].0).is_none() {
+                push_token(&mut result, run.slice(pair[0].0, pair[1].0));
             }
-            i -= k as u32;
         }
-        return result;
+        (boundaries, result)
     }
 
-    /// Calculate the cost of each word in the corpus
-    /// Return a Tuple containing a HashMap of words and their costs
-    /// as values and the maximum cost as second value
-    fn set_cost_dict(&mut self) -> (HashMap<String, f32>, i32) {
-        let mut dict = HashMap::new();
-        let words = self.lines_from_file();
-        let words_length = words.len() as f32;
-        let mut max_word = 0;
-        for (idx, word) in words.iter().enumerate() {
-            let a = (idx + 1) as f32;
-            let c = a * words_length.ln();
-            let z = c.ln();
-            dict.insert(word.to_string(), z);
+    /// The `k` cheapest splits of a run, see `split_top_k`. Every position keeps its `k`
+    /// cheapest `(cost, length, rank)` paths: the cost of the path, the length of its last
+    /// word and the rank of the path it extends at the start of that word.
+    fn run_top_k(&self, run: &Run, k: usize) -> Vec<(Vec<String>, f32)> {
+        let mut paths: Vec<Vec<(f32, usize, usize)>> = vec![vec![(0.0, 0, 0)]];
+        for i in 1..(run.length + 1) {
+            let window = i.min(self.max_word(run).max(1) as usize);
+            let word_costs = self.candidate_costs(run, i, window);
+            let mut extended: Vec<(f32, usize, usize)> = Vec::new();
+            for (index, word_cost) in word_costs.into_iter().enumerate() {
+                let length = index + 1;
+                let start = i - length;
+                let word_cost = match self.deletion(run, start, i) {
+                    Some(deletion_cost) => deletion_cost,
+                    None => self.weighted(word_cost, start) + self.length_penalty,
+                };
+                for (rank, (cost, _, _)) in paths[start].iter().enumerate() {
+                    extended.push((cost + word_cost, length, rank));
+                }
+            }
+            if let Some((start, span_cost)) = run.span_through(i) {
+                let span_cost = self.weighted(span_cost, start) + self.length_penalty;
+                for (rank, (cost, _, _)) in paths[start].iter().enumerate() {
+                    extended.push((cost + span_cost, i - start, rank));
+                }
+            }
+            extended
+                .sort_by(|a, b| self.compare_candidates(&(a.0, a.1 as f32), &(b.0, b.1 as f32)));
+            // A number or acronym is also a plain word of the same length, keep the cheaper
+            let mut kept: Vec<(f32, usize, usize)> = Vec::with_capacity(k);
+            for path in extended {
+                if kept.len() == k {
+                    break;
+                }
+                if kept
+                    .iter()
+                    .all(|other| (other.1, other.2) != (path.1, path.2))
+                {
+                    kept.push(path);
+                }
+            }
+            paths.push(kept);
         }
-        words.iter().for_each(|word| {
-            let word_cost = word.chars().count() as i32;
-            if word_cost > max_word {
-                max_word = word_cost;
+        let mut splits: Vec<(Vec<String>, f32)> = Vec::with_capacity(k);
+        for (rank, (cost, _, _)) in paths[run.length].iter().enumerate() {
+            let mut words: Vec<String> = Vec::new();
+            let (mut i, mut rank) = (run.length, rank);
+            while i > 0 {
+                let (_, length, previous) = paths[i][rank];
+                if self.deletion(run, i - length, i).is_none() {
+                    push_token(&mut words, run.slice(i - length, i));
+                }
+                i -= length;
+                rank = previous;
             }
-        });
-        return (dict, max_word);
+            words.reverse();
+            // Glued numbers can give the same words from different paths
+            if splits.iter().all(|(other, _)| *other != words) {
+                splits.push((words, *cost));
+            }
+        }
+        splits
     }
 
-    // Returns the best match for a word in the corpus.
-    /// A word is considered to be a match if it is within `max_distance` of the start of the word.
-    /// # Arguments
-    /// * `text` - The text to be split
+    /// Split a run with the Viterbi decoder of `split_bigram`.
+    /// The state at a position is the word ending there; for every state, the cheapest
+    /// path to it is kept together with the start of the word before it.
     /// # Returns
-    /// A String object containing the split text
+    /// The words of the run, last word first like `minimal_cost`
+    fn viterbi(&self, run: &Run) -> Vec<String> {
+        let max_word = self.max_word(run).max(1) as usize;
+        let unknown_cost = self.unknown_char_cost();
+        // At every position, the `(start, cost, start of the word before)` of each state
+        let mut states: Vec<Vec<(usize, f32, usize)>> = vec![vec![(0, 0.0, 0)]];
+        for end in 1..(run.length + 1) {
+            let span = run.span_through(end);
+            let starts = (1..(max_word.min(end) + 1))
+                .map(|k| end - k)
+                .chain(span.map(|(start, _)| start));
+            let mut here: Vec<(usize, f32, usize)> = Vec::new();
+            for start in starts {
+                if here.iter().any(|(known, _, _)| *known == start) {
+                    continue;
+                }
+                let word = run.lowered_slice(start, end);
+                let word_cost = match span {
+                    Some((span_start, span_cost)) if span_start == start => span_cost,
+                    _ => match self.word_cost(word) {
+                        f32::MAX => unknown_cost * (end - start) as f32,
+                        cost => cost,
+                    },
+                };
+                let best = states[start]
+                    .iter()
+                    .map(|(previous, cost, _)| {
+                        let transition = match start {
+                            0 => 0.0,
+                            _ => self.transition_cost(
+                                run.lowered_slice(*previous, start),
+                                word,
+                                word_cost,
+                            ),
+                        };
+                        (
+                            cost + word_cost + transition + self.length_penalty,
+                            *previous,
+                        )
+                    })
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+                if let Some((cost, previous)) = best {
+                    here.push((start, cost, previous));
+                }
+            }
+            states.push(here);
+        }
+        let mut result = Vec::new();
+        let Some(&(mut start, _, mut previous)) =
+            states[run.length].iter().min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            return result;
+        };
+        let mut end = run.length;
+        while end > 0 {
+            push_token(&mut result, run.slice(start, end));
+            end = start;
+            let state = states[end].iter().find(|(known, _, _)| *known == previous);
+            (start, previous) = state.map_or((0, 0), |(start, _, previous)| (*start, *previous));
+        }
+        result
+    }
+
+    /// The change to the cost of `word`, `word_cost`, when it comes after `previous`:
+    /// a pair seen by `load_bigrams` makes it cheaper, never dearer
+    fn transition_cost(&self, previous: &str, word: &str, word_cost: f32) -> f32 {
+        let pair_cost = self.bigrams.get(previous).and_then(|next| next.get(word));
+        let Some(weight) = self.bigram_weight else {
+            return pair_cost.map_or(0.0, |cost| (cost - word_cost).min(0.0));
+        };
+        let weight = weight.clamp(0.0, 0.99);
+        let Some(&pair_cost) = pair_cost else {
+            return -(1.0 - weight).ln();
+        };
+        // Both probabilities relative to the larger one, so that neither underflows
+        let least = pair_cost.min(word_cost);
+        let mixed = weight * (least - pair_cost).exp() + (1.0 - weight) * (least - word_cost).exp();
+        least - mixed.ln() - word_cost
+    }
+
+    /// The Zipf cost of the last rank of the dictionary
+    fn rarest_cost(&self) -> f32 {
+        let words = self.word_count().max(2) as f32;
+        (words * words.ln()).ln()
+    }
+
+    /// The cost of every character of an unknown word, a little more than `rarest_cost` and
+    /// than any dictionary word, e.g. under `CostModel::Frequency` where the rare words cost
+    /// more than the Zipf cost of the last rank
+    fn unknown_char_cost(&self) -> f32 {
+        let dearest = match self.cost_function {
+            None => self.dearest_cost.unwrap_or(f32::MIN),
+            Some(_) => f32::MIN,
+        };
+        self.rarest_cost().max(dearest) + 1.0
+    }
+
+    /// The cost of the split of a text per character, unknown words costing
+    /// `unknown_char_cost` for each of their characters
+    pub(crate) fn normalized_cost(&self, text: &str) -> f32 {
+        let mut cost = 0.0;
+        let mut length = 0;
+        for word in self.text_words(text, &mut Trace::new()) {
+            cost += self.token_cost(&word);
+            length += word.chars().count();
+        }
+        cost / length.max(1) as f32
+    }
+
+    /// The cost of a word of a tokenization, its dictionary cost or `unknown_char_cost` for
+    /// every character of an unknown word; a number costs nothing like in `candidates`
+    fn token_cost(&self, word: &str) -> f32 {
+        if self.is_number(word) {
+            return 0.0;
+        }
+        match self.word_cost(&dictionary_key(word)) {
+            f32::MAX => self.unknown_char_cost() * word.chars().count() as f32,
+            word_cost => word_cost,
+        }
+    }
+
+    /// The cost of a tokenization, see `tokenization_cost`
+    fn tokens_cost<W: AsRef<str>>(&self, tokens: &[W]) -> f32 {
+        tokens
+            .iter()
+            .map(|token| self.token_cost(token.as_ref()) + self.length_penalty)
+            .sum()
+    }
+
+    /// The longest word the dynamic program considers in a run, in characters
+    fn max_word(&self, run: &Run) -> i32 {
+        run.max_word.unwrap_or(self.dict().1)
+    }
+
+    /// The cost of dropping the characters from `start` to `end` of the run, if
+    /// `deletion_cost` drops them: a single character not in the dictionary, nor a number
+    /// or an acronym
+    fn deletion(&self, run: &Run, start: usize, end: usize) -> Option<f32> {
+        let deletion_cost = self.deletion_cost?;
+        let kept = run.span_through(end).is_some_and(|(span, _)| span == start);
+        (end == start + 1 && !kept && self.word_cost(run.lowered_slice(start, end)) == f32::MAX)
+            .then_some(deletion_cost)
+    }
+
+    /// The cost of a single lowercased word, `f32::MAX` when it is not in the dictionary,
+    /// see `cost_function`
+    fn word_cost(&self, word: &str) -> f32 {
+        match &self.cost_function {
+            Some(cost_function) => cost_function.cost(word),
+            None => self.dict().0.get(word).map_or(f32::MAX, |x| *x),
+        }
+    }
+}
+
+impl<S: BuildHasher + Clone + Default + Send + Sync> LanguageModel<S> {
+    /// Load the dictionary and split a batch of texts with it, both spread over the
+    /// available cores: the corpus lines are turned into word costs in chunks, one per
+    /// thread, and the texts are split in chunks with the one dictionary built.
+    /// With the `rayon` feature, both run on the rayon pool, so the threads of the load
+    /// are those of the splits; without it, each spawns a thread per core.
+    /// The dictionary and the splits are the same as with `try_load` then `split_many`;
+    /// a model already loaded only splits. The speedup grows with the number of cores, see
+    /// the `load_and_split` benchmarks: on a single core it is as fast as the serial path.
+    /// # Arguments
+    /// * `texts` - The texts to be split
     /// # Examples
     /// ```
     /// use rsplitter::LanguageModel;
-    /// let mut lm = LanguageModel::new("corpus.txt".to_string());
-    /// let text = "rustisgreat";
-    /// let result = lm.split(text.to_string());
-    /// assert_eq!(result, "rust is great");
+    /// let mut lm = LanguageModel::new(String::new());
+    /// let texts = ["rustisgreat".to_string(), "thequickbrownfox".to_string()];
+    /// let splits = lm.load_and_split_many(&texts).unwrap();
+    /// assert_eq!(splits, ["rust is great", "the quick brown fox"]);
     /// ```
-    /// Result: "This is a test"
-    pub fn split(&mut self, text: String) -> String {
-        let mut cost: Vec<f32> = Vec::new();
-        cost.push(0.0);
-        let text_length = text.chars().count() as u32;
-        let processed_cost = self.build_cost_array(text_length, text.clone(), cost);
-        let texts = self.minimal_cost(text.clone(), processed_cost, text_length);
-        return texts.into_iter().rev().collect::<Vec<String>>().join(" ");
+    pub fn load_and_split_many(&mut self, texts: &[String]) -> Result<Vec<String>, SplitError> {
+        let threads = available_threads();
+        if self.cost_dict.is_none() || self.load_failed {
+            let lines = self.lines_from_file()?;
+            let start = Instant::now();
+            let cost_dict = self.parallel_cost_dict(&lines, threads);
+            self.load_cost_dict(cost_dict, &lines, start)?;
+        }
+        self.try_ensure_loaded()?;
+        Ok(self.split_chunks(texts, threads))
+    }
+
+    /// Split a batch of texts like `split`, spread over the available cores.
+    /// The splits do not go through the cache of `cache_capacity`.
+    /// # Arguments
+    /// * `texts` - The texts to be split
+    /// # Returns
+    /// The split texts, in the order of `texts`
+    pub fn split_many(&mut self, texts: &[String]) -> Vec<String> {
+        self.ensure_loaded();
+        self.split_chunks(texts, available_threads())
+    }
+
+    /// Split the texts in `threads` chunks, one thread each, or on the rayon pool with the
+    /// `rayon` feature. A single chunk is split on the calling thread, so that no thread is
+    /// spawned, e.g. on `wasm32-unknown-unknown`.
+    pub(crate) fn split_chunks(&self, texts: &[String], threads: usize) -> Vec<String> {
+        let split_one = |text: &String| {
+            if self.is_too_long(text) {
+                text.clone()
+            } else {
+                self.split_traced(text, &mut Trace::new())
+            }
+        };
+        let split_all = |texts: &[String]| texts.iter().map(split_one).collect::<Vec<String>>();
+        if threads <= 1 {
+            return split_all(texts);
+        }
+        #[cfg(feature = "rayon")]
+        return texts.par_iter().map(split_one).collect();
+        #[cfg(not(feature = "rayon"))]
+        {
+            let chunk = texts.len().div_ceil(threads).max(1);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = texts
+                    .chunks(chunk)
+                    .map(|texts| scope.spawn(move || split_all(texts)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("a split does not panic"))
+                    .collect()
+            })
+        }
+    }
+
+    /// `set_cost_dict` with the lines in `threads` chunks, one thread each.
+    /// The chunks are merged in the order of the lines, so the dictionary is the same.
+    /// With the `rayon` feature, `set_cost_dict` already runs on the rayon pool.
+    pub(crate) fn parallel_cost_dict(&self, lines: &[String], threads: usize) -> CostDict<S> {
+        if threads <= 1 || cfg!(feature = "rayon") {
+            return self.set_cost_dict(lines);
+        }
+        let chunk = lines.len().div_ceil(threads.max(1)).max(1);
+        match self.cost_model {
+            CostModel::ZipfRank => {
+                let partials: Vec<CostDict<S>> = std::thread::scope(|scope| {
+                    let handles: Vec<_> = lines
+                        .chunks(chunk)
+                        .enumerate()
+                        .map(|(idx, part)| {
+                            let hash_builder = self.hash_builder.clone();
+                            scope.spawn(move || {
+                                zipf_chunk_costs(part, idx * chunk, lines.len(), hash_builder)
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| handle.join().expect("building costs does not panic"))
+                        .collect()
+                });
+                let mut cost_dict =
+                    merge_zipf_chunks(partials, lines.len(), self.hash_builder.clone());
+                self.add_cased_costs(&mut cost_dict, lines);
+                cost_dict
+            }
+            CostModel::Frequency => {
+                let counts: Vec<(String, f64)> = std::thread::scope(|scope| {
+                    let handles: Vec<_> = lines
+                        .chunks(chunk)
+                        .map(|part| scope.spawn(move || frequency_counts(part)))
+                        .collect();
+                    handles
+                        .into_iter()
+                        .flat_map(|handle| handle.join().expect("counting does not panic"))
+                        .collect()
+                });
+                let mut cost_dict =
+                    merge_frequency_counts(counts, self.hash_builder.clone(), self.capacity_hint);
+                self.add_cased_costs(&mut cost_dict, lines);
+                cost_dict
+            }
+        }
+    }
+}
+
+/// The number of threads of the parallel helpers, one per core
+pub(crate) fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// The dynamic program over a run of text, by character position: the cost of the best
+/// split of the first `i` characters and the length of its last word, 0 for the empty
+/// prefix
+#[derive(Clone, Debug)]
+pub(crate) struct Lattice {
+    pub(crate) cost: Vec<f32>,
+    pub(crate) back: Vec<usize>,
+}
+
+impl Lattice {
+    /// The dynamic program over the empty prefix
+    fn new() -> Self {
+        Lattice {
+            cost: vec![0.0],
+            back: vec![0],
+        }
+    }
+
+    /// The length of the best word ending at `i`, kept between 1 and `i` so that the
+    /// backtracking always moves towards the start of the run
+    fn backtrack_length(&self, i: usize) -> usize {
+        let k = self.back[i];
+        debug_assert!(k >= 1 && k <= i, "a word of {} characters ends at {}", k, i);
+        k.clamp(1, i)
+    }
+}
+
+/// A run of text without whitespace, prepared for the dictionary lookups
+pub(crate) struct Run<'a> {
+    /// The run as written, the words are sliced from it
+    text: &'a str,
+    /// The run lowercased like the dictionary keys
+    lowered: String,
+    /// The length of the run in characters
+    length: usize,
+    /// Whether the run is pure ASCII: every byte is then a character, so the lowercasing is
+    /// bytewise and the trie is walked over bytes instead of decoding characters
+    ascii: bool,
+    /// The byte offset of every character of `text` and of `lowered`, and of their ends,
+    /// empty for an ASCII run whose character positions are byte offsets
+    offsets: Vec<usize>,
+    lowered_offsets: Vec<usize>,
+    /// The `(start, end, cost)` of the words kept whole, in character positions: the
+    /// numbers, see `number_spans`, the acronyms, see `with_acronyms`, and the symbol
+    /// clusters, see `with_symbol_clusters`
+    spans: Vec<(usize, usize, f32)>,
+    /// The character positions inside an extended grapheme cluster, e.g. before a combining
+    /// accent or in an emoji sequence, where no word starts or ends; sorted, and empty
+    /// without the `graphemes` feature
+    inside_clusters: Vec<usize>,
+    /// Overrides the longest dictionary word, see `LanguageModel::split_bounded`
+    max_word: Option<i32>,
+    /// Overrides `LanguageModel::beam_width`, see `LanguageModel::split_limited`
+    beam_width: Option<usize>,
+}
+
+impl<'a> Run<'a> {
+    /// Prepare a run, on the ASCII fast path when possible
+    /// # Arguments
+    /// * `text` - The run
+    /// * `separators` - The characters allowed between the digits of a number
+    /// * `symbols` - The symbols kept with the number right after them
+    pub(crate) fn new(text: &'a str, separators: &[char], symbols: &[char]) -> Self {
+        if text.is_ascii() {
+            Run {
+                text,
+                lowered: text.to_ascii_lowercase(),
+                length: text.len(),
+                ascii: true,
+                offsets: Vec::new(),
+                lowered_offsets: Vec::new(),
+                spans: number_spans(text, separators, symbols),
+                inside_clusters: Vec::new(),
+                max_word: None,
+                beam_width: None,
+            }
+        } else {
+            Run::unicode(text, separators, symbols)
+        }
+    }
+
+    /// Prepare a run on the general path, whatever its characters
+    pub(crate) fn unicode(text: &'a str, separators: &[char], symbols: &[char]) -> Self {
+        let lowered = lowercase_for_lookup(text);
+        Run {
+            text,
+            length: text.chars().count(),
+            ascii: false,
+            offsets: char_offsets(text),
+            lowered_offsets: char_offsets(&lowered),
+            lowered,
+            spans: number_spans(text, separators, symbols),
+            inside_clusters: clusters(text)
+                .flat_map(|(start, end)| start + 1..end)
+                .collect(),
+            max_word: None,
+            beam_width: None,
+        }
+    }
+
+    /// Keep the longest of `units` right after every number whole, at `cost`, see
+    /// `LanguageModel::units`
+    fn with_units(mut self, units: &[String], cost: f32) -> Self {
+        if units.is_empty() {
+            return self;
+        }
+        let number_ends: Vec<usize> = self.spans.iter().map(|(_, end, _)| *end).collect();
+        for end in number_ends {
+            let rest = self.lowered_slice(end, self.length);
+            let unit = units
+                .iter()
+                .filter(|unit| !unit.is_empty() && rest.starts_with(unit.as_str()))
+                .map(|unit| unit.chars().count())
+                .max();
+            if let Some(length) = unit {
+                self.spans.push((end, end + length, cost));
+            }
+        }
+        self
+    }
+
+    /// Keep every grapheme cluster of several characters that does not start with a letter
+    /// whole, at `cost`, e.g. an emoji with a skin tone or a flag: a word of its own, like
+    /// a symbol alone
+    fn with_symbol_clusters(mut self, cost: f32) -> Self {
+        if self.ascii {
+            return self;
+        }
+        let symbols: Vec<(usize, usize, f32)> = clusters(self.text)
+            .filter(|(start, _)| {
+                !self
+                    .slice(*start, start + 1)
+                    .starts_with(char::is_alphabetic)
+            })
+            .map(|(start, end)| (start, end, cost))
+            .collect();
+        self.spans.extend(symbols);
+        self
+    }
+
+    /// Whether a character position is inside a grapheme cluster
+    fn is_inside_cluster(&self, position: usize) -> bool {
+        self.inside_clusters.binary_search(&position).is_ok()
+    }
+
+    /// Keep every maximal run of at least `min_length` uppercase letters whole, at `cost`
+    fn with_acronyms(mut self, min_length: usize, cost: f32) -> Self {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            while i < chars.len() && chars[i].is_uppercase() {
+                i += 1;
+            }
+            if i - start >= min_length.max(1) {
+                self.spans.push((start, i, cost));
+            }
+            i = i.max(start + 1);
+        }
+        self
+    }
+
+    /// The characters of the run from `start` to `end`, as written
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        if self.ascii {
+            return &self.text[start..end];
+        }
+        &self.text[self.offsets[start]..self.offsets[end]]
+    }
+
+    /// The characters of the lowercased run from `start` to `end`, see `slice`
+    fn lowered_slice(&self, start: usize, end: usize) -> &str {
+        if self.ascii {
+            return &self.lowered[start..end];
+        }
+        &self.lowered[self.lowered_offsets[start]..self.lowered_offsets[end]]
+    }
+
+    /// The start and cost of the span ending at `end` or going past it, if any
+    fn span_through(&self, end: usize) -> Option<(usize, f32)> {
+        self.spans
+            .iter()
+            .find(|(start, span_end, _)| *start < end && end <= *span_end)
+            .map(|(start, _, cost)| (*start, *cost))
+    }
+
+    /// The end and cost of the span starting at `start`, if any
+    fn span_starting_at(&self, start: usize) -> Option<(usize, f32)> {
+        self.spans
+            .iter()
+            .find(|(span_start, _, _)| *span_start == start)
+            .map(|(_, end, cost)| (*end, *cost))
+    }
+}
+
+/// The `(start, end)` character positions of the extended grapheme clusters of a text that
+/// have more than one character
+fn clusters(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut position = 0;
+    graphemes(text).filter_map(move |(_, cluster)| {
+        let start = position;
+        position += cluster.chars().count();
+        (position > start + 1).then_some((start, position))
+    })
+}
+
+/// The byte offset and the text of every extended grapheme cluster of a text
+#[cfg(feature = "graphemes")]
+fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.grapheme_indices(true)
+}
+
+/// Every character is a grapheme cluster of its own without the `graphemes` feature
+#[cfg(not(feature = "graphemes"))]
+fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.char_indices()
+        .map(|(idx, c)| (idx, &text[idx..idx + c.len_utf8()]))
+}
+
+/// The `(start, end, cost)` character positions of the numbers of a text, which cost nothing.
+/// A number is a run of ASCII digits, possibly with single separators between digits,
+/// e.g. "1,234.56" with the separators `,` and `.`; a separator not followed by a digit
+/// ends the number. A symbol right before the first digit is part of the number, e.g. "$99".
+fn number_spans(text: &str, separators: &[char], symbols: &[char]) -> Vec<(usize, usize, f32)> {
+    let chars: Vec<char> = text.chars().collect();
+    let skip_digits = |mut i: usize| {
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = if i > 0 && symbols.contains(&chars[i - 1]) {
+            i - 1
+        } else {
+            i
+        };
+        i = skip_digits(i);
+        while i + 1 < chars.len() && separators.contains(&chars[i]) && chars[i + 1].is_ascii_digit()
+        {
+            i = skip_digits(i + 1);
+        }
+        spans.push((start, i, 0.0));
+    }
+    spans
+}
+
+/// The byte offset of every character of a text, then the length of the text
+fn char_offsets(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(offset, _)| offset)
+        .chain([text.len()])
+        .collect()
+}
+
+/// Turn increasing byte offsets of a text into character offsets
+pub(crate) fn char_spans(text: &str, spans: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // The character index of every character boundary, by byte offset
+    let mut chars = vec![0; text.len() + 1];
+    for (index, (offset, c)) in text.char_indices().enumerate() {
+        chars[offset..offset + c.len_utf8()].fill(index);
+    }
+    chars[text.len()] = text.chars().count();
+    spans
+        .iter()
+        .map(|(start, end)| (chars[*start], chars[*end]))
+        .collect()
+}
+
+/// The character positions of the ends of the words but the last
+fn word_boundaries<W: AsRef<str>>(words: &[W]) -> Vec<usize> {
+    let mut position = 0;
+    let mut boundaries = Vec::new();
+    for word in words.iter().take(words.len().saturating_sub(1)) {
+        position += word.as_ref().chars().count();
+        boundaries.push(position);
+    }
+    boundaries
+}
+
+/// The word boundaries of a beam search hypothesis, with the accumulated cost at each of them
+type Boundaries = Vec<(usize, f32)>;
+
+/// Put a token in front of the words following it, which are stored last word first.
+/// A possessive "'s" and consecutive digits are glued to the token before them.
+fn push_token(result: &mut Vec<String>, token: &str) {
+    let mut new_token: bool = true;
+    let result_length = result.len();
+    if result_length > 0 && glues(token, &result[result_length - 1]) {
+        let mut test = token.to_string();
+        test.push_str(&result[result_length - 1]);
+        result[result_length - 1] = test;
+        new_token = false;
+    }
+    if new_token {
+        result.push(token.to_string());
+    }
+}
+
+/// Whether a token is glued to the word after it, see `push_token`
+fn glues(token: &str, next: &str) -> bool {
+    token != "'"
+        && (next == "'s"
+            || (token.chars().next().unwrap().is_ascii_digit()
+                && next.chars().next().unwrap().is_ascii_digit()))
+}
+
+/// Push a separator, appended to the last token when it is a separator too
+fn push_separator(tokens: &mut Vec<Token>, separator: &str) {
+    if separator.is_empty() {
+        return;
+    }
+    match tokens.last_mut() {
+        Some(Token::Separator(last)) => last.push_str(separator),
+        _ => tokens.push(Token::Separator(separator.to_string())),
+    }
+}
+
+/// Render the casing of a split text
+fn apply_case_mode(case_mode: CaseMode, text: String) -> String {
+    match case_mode {
+        // The corpus casing is applied to every word, see `split_run`
+        CaseMode::Original | CaseMode::Corpus => text,
+        CaseMode::SentenceCase => capitalize_first_word(text),
+        CaseMode::Lowercase => text.to_lowercase(),
+        CaseMode::TitleCase => capitalize_words(&text),
+    }
+}
+
+/// `apply_case_mode` for the tokens of `split_tokens`, whose words have no whitespace but
+/// in a phrase
+fn case_tokens(case_mode: CaseMode, tokens: &mut [Token]) {
+    let transform: fn(&str) -> String = match case_mode {
+        CaseMode::Original | CaseMode::Corpus => return,
+        CaseMode::SentenceCase => return sentence_case_tokens(tokens),
+        CaseMode::Lowercase => str::to_lowercase,
+        CaseMode::TitleCase => capitalize_words,
+    };
+    for token in tokens {
+        if let Token::Word(word) = token {
+            *word = transform(word);
+        }
+    }
+}
+
+/// `CaseMode::SentenceCase` for the tokens of `split_tokens`, like `capitalize_first_word`
+/// over their concatenation
+fn sentence_case_tokens(tokens: &mut [Token]) {
+    let text: String = tokens.iter().map(Token::as_str).collect();
+    let capitalized = capitalize_first_word(text.clone());
+    let Some(idx) = text
+        .bytes()
+        .zip(capitalized.bytes())
+        .position(|(a, b)| a != b)
+    else {
+        return;
+    };
+    // The token holding the capitalized letter takes the text at the same place
+    let mut start = 0;
+    for token in tokens.iter_mut() {
+        let end = start + token.as_str().len();
+        if idx < end {
+            let grown = end + capitalized.len() - text.len();
+            let changed = String::from(&capitalized[start..grown]);
+            match token {
+                Token::Word(word) | Token::Separator(word) => *word = changed,
+            }
+            return;
+        }
+        start = end;
+    }
+}
+
+/// Uppercase the first letter of every word, the words cut at ASCII whitespace like for
+/// `capitalize_first_word`
+fn capitalize_words(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    // Whether the current word has no letter yet
+    let mut capitalize = true;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            capitalize = true;
+            result.push(c);
+        } else if capitalize && c.is_alphabetic() {
+            capitalize = false;
+            result.extend(c.to_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Uppercase the first letter of the first word, if that word has one
+fn capitalize_first_word(text: String) -> String {
+    let word_start = text
+        .find(|c: char| !c.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    let word_end = text[word_start..]
+        .find(|c: char| c.is_ascii_whitespace())
+        .map_or(text.len(), |end| word_start + end);
+    match text[word_start..word_end]
+        .char_indices()
+        .find(|(_, c)| c.is_alphabetic())
+    {
+        Some((idx, c)) => {
+            let idx = word_start + idx;
+            let upper: String = c.to_uppercase().collect();
+            format!("{}{}{}", &text[..idx], upper, &text[idx + c.len_utf8()..])
+        }
+        None => text,
+    }
+}
+
+/// The dictionary key of a word, under which its cost and its corpus casing are stored.
+/// A phrase is keyed without its whitespace, as it is matched against text without any.
+fn dictionary_key(word: &str) -> String {
+    let key = if word.contains(char::is_whitespace) {
+        word.split_whitespace().collect::<String>().to_lowercase()
+    } else {
+        word.to_lowercase()
+    };
+    if key.contains(TYPOGRAPHIC_APOSTROPHES) {
+        key.replace(TYPOGRAPHIC_APOSTROPHES, "'")
+    } else {
+        key
+    }
+}
+
+/// The apostrophes a contraction is also written with, e.g. "don’t", looked up as `'`
+const TYPOGRAPHIC_APOSTROPHES: [char; 2] = ['\u{2019}', '\u{02bc}'];
+
+/// Whether a character is an apostrophe, `'` or a typographic one
+pub(crate) fn is_apostrophe(c: char) -> bool {
+    c == '\'' || TYPOGRAPHIC_APOSTROPHES.contains(&c)
+}
+
+/// The byte length of the start of a text that is the word, whatever their casing
+fn matched_len(text: &str, word: &str) -> Option<usize> {
+    let mut expected = word.chars().flat_map(char::to_lowercase);
+    let mut pending = expected.next();
+    for (idx, c) in text.char_indices() {
+        if pending.is_none() {
+            return Some(idx);
+        }
+        for lower in c.to_lowercase() {
+            if pending != Some(lower) {
+                return None;
+            }
+            pending = expected.next();
+        }
+    }
+    pending.is_none().then_some(text.len())
+}
+
+/// Lowercase a run once for all its dictionary lookups, like `dictionary_key`.
+/// The characters are looked up by their position in the run, so a character whose
+/// lowercase form has another length keeps its casing and only matches as written.
+/// A typographic apostrophe is looked up as `'`, like by `dictionary_key`.
+fn lowercase_for_lookup(text: &str) -> String {
+    let mut lowered = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_apostrophe(c) {
+            lowered.push('\'');
+            continue;
+        }
+        let mut lower = c.to_lowercase();
+        match (lower.next(), lower.next()) {
+            (Some(l), None) if l.len_utf8() == c.len_utf8() => lowered.push(l),
+            _ => lowered.push(c),
+        }
+    }
+    lowered
+}
+
+/// Trim every line and drop the blank ones and the comments.
+/// Dropped lines are gone before the words are ranked, so they do not shift the ranks.
+fn clean_lines<L: AsRef<str>>(
+    lines: impl IntoIterator<Item = L>,
+    comment_prefix: Option<char>,
+) -> Vec<String> {
+    lines
+        .into_iter()
+        .filter_map(|line| {
+            let line = line.as_ref().trim();
+            let comment = comment_prefix.is_some_and(|prefix| line.starts_with(prefix));
+            (!line.is_empty() && !comment).then(|| line.to_string())
+        })
+        .collect()
+}
+
+/// Costs for a ranked word list, the n-th word costs `ln(n * ln(N))`.
+/// A phrase is ranked like a word, see `CostModel::ZipfRank`.
+/// With the `rayon` feature, the lines are split in a chunk per thread of the rayon pool.
+fn zipf_costs<S: BuildHasher>(lines: &[String], hash_builder: S) -> CostDict<S> {
+    #[cfg(feature = "rayon")]
+    {
+        let chunk = lines.len().div_ceil(rayon::current_num_threads()).max(1);
+        let partials: Vec<CostDict<FxBuildHasher>> = lines
+            .par_chunks(chunk)
+            .enumerate()
+            .map(|(idx, part)| {
+                zipf_chunk_costs(part, idx * chunk, lines.len(), FxBuildHasher::default())
+            })
+            .collect();
+        merge_zipf_chunks(partials, lines.len(), hash_builder)
+    }
+    #[cfg(not(feature = "rayon"))]
+    zipf_chunk_costs(lines, 0, lines.len(), hash_builder)
+}
+
+/// The dictionary of the `zipf_chunk_costs` of consecutive chunks of the lines, in their
+/// order: an earlier chunk has the more frequent casing of a word
+fn merge_zipf_chunks<P, S: BuildHasher>(
+    partials: Vec<CostDict<P>>,
+    lines: usize,
+    hash_builder: S,
+) -> CostDict<S> {
+    let mut dict = HashMap::with_capacity_and_hasher(lines, hash_builder);
+    let mut max_word = 0;
+    for (partial, partial_max) in partials {
+        for (word, cost) in partial {
+            dict.entry(word).or_insert(cost);
+        }
+        max_word = max_word.max(partial_max);
+    }
+    (dict, max_word)
+}
+
+/// An `InvalidInput` error unless the weight of a corpus is positive and finite
+fn check_weight(weight: f32) -> Result<(), SplitError> {
+    if weight > 0.0 && weight.is_finite() {
+        Ok(())
+    } else {
+        Err(SplitError::InvalidInput(format!(
+            "the weight of a corpus is positive and finite, not {}",
+            weight
+        )))
+    }
+}
+
+/// The cost of the word of a corpus line, `ln(rank * ln(total))`, from the rank of its line
+/// starting at 0 and the number of lines. The cost is at least 0: in a corpus of a few lines
+/// the first ranks would cost less, down to `-inf` for a single line, and a path through such
+/// a word would be cheaper than any split of the rest of the text.
+fn zipf_cost(idx: usize, total: usize) -> f32 {
+    ((idx + 1) as f32 * (total as f32).ln()).ln().max(0.0)
+}
+
+/// `zipf_costs` for the lines of a corpus of `total` lines from the rank `first_rank` on
+fn zipf_chunk_costs<S: BuildHasher>(
+    lines: &[String],
+    first_rank: usize,
+    total: usize,
+    hash_builder: S,
+) -> CostDict<S> {
+    let mut dict = HashMap::with_capacity_and_hasher(lines.len(), hash_builder);
+    let mut max_word = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        // The word of the line without its whitespace
+        let word: String = line.split_whitespace().collect();
+        max_word = max_word.max(word.chars().count() as i32);
+        // Casings of a word share the cost of the most frequent one
+        dict.entry(dictionary_key(&word))
+            .or_insert(zipf_cost(first_rank + idx, total));
+    }
+    (dict, max_word)
+}
+
+/// The first casing in the corpus of every word that has one with an uppercase letter,
+/// by lowercased word. A word written all lowercase first keeps the input casing.
+/// A phrase is kept whatever its casing, its words separated by a single space.
+fn corpus_casings<S: BuildHasher>(words: &[&str], hash_builder: S) -> HashMap<String, String, S> {
+    let mut first: HashMap<String, (usize, String)> = HashMap::new();
+    for (idx, word) in words.iter().enumerate() {
+        if word.contains(char::is_whitespace) {
+            let phrase = word.split_whitespace().collect::<Vec<&str>>().join(" ");
+            first.entry(dictionary_key(word)).or_insert((idx, phrase));
+        } else if word.chars().any(char::is_uppercase) {
+            first
+                .entry(dictionary_key(word))
+                .or_insert((idx, word.to_string()));
+        }
+    }
+    if !first.is_empty() {
+        for (idx, word) in words.iter().enumerate() {
+            if first.get(*word).is_some_and(|(cased, _)| idx < *cased) {
+                first.remove(*word);
+            }
+        }
+    }
+    let mut casings = HashMap::with_capacity_and_hasher(first.len(), hash_builder);
+    casings.extend(first.into_iter().map(|(word, (_, casing))| (word, casing)));
+    casings
+}
+
+/// The word and the count of a `word count` line, if the count is positive and finite
+fn frequency_entry(line: &str) -> Option<(&str, f64)> {
+    let (word, last) = line.rsplit_once(char::is_whitespace)?;
+    let (word, count) = match last.parse::<f64>() {
+        Ok(count) => (word, count),
+        // A tag after the count, e.g. the part of speech of a jieba dictionary
+        Err(_) => {
+            let (word, count) = word.trim_end().rsplit_once(char::is_whitespace)?;
+            (word, count.parse::<f64>().ok()?)
+        }
+    };
+    (count > 0.0 && count.is_finite()).then_some((word.trim_end(), count))
+}
+
+/// Costs for `word count` lines, each word costs `ln(total / count)`.
+/// Lines without a positive and finite count are ignored. The dictionary is sized for
+/// `capacity` words, or for every line with a count.
+fn frequency_costs<S: BuildHasher>(
+    lines: &[String],
+    hash_builder: S,
+    capacity: Option<usize>,
+) -> CostDict<S> {
+    let counts: Vec<(String, f64)> = frequency_counts(lines);
+    merge_frequency_counts(counts, hash_builder, capacity)
+}
+
+/// The dictionary key and the count of every `word count` line with a positive and finite
+/// count
+fn frequency_counts(lines: &[String]) -> Vec<(String, f64)> {
+    let line_count =
+        |line: &String| frequency_entry(line).map(|(word, count)| (dictionary_key(word), count));
+    #[cfg(feature = "rayon")]
+    return lines.par_iter().filter_map(line_count).collect();
+    #[cfg(not(feature = "rayon"))]
+    lines.iter().filter_map(line_count).collect()
+}
+
+/// `frequency_costs` from the counts of `frequency_counts`, in the order of the lines
+fn merge_frequency_counts<S: BuildHasher>(
+    counts: Vec<(String, f64)>,
+    hash_builder: S,
+    capacity: Option<usize>,
+) -> CostDict<S> {
+    let total: f64 = counts.iter().map(|(_, count)| count).sum();
+    let capacity = capacity.unwrap_or(counts.len());
+    let mut dict = HashMap::with_capacity_and_hasher(capacity, hash_builder);
+    let mut max_word = 0;
+    // Casings of a word add up
+    let mut merged: HashMap<String, f64> = HashMap::with_capacity(capacity);
+    for (word, count) in counts {
+        *merged.entry(word).or_insert(0.0) += count;
+    }
+    for (word, count) in merged {
+        max_word = max_word.max(word.chars().count() as i32);
+        dict.insert(word, (total / count).ln() as f32);
     }
+    (dict, max_word)
 }
@@ -1,101 +1,232 @@
+mod builder;
+mod cache;
+#[cfg(feature = "config")]
+mod config;
+mod detect;
+mod domain;
+mod error;
+mod export;
+mod ffi;
+mod hasher;
+#[cfg(feature = "bundled-corpus")]
+mod huffman;
+mod instrument;
 mod language_model;
-use lazy_static::lazy_static;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod normalize;
+mod pretokenize;
+mod sentences;
+mod serialize;
+mod shared;
+mod spelling;
+mod stream;
+mod trie;
+#[cfg(feature = "wordninja")]
+mod wordninja;
+mod words;
+pub use builder::LanguageModelBuilder;
+#[cfg(feature = "bundled-corpus")]
+use cache::SharedDict;
+pub use cache::{clear_dictionary_cache, CacheStats};
+#[cfg(feature = "config")]
+pub use config::{Config, OutputFormat, PreTokenizerMode};
+pub use detect::detect_language;
+pub use domain::DomainSplit;
+pub use error::SplitError;
+pub use export::{DictionaryStats, ExportFormat};
+pub use hasher::{DictHasher, FxBuildHasher, FxHasher};
+use language_model::Trace;
+pub use language_model::{
+    Algorithm, Candidate, CaseMode, CorpusSource, Correction, CostDict, CostModel, DebugSplit,
+    DictionaryBackend, EvalReport, Language, LanguageModel, LoadStats, Normalizer, OovPolicy,
+    PositionExplanation, PositionWeight, PrefixCosts, SplitLimits, SplitResult, SplitStats,
+    TieBreak, Token, WordCost, FUZZY_MIN_LENGTH, UNITS,
+};
+#[cfg(feature = "mmap")]
+pub use mapped::MappedDictionary;
+pub use normalize::{collapse_elongations, strip_accents};
+pub use pretokenize::{Alphabetic, Hashtag, Identifier, PreToken, PreTokenizer, Whitespace};
+pub use sentences::SentenceRules;
+pub use shared::{ModelHandle, SharedModel};
+#[cfg(feature = "bundled-corpus")]
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::include_str;
+use std::hash::BuildHasher;
+#[cfg(feature = "bundled-corpus")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "bundled-corpus")]
+use std::sync::RwLock;
+pub use stream::{SplitIter, SplitStream};
 
-lazy_static! {
-    static ref COST_DICT: (HashMap<String, f32>, i32) = get_cost_dict("".to_string());
+/// The dictionary of the free functions, shared by every thread without a corpus of its own.
+/// `None` until the first split, the bundled corpus is then built once for the process.
+#[cfg(feature = "bundled-corpus")]
+static SHARED_DEFAULT: RwLock<Option<SharedDict>> = RwLock::new(None);
+
+/// How many times `SHARED_DEFAULT` was replaced, so threads notice a reload
+#[cfg(feature = "bundled-corpus")]
+static SHARED_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The model behind the free functions of a thread
+#[cfg(feature = "bundled-corpus")]
+struct DefaultModel {
+    model: LanguageModel,
+    /// The generation of the shared dictionary the model uses,
+    /// `None` once `set_thread_corpus` gave the thread a corpus of its own
+    generation: Option<u64>,
 }
 
-/// Returns the path to build a dictionary of all the costs of each word.
-/// The file is a text file with each line containing a word.
-/// The order of the words in the file will define the score of each word.
-/// The further a word is from the start of the file, the higher its score, thus it will be
-/// less likely to be used.
-macro_rules! corpus {
-    () => {
-        "corpus.txt"
-    };
+#[cfg(feature = "bundled-corpus")]
+thread_local! {
+    /// The model behind the free functions.
+    /// Every thread holds its own settings but they share the default dictionary, a thread
+    /// only takes the read lock again after `reload_default_corpus`.
+    static DEFAULT_MODEL: RefCell<DefaultModel> = RefCell::new(DefaultModel {
+        model: LanguageModel::default(),
+        generation: Some(0),
+    });
+
+    /// The models of `split_auto_language`, one per bundled language once it was detected
+    static LANGUAGE_MODELS: RefCell<HashMap<Language, LanguageModel>> = RefCell::new(HashMap::new());
 }
 
-fn lines_from_file(corpus_path: String) -> Vec<String> {
-    if corpus_path.is_empty() {
-        let my_str = include_str!(corpus!());
-        my_str.lines().map(|l| l.to_string()).collect()
-    } else {
-        std::fs::read_to_string(corpus_path)
-            .unwrap()
-            .lines()
-            .map(|l| l.to_string())
-            .collect()
-    }
+/// Run `f` with the default model of the current thread, loading it if needed
+#[cfg(feature = "bundled-corpus")]
+fn with_default_model<T>(f: impl FnOnce(&LanguageModel) -> T) -> T {
+    DEFAULT_MODEL.with(|default| {
+        let mut default = default.borrow_mut();
+        let DefaultModel { model, generation } = &mut *default;
+        if let Some(generation) = generation {
+            let current = SHARED_GENERATION.load(Ordering::Acquire);
+            if model.cost_dict.is_none() || *generation != current {
+                let (current, (cost_dict, casings)) = shared_default();
+                model.cost_dict = Some(cost_dict);
+                model.casings = casings;
+                model.invalidate_indexes();
+                *generation = current;
+            }
+        }
+        model.ensure_loaded();
+        f(model)
+    })
 }
 
-/// Get the cost dictionary from a list of words
-fn get_cost_dict(corpus_path: String) -> (HashMap<String, f32>, i32) {
-    let mut dict = HashMap::new();
-    let words = lines_from_file(corpus_path);
-    let words_length = words.len() as f32;
-    let mut max_word = 0;
-    for (idx, word) in words.iter().enumerate() {
-        let a = (idx + 1) as f32;
-        let c = a * words_length.ln();
-        let z = c.ln();
-        dict.insert(word.to_string(), z);
-    }
-    words.iter().for_each(|word| {
-        let word_cost = word.chars().count() as i32;
-        if word_cost > max_word {
-            max_word = word_cost;
+/// The shared default dictionary with its generation, built from the bundled corpus if needed
+#[cfg(feature = "bundled-corpus")]
+fn shared_default() -> (u64, SharedDict) {
+    {
+        let shared = SHARED_DEFAULT.read().unwrap_or_else(|err| err.into_inner());
+        if let Some(dict) = shared.as_ref() {
+            return (SHARED_GENERATION.load(Ordering::Acquire), dict.clone());
         }
+    }
+    let mut shared = SHARED_DEFAULT
+        .write()
+        .unwrap_or_else(|err| err.into_inner());
+    let dict = shared.get_or_insert_with(|| {
+        let mut model = LanguageModel::default();
+        model.ensure_loaded();
+        (model.cost_dict.unwrap(), model.casings)
     });
-    return (dict, max_word);
+    (SHARED_GENERATION.load(Ordering::Acquire), dict.clone())
 }
 
-fn best_match(i: i32, text: String, cost: &mut Vec<f32>) -> (f32, f32) {
-    let max = vec![0, i - COST_DICT.1].into_iter().max().unwrap() as usize;
-    let mut slice: Vec<f32> = cost[max..i as usize].to_vec();
-    slice.sort_by(|a, b| b.partial_cmp(a).unwrap());
-    let mut array_min: Vec<(f32, f32)> = Vec::new();
-    for (k, c) in slice.iter().enumerate() {
-        let word_cost = COST_DICT
-            .0
-            .get(
-                &text[(i - k as i32 - 1) as usize..i as usize]
-                    .to_string()
-                    .to_lowercase(),
-            )
-            .map_or(f32::MAX, |x| *x);
-        array_min.push((c + word_cost, k as f32 + 1.0));
-    }
-    return array_min
-        .into_iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+/// Replace the shared default dictionary, every thread using it switches on its next split
+#[cfg(feature = "bundled-corpus")]
+fn install_default(dict: SharedDict) {
+    let mut shared = SHARED_DEFAULT
+        .write()
+        .unwrap_or_else(|err| err.into_inner());
+    *shared = Some(dict);
+    SHARED_GENERATION.fetch_add(1, Ordering::Release);
 }
 
-fn build_cost_array(text_length: u32, text: String, cost: &mut Vec<f32>) {
-    for i in 1..(text_length + 1) {
-        let (c, _k) = best_match(i as i32, text.clone(), cost);
-        cost.push(c);
-    }
+/// Use another corpus for the free functions, in all the threads.
+/// The new dictionary is built right away, the threads without a corpus of their own, see
+/// `set_thread_corpus`, use it from their next split on.
+/// # Arguments
+/// * `path` - The corpus file, an empty path selects the bundled corpus
+/// # Returns
+/// An error if the corpus cannot be read or has no word, the dictionary is then unchanged
+#[cfg(feature = "bundled-corpus")]
+pub fn reload_default_corpus(path: &str) -> Result<(), SplitError> {
+    let model = LanguageModel::from_corpus(path.to_string())?;
+    install_default((model.cost_dict.unwrap(), model.casings));
+    Ok(())
 }
 
-fn minimal_cost(text: String, cost: &mut Vec<f32>, text_length: u32) -> Vec<String> {
-    let mut result: Vec<String> = Vec::new();
-    let mut i = text_length;
-    while i > 0 {
-        let (_c, k) = best_match(i as i32, text.clone(), cost);
-        result.push(text[(i - k as u32) as usize..i as usize].to_string());
-        i -= k as u32;
-    }
-    return result;
+/// Use an in-memory corpus for the free functions, in all the threads, like
+/// `reload_default_corpus` but without any file
+/// # Arguments
+/// * `words` - The corpus lines, the most frequent word first
+/// # Returns
+/// An error if the corpus has no word, the dictionary is then unchanged
+/// # Examples
+/// ```
+/// rsplitter::set_corpus(vec!["ban".to_string(), "kof".to_string(), "jordan".to_string()])
+///     .unwrap();
+/// assert_eq!(rsplitter::split("bankofjordan".to_string()), "ban kof jordan");
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn set_corpus(words: Vec<String>) -> Result<(), SplitError> {
+    let mut model = LanguageModel::default();
+    let lines = model.clean_lines(words);
+    model.load_lines(&lines)?;
+    install_default((model.cost_dict.unwrap(), model.casings));
+    Ok(())
+}
+
+/// Build the cost dictionary of a corpus embedded in the binary, see
+/// `LanguageModel::from_bytes`
+/// # Arguments
+/// * `bytes` - The UTF-8 corpus, one entry per line like a corpus file
+/// # Returns
+/// The cost of every word and the length of the longest one, or an error if the bytes are
+/// not UTF-8 or have no word
+pub fn get_cost_dict_from_bytes(bytes: &[u8]) -> Result<CostDict, SplitError> {
+    let model = LanguageModel::from_bytes(bytes)?;
+    Ok(Arc::unwrap_or_clone(
+        model.cost_dict.expect("from_bytes loads the dictionary"),
+    ))
+}
+
+/// Use another corpus for the free functions, in the current thread only.
+/// The dictionary is built lazily on the next split, other threads are not affected.
+/// # Arguments
+/// * `corpus_path` - The corpus file, an empty path selects the bundled corpus
+#[cfg(feature = "bundled-corpus")]
+pub fn set_thread_corpus(corpus_path: String) {
+    DEFAULT_MODEL.with(|default| {
+        *default.borrow_mut() = DefaultModel {
+            model: LanguageModel::new(corpus_path),
+            generation: None,
+        }
+    });
+}
+
+/// The bundled corpus, `src/corpus.txt` encoded by the build script, see `huffman`.
+/// Each line of the corpus holds a word, the order of the words defines their costs: the
+/// further a word is from the start of the file, the higher its cost, thus it will be less
+/// likely to be used.
+#[cfg(feature = "bundled-corpus")]
+static ENCODED_CORPUS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/corpus.txt.huffman"));
+
+/// The bundled corpus, used whenever no corpus path is given, decoded on first use
+#[cfg(feature = "bundled-corpus")]
+pub(crate) fn default_corpus() -> &'static str {
+    static CORPUS: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    CORPUS.get_or_init(|| {
+        let corpus = huffman::decode(ENCODED_CORPUS).expect("the bundled corpus decodes");
+        String::from_utf8(corpus).expect("the bundled corpus is UTF-8")
+    })
 }
 
 // Returns the best match for a word in the corpus.
 /// A word is considered to be a match if it is within `max_distance` of the start of the word.
 /// # Arguments
-/// * `text` - The text to be split
+/// * `text` - The text to be split, a `String` or a `&str`
 /// # Returns
 /// A String object containing the split text
 /// # Examples
@@ -106,20 +237,274 @@ fn minimal_cost(text: String, cost: &mut Vec<f32>, text_length: u32) -> Vec<Stri
 /// assert_eq!(result, "rust is great");
 /// ```
 /// Result: "This is a test"
-pub fn split(text: String) -> String {
-    let mut cost: Vec<f32> = Vec::new();
-    cost.push(0.0);
-    let text_length = text.chars().count() as u32;
-    build_cost_array(text_length, text.clone(), &mut cost);
-    let texts = minimal_cost(text.clone(), &mut cost, text_length);
-    return texts.into_iter().rev().collect::<Vec<String>>().join(" ");
+#[cfg(feature = "bundled-corpus")]
+pub fn split(text: impl AsRef<str>) -> String {
+    with_default_model(|model| model.split_traced(text.as_ref(), &mut Trace::new()))
+}
+
+/// Split the glued words of a paragraph with the bundled corpus, the tokens that are
+/// dictionary words and the whitespace between the tokens kept as they are, see
+/// `LanguageModel::split_text`
+/// # Examples
+/// ```
+/// let text = "please visitbankofjordan today\nthanks";
+/// assert_eq!(rsplitter::split_text(text), "please visit bank of jordan today\nthanks");
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_text(text: impl AsRef<str>) -> String {
+    with_default_model(|model| model.split_unknown_tokens(text.as_ref()))
+}
+
+/// Split a text with the bundled corpus into its words, e.g. for another tokenizer
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The words of `split`, without the whitespace
+/// # Examples
+/// ```
+/// use rsplitter::split_words;
+/// assert_eq!(split_words("rustisgreat now"), ["rust", "is", "great", "now"]);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_words(text: &str) -> Vec<String> {
+    with_default_model(|model| model.words_of(text))
+}
+
+/// Split a batch of texts with the bundled corpus, spread over the available cores like
+/// `LanguageModel::split_many`. Every thread reads the one dictionary of the default model.
+/// # Arguments
+/// * `texts` - The texts to be split
+/// # Returns
+/// The split texts, in the order of `texts`
+/// # Examples
+/// ```
+/// use rsplitter::split_batch;
+/// let texts = ["rustisgreat".to_string(), "throwbackthursday".to_string()];
+/// assert_eq!(split_batch(&texts), ["rust is great", "throwback thursday"]);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_batch(texts: &[String]) -> Vec<String> {
+    with_default_model(|model| model.split_chunks(texts, language_model::available_threads()))
+}
+
+/// The byte offsets of the words of a split with the bundled corpus, see
+/// `LanguageModel::split_spans`
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The `(start, end)` byte offsets of every word in `text`
+/// # Examples
+/// ```
+/// use rsplitter::split_spans;
+/// assert_eq!(split_spans("rust isgreat"), [(0, 4), (5, 7), (7, 12)]);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_spans(text: &str) -> Vec<(usize, usize)> {
+    with_default_model(|model| model.word_spans(text))
+}
+
+/// The character offsets of the words of a split with the bundled corpus, see
+/// `LanguageModel::split_char_spans`
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The `(start, end)` code point offsets of every word in `text`
+#[cfg(feature = "bundled-corpus")]
+pub fn split_char_spans(text: &str) -> Vec<(usize, usize)> {
+    let spans = split_spans(text);
+    language_model::char_spans(text, &spans)
+}
+
+/// Split a text with a dictionary of word costs, without any corpus or default model.
+/// The dictionary is copied into a temporary model, build a `LanguageModel` once to split
+/// many texts.
+/// # Arguments
+/// * `text` - The text to be split
+/// * `dict` - The cost of every lowercase word, a cheaper word is more likely
+/// * `max_word` - The length of the longest word of `dict`, in characters
+/// # Examples
+/// ```
+/// use rsplitter::split_with_dictionary;
+/// use std::collections::HashMap;
+/// let dict: HashMap<String, f32> = [("rust", 1.0), ("is", 1.0), ("great", 1.0)]
+///     .into_iter()
+///     .map(|(word, cost)| (word.to_string(), cost))
+///     .collect();
+/// assert_eq!(split_with_dictionary("rustisgreat", &dict, 5), "rust is great");
+/// ```
+pub fn split_with_dictionary<S: BuildHasher + Clone>(
+    text: &str,
+    dict: &HashMap<String, f32, S>,
+    max_word: i32,
+) -> String {
+    let mut model = LanguageModel::with_hasher(String::new(), dict.hasher().clone());
+    model.cost_dict = Some(Arc::new((dict.clone(), max_word)));
+    model.split_traced(text, &mut Trace::new())
+}
+
+/// Split a text with the bundled corpus, also returning the cost array of the dynamic program
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The split text and the accumulated cost of every prefix of the text
+/// # Examples
+/// ```
+/// use rsplitter::split_debug;
+/// let (result, cost) = split_debug("rustisgreat".to_string());
+/// assert_eq!(result, "rust is great");
+/// assert_eq!(cost.len(), 12);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_debug(text: String) -> (String, Vec<f32>) {
+    let mut trace = Trace::new();
+    let result = with_default_model(|model| model.split_traced(&text, &mut trace));
+    (result, trace.cost)
+}
+
+/// Count the words of a split with the bundled corpus, without building them
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// How many words `split` would return
+/// # Examples
+/// ```
+/// use rsplitter::segment_count;
+/// assert_eq!(segment_count("thequickbrownfox".to_string()), 4);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn segment_count(text: String) -> usize {
+    with_default_model(|model| model.count_words(&text))
+}
+
+/// Split a text at the Unicode word boundaries first, then split the glued words with the
+/// bundled corpus, see `LanguageModel::split_unicode_words`
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The words of the text
+/// # Examples
+/// ```
+/// use rsplitter::split_unicode_words;
+/// assert_eq!(split_unicode_words("東京 isbig."), ["東", "京", "is", "big"]);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_unicode_words(text: &str) -> Vec<String> {
+    with_default_model(|model| model.split_unicode_text(text))
+}
+
+/// Split every line of a multi-line text independently.
+/// Words are never merged across a line boundary and the newlines are kept as they are.
+/// # Arguments
+/// * `text` - The lines to be split
+/// # Returns
+/// A String object containing the split lines, joined with `\n`
+/// # Examples
+/// ```
+/// use rsplitter::split_lines;
+/// let result = split_lines("rustisgreat\nbankofjordan");
+/// assert_eq!(result, "rust is great\nbank of jordan");
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_lines(text: &str) -> String {
+    text.split('\n')
+        .map(|line| split(String::from(line)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// List, at every position of a text, the candidate words considered with the bundled corpus
+/// # Arguments
+/// * `text` - The text to be explained
+/// # Returns
+/// One `PositionExplanation` per non-whitespace character
+/// # Examples
+/// ```
+/// use rsplitter::explain;
+/// let explanations = explain("rustisgreat".to_string());
+/// let last = &explanations[explanations.len() - 1];
+/// assert_eq!(last.candidates[last.winner].word, "great");
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn explain(text: String) -> Vec<PositionExplanation> {
+    with_default_model(|model| model.explain_text(&text))
+}
+
+/// Split a text with the model that explains it best, e.g. the one of its language.
+/// Every model splits the text and the one with the lowest cost per character wins, the
+/// unknown words costing more than the rarest word of the model for each of their characters.
+/// # Arguments
+/// * `text` - The text to be split
+/// * `models` - The candidate models, loaded, e.g. with `LanguageModel::from_corpus`
+/// # Returns
+/// The split of the best model and its index in `models`, `None` when `models` is empty
+/// # Panics
+/// If one of the models is not loaded
+pub fn split_auto(text: &str, models: &[&LanguageModel]) -> Option<(String, usize)> {
+    let index = (0..models.len()).min_by(|a, b| {
+        models[*a]
+            .normalized_cost(text)
+            .total_cmp(&models[*b].normalized_cost(text))
+    })?;
+    Some((models[index].split_traced(text, &mut Trace::new()), index))
+}
+
+/// Split the name of a domain with the bundled corpus, see `LanguageModel::split_domain`
+/// # Arguments
+/// * `url` - A URL or a host name
+/// # Returns
+/// The words of the name and of the subdomains, and the public suffix
+/// # Examples
+/// ```
+/// use rsplitter::split_domain;
+/// assert_eq!(split_domain("choosespain.com").name(), "choose spain");
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_domain(url: &str) -> DomainSplit {
+    with_default_model(|model| model.domain_of(url))
+}
+
+/// Split a text with the bundled corpus of its language, guessed with `detect_language`,
+/// e.g. for social media posts whose language is not known up front
+/// # Arguments
+/// * `text` - The text to be split
+/// # Returns
+/// The split text and the language it was split in
+/// # Examples
+/// ```
+/// use rsplitter::{split_auto_language, Language};
+/// let (split, language) = split_auto_language("rustisgreat");
+/// assert_eq!(split, "rust is great");
+/// assert_eq!(language, Language::English);
+/// ```
+#[cfg(feature = "bundled-corpus")]
+pub fn split_auto_language(text: &str) -> (String, Language) {
+    let language = detect_language(text);
+    let split = LANGUAGE_MODELS.with(|models| {
+        let mut models = models.borrow_mut();
+        let model = models
+            .entry(language)
+            .or_insert_with(|| LanguageModel::for_language(language));
+        model.ensure_loaded();
+        model.split_traced(text, &mut Trace::new())
+    });
+    (split, language)
 }
 
 // pub fn split() {}
 
-#[cfg(test)]
+#[cfg(all(test, feature = "bundled-corpus"))]
+// The first tests split owned texts, from when `split` only took a `String`
+#[allow(clippy::unnecessary_to_owned)]
 mod tests {
     use super::*;
+
+    /// Write a throwaway corpus file and return its path
+    fn write_corpus(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rsplitter_{}_{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
     #[test]
     fn test_split() {
         let text = "bankofjordan";
@@ -133,6 +518,7 @@ mod tests {
         let mut language_model: language_model::LanguageModel = language_model::LanguageModel {
             corpus_path: "".to_string(),
             cost_dict: None,
+            ..Default::default()
         };
         let result = language_model.split(text.to_string());
         assert_eq!(result, "The quick brown fox jumps over the lazy dog");
@@ -160,4 +546,2755 @@ mod tests {
         assert_eq!(result, "The quick brown fox jumps over the lazy dog");
         assert!(duration.as_millis() < 300);
     }
+
+    #[test]
+    fn test_frequency_cost_model() {
+        let corpus = write_corpus("frequency.txt", "rust 100000\nis 90\ngreat 80\n");
+        let mut language_model = LanguageModel::new(corpus);
+        language_model.cost_model = CostModel::Frequency;
+        assert_eq!(
+            language_model.split(String::from("rustisgreat")),
+            "rust is great"
+        );
+        let costs = &language_model.cost_dict.as_ref().unwrap().0;
+        // Adjacent ranks, but three orders of magnitude apart in frequency
+        assert!(costs["is"] - costs["rust"] > 5.0);
+        assert!(costs["great"] - costs["is"] < 0.2);
+        // A count that is not finite is skipped like a missing one
+        let corpus = write_corpus("frequency_inf.txt", "rust NaN\nis 5\ngreat inf\nx 1e400\n");
+        let mut lm = LanguageModel::new(corpus);
+        lm.cost_model = CostModel::Frequency;
+        assert_eq!(lm.cost("is"), Some(0.0));
+        assert_eq!((lm.cost("great"), lm.cost("x")), (None, None));
+    }
+
+    #[test]
+    fn test_split_lines() {
+        let result = split_lines("thequickbrownfox\njumpsoverthelazydog");
+        assert_eq!(result, "the quick brown fox\njumps over the lazy dog");
+    }
+
+    #[test]
+    fn test_split_keeps_whitespace_boundaries() {
+        let result = split(String::from("foo\tbarbaz\nqux"));
+        let lines: Vec<&str> = result.split('\n').collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "foo\tbar baz");
+        // "qux" is not in the corpus, only check nothing leaked across the newline
+        assert_eq!(lines[1].replace(' ', ""), "qux");
+    }
+
+    #[test]
+    fn test_sentence_case() {
+        let corpus = write_corpus("sentence_case.txt", "the\ncat\nsat\n'twas\nnight\n");
+        let mut language_model = LanguageModel::new(corpus);
+        language_model.case_mode = CaseMode::SentenceCase;
+        assert_eq!(
+            language_model.split(String::from("thecatsat")),
+            "The cat sat"
+        );
+        assert_eq!(
+            language_model.split(String::from("'twasnight")),
+            "'Twas night"
+        );
+        assert_eq!(language_model.split(String::from("42cat")), "42 cat");
+    }
+
+    #[test]
+    fn test_title_and_lowercase() {
+        let corpus = write_corpus("title_case.txt", "the\ncat\nsat\n'twas\nnight\n");
+        let mut lm = LanguageModel::new(corpus);
+        lm.case_mode = CaseMode::TitleCase;
+        assert_eq!(lm.split("thecatSAT"), "The Cat SAT");
+        assert_eq!(lm.split("'twasnight 42cat"), "'Twas Night 42 Cat");
+        let tokens = lm.split_tokens(String::from("thecat"));
+        assert_eq!(tokens[2], Token::Word(String::from("Cat")));
+        lm.case_mode = CaseMode::Lowercase;
+        assert_eq!(lm.split("TheCatSAT"), "the cat sat");
+        let tokens = lm.split_tokens(String::from("TheCat"));
+        assert_eq!(tokens[0], Token::Word(String::from("the")));
+        let mut lm = LanguageModel::builder()
+            .case_mode(CaseMode::SentenceCase)
+            .build()
+            .unwrap();
+        assert_eq!(lm.split("thequickbrownfox"), "The quick brown fox");
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let corpus = write_corpus("sentences.txt", "the\ncat\nsat\ndog\nran\n");
+        let mut language_model = LanguageModel::new(corpus);
+        let sentences = language_model.split_sentences(
+            String::from("ThecatsatThedogran"),
+            &SentenceRules::default(),
+        );
+        assert_eq!(
+            sentences,
+            vec![vec!["The", "cat", "sat"], vec!["The", "dog", "ran"]]
+        );
+    }
+
+    #[test]
+    fn test_spelling_correction() {
+        let corpus = write_corpus("spelling.txt", "hello\nworld\n");
+        let mut language_model = LanguageModel::new(corpus);
+        assert_ne!(language_model.split(String::from("helllo")), "hello");
+        language_model.spelling_correction = true;
+        let (result, corrections) = language_model.split_with_corrections(String::from("helllo"));
+        assert_eq!(result, "hello");
+        assert_eq!(
+            corrections,
+            vec![Correction {
+                original: String::from("helllo"),
+                corrected: String::from("hello"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_trie_backend_matches_hashmap() {
+        let texts = [
+            "Thequickbrownfoxjumpsoverthelazydog",
+            "bankofjordan",
+            "itwasthebestoftimesitwastheworstoftimes",
+            "wethepeopleoftheunitedstatesinordertoformamoreperfectunion",
+        ];
+        let mut hash_map = LanguageModel::new(String::new());
+        let mut trie = LanguageModel::new(String::new());
+        trie.backend = DictionaryBackend::Trie;
+        for text in texts {
+            assert_eq!(
+                trie.split(String::from(text)),
+                hash_map.split(String::from(text))
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_debug() {
+        let text = "thequickbrownfox";
+        let (result, cost) = split_debug(String::from(text));
+        assert_eq!(result, split(String::from(text)));
+        assert_eq!(cost.len(), text.len() + 1);
+        assert_eq!(cost[0], 0.0);
+        // Each chosen word adds to the cost, so it grows from one boundary to the next
+        let mut boundaries = vec![0];
+        for word in result.split(' ') {
+            boundaries.push(boundaries[boundaries.len() - 1] + word.len());
+        }
+        assert!(boundaries
+            .windows(2)
+            .all(|pair| cost[pair[0]] < cost[pair[1]]));
+        // A space is a free boundary
+        let (_, spaced_cost) = split_debug(String::from("thequickbrownfox jumps"));
+        assert_eq!(spaced_cost.len(), 23);
+        assert_eq!(spaced_cost[16], cost[16]);
+        assert_eq!(spaced_cost[17], cost[16]);
+    }
+
+    #[test]
+    fn test_explain_reconstructs_split() {
+        let text = "thequickbrownfox";
+        let explanations = explain(String::from(text));
+        assert_eq!(explanations.len(), text.len());
+        let mut words = Vec::new();
+        let mut position = text.len();
+        while position > 0 {
+            let explanation = &explanations[position - 1];
+            assert_eq!(explanation.position, position);
+            let word = &explanation.candidates[explanation.winner].word;
+            position -= word.len();
+            words.push(word.to_string());
+        }
+        words.reverse();
+        assert_eq!(words.join(" "), split(String::from(text)));
+    }
+
+    #[test]
+    fn test_beam_width() {
+        let mut exact = LanguageModel::new(String::new());
+        let mut beam = LanguageModel::new(String::new());
+        beam.beam_width = Some(1000);
+        for text in ["thequickbrownfox", "bankofjordan", "itwasthebestoftimes"] {
+            assert_eq!(
+                beam.split(String::from(text)),
+                exact.split(String::from(text))
+            );
+        }
+        let corpus = write_corpus("beam.txt", "in\ningot\ngo\nt\n");
+        let mut exact = LanguageModel::new(corpus.clone());
+        let mut greedy = LanguageModel::new(corpus);
+        greedy.beam_width = Some(1);
+        assert_eq!(exact.split(String::from("ingotin")), "ingot in");
+        // The cheapest first word is "in", and each following step is forced
+        assert_eq!(greedy.split(String::from("ingotin")), "in go t in");
+        greedy.beam_width = Some(0);
+        assert_eq!(greedy.split(String::from("ingotin")), "in go t in");
+        assert!(matches!(
+            LanguageModel::builder().beam_width(0).build(),
+            Err(SplitError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_model_round_trip() {
+        let corpus = write_corpus("round_trip.txt", "rust\nis\ngreat\ncafé\n");
+        let mut language_model = LanguageModel::new(corpus);
+        let mut buffer = Vec::new();
+        language_model.to_writer(&mut buffer).unwrap();
+        let mut restored = LanguageModel::from_reader(std::io::Cursor::new(&buffer)).unwrap();
+        assert_eq!(restored.cost_dict, language_model.cost_dict);
+        assert_eq!(restored.split(String::from("rustisgreat")), "rust is great");
+        assert!(matches!(
+            LanguageModel::from_reader(&buffer[..buffer.len() - 1]),
+            Err(SplitError::Io(_))
+        ));
+        assert!(matches!(
+            LanguageModel::from_reader(&b"not a model"[..]),
+            Err(SplitError::InvalidModel(_))
+        ));
+    }
+
+    #[test]
+    fn test_thread_corpus_is_per_thread() {
+        let corpus = write_corpus("thread.txt", "ban\nkof\njordan\n");
+        let custom = std::thread::spawn(move || {
+            set_thread_corpus(corpus);
+            split(String::from("bankofjordan"))
+        })
+        .join()
+        .unwrap();
+        assert_eq!(custom, "ban kof jordan");
+        assert_eq!(split(String::from("bankofjordan")), "bank of jordan");
+    }
+
+    #[test]
+    fn test_memory_estimate_scales_with_vocabulary() {
+        let words: Vec<String> = (0..2000).map(|i| format!("word{}", i)).collect();
+        let mut small = LanguageModel::new(write_corpus("small.txt", &words[..200].join("\n")));
+        let mut large = LanguageModel::new(write_corpus("large.txt", &words.join("\n")));
+        assert_eq!(small.memory_estimate(), 0);
+        small.split(String::new());
+        large.split(String::new());
+        assert!(large.memory_estimate() > 5 * small.memory_estimate());
+        Arc::get_mut(large.cost_dict.as_mut().unwrap())
+            .unwrap()
+            .0
+            .retain(|word, _| word.len() < 6);
+        let before = large.memory_estimate();
+        large.shrink_to_fit();
+        assert!(large.memory_estimate() < before);
+    }
+
+    #[test]
+    fn test_custom_hasher() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+        let mut lm: LanguageModel<BuildHasherDefault<DefaultHasher>> =
+            LanguageModel::with_hasher(String::new(), BuildHasherDefault::default());
+        assert_eq!(
+            lm.split(String::from("thequickbrownfoxjumpsoverthelazydog")),
+            split(String::from("thequickbrownfoxjumpsoverthelazydog"))
+        );
+        lm.backend = DictionaryBackend::Trie;
+        lm.trie = None;
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+    }
+
+    #[test]
+    fn test_empty_corpus_is_an_error() {
+        let empty = write_corpus("empty.txt", "");
+        let blank = write_corpus("blank.txt", "\n  \n\t\n");
+        for corpus in [empty, blank] {
+            assert!(matches!(
+                LanguageModel::from_corpus(corpus),
+                Err(SplitError::EmptyCorpus)
+            ));
+        }
+        assert!(LanguageModel::from_corpus(String::new()).is_ok());
+    }
+
+    #[test]
+    fn test_messy_corpus_lines() {
+        let messy = write_corpus("messy.txt", "the  \r\n\r\n  cat\r\n\nsat \t\n");
+        let clean = write_corpus("clean.txt", "the\ncat\nsat\n");
+        let mut messy = LanguageModel::from_corpus(messy).unwrap();
+        let clean = LanguageModel::from_corpus(clean).unwrap();
+        let mut words: Vec<&String> = messy.cost_dict.as_ref().unwrap().0.keys().collect();
+        words.sort();
+        assert_eq!(words, ["cat", "sat", "the"]);
+        assert_eq!(messy.cost_dict, clean.cost_dict);
+        assert_eq!(messy.split(String::from("thecatsat")), "the cat sat");
+    }
+
+    #[test]
+    fn test_corpus_comments_are_skipped() {
+        let annotated = write_corpus(
+            "annotated.txt",
+            "# most frequent\nthe\n  # animals\ncat\n#verbs\nsat\n",
+        );
+        let plain = write_corpus("plain.txt", "the\ncat\nsat\n");
+        let annotated_model = LanguageModel::from_corpus(annotated.clone()).unwrap();
+        let plain_model = LanguageModel::from_corpus(plain).unwrap();
+        assert_eq!(annotated_model.cost_dict, plain_model.cost_dict);
+
+        let mut kept = LanguageModel::new(annotated);
+        kept.comment_prefix = None;
+        kept.try_load().unwrap();
+        // "#verbs" is then a word, and the comments with spaces phrases
+        assert_eq!(kept.cost_dict.as_ref().unwrap().0.len(), 6);
+    }
+
+    #[test]
+    fn test_corpus_byte_order_mark() {
+        let bom = write_corpus("bom.txt", "\u{feff}the\ncat\nsat\n");
+        let plain = write_corpus("no_bom.txt", "the\ncat\nsat\n");
+        let bom = LanguageModel::from_corpus(bom).unwrap();
+        let plain = LanguageModel::from_corpus(plain).unwrap();
+        let first_cost = |lm: &LanguageModel| lm.cost_dict.as_ref().unwrap().0.get("the").copied();
+        assert!(first_cost(&plain).is_some());
+        assert_eq!(first_cost(&bom), first_cost(&plain));
+    }
+
+    #[test]
+    fn test_mixed_case_corpus() {
+        let corpus = write_corpus("mixed_case.txt", "The\nCat\nSAT\ncat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert_eq!(lm.cost_dict.as_ref().unwrap().0.len(), 3);
+        assert_eq!(lm.split(String::from("thecatsat")), "the cat sat");
+        assert_eq!(lm.split(String::from("TheCATSat")), "The CAT Sat");
+    }
+
+    #[test]
+    fn test_ascii_fast_path_parity() {
+        use crate::language_model::Run;
+        let inputs = [
+            "Thequickbrownfoxjumpsoverthelazydog",
+            "itwasthebestoftimesitwastheworstoftimes",
+            "HelloWorld2024isit's",
+            "xqzvk",
+            "",
+        ];
+        for backend in [DictionaryBackend::HashMap, DictionaryBackend::Trie] {
+            let mut lm = LanguageModel::new(String::new());
+            lm.backend = backend;
+            lm.ensure_loaded();
+            for input in inputs {
+                let (mut fast, mut general) = (Trace::new(), Trace::new());
+                assert_eq!(
+                    lm.split_run(
+                        Run::new(input, &lm.number_separators, &lm.currency_symbols),
+                        &mut fast
+                    ),
+                    lm.split_run(
+                        Run::unicode(input, &lm.number_separators, &lm.currency_symbols),
+                        &mut general
+                    )
+                );
+                assert_eq!(fast.cost, general.cost);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ffi() {
+        use std::ffi::{CStr, CString};
+        let text = CString::new("rustisgreat").unwrap();
+        let invalid: &[u8] = b"\xff\xfe\0";
+        unsafe {
+            let split = ffi::rsplitter_split(text.as_ptr());
+            assert_eq!(CStr::from_ptr(split).to_str().unwrap(), "rust is great");
+            ffi::rsplitter_free(split);
+            assert!(ffi::rsplitter_split(std::ptr::null()).is_null());
+            assert!(ffi::rsplitter_split(invalid.as_ptr().cast()).is_null());
+
+            let path = CString::new("").unwrap();
+            let model = ffi::rsplitter_model_new(path.as_ptr());
+            assert!(!model.is_null());
+            let split = ffi::rsplitter_model_split(model, text.as_ptr());
+            assert_eq!(CStr::from_ptr(split).to_str().unwrap(), "rust is great");
+            ffi::rsplitter_free(split);
+            assert!(ffi::rsplitter_model_split(model, std::ptr::null()).is_null());
+            ffi::rsplitter_model_free(model);
+
+            let missing = CString::new("/does/not/exist").unwrap();
+            assert!(ffi::rsplitter_model_new(missing.as_ptr()).is_null());
+
+            let corpus = b"ban\nkof\njordan\n";
+            let model = ffi::rsplitter_model_from_bytes(corpus.as_ptr(), corpus.len());
+            let text = CString::new("bankofjordan").unwrap();
+            let split = ffi::rsplitter_model_split(model, text.as_ptr());
+            assert_eq!(CStr::from_ptr(split).to_str().unwrap(), "ban kof jordan");
+            ffi::rsplitter_free(split);
+            ffi::rsplitter_model_free(model);
+            assert!(ffi::rsplitter_model_from_bytes(std::ptr::null(), 0).is_null());
+            assert!(ffi::rsplitter_model_from_bytes(invalid.as_ptr(), 2).is_null());
+        }
+    }
+
+    #[test]
+    fn test_segment_count() {
+        let inputs = [
+            "Thequickbrownfoxjumpsoverthelazydog",
+            "theboy'sdogate15012apples",
+            "rust is  great\nbankofjordan",
+            "xqzvk",
+            "",
+        ];
+        for input in inputs {
+            let words = split(String::from(input));
+            assert_eq!(
+                segment_count(String::from(input)),
+                words.split_whitespace().count()
+            );
+        }
+        // Phrases and the other settings of the split are counted like `split_words`
+        let mut lm = LanguageModel::new(write_corpus("count_phrases", "i\nlove\nnew york\n"));
+        assert_eq!(lm.split_words("ilovenewyork").len(), 4);
+        assert_eq!(lm.segment_count(String::from("ilovenewyork")), 4);
+        let mut lm = LanguageModel::new(String::new());
+        lm.keep_punctuation = true;
+        let words = lm.split_words("bankofjordan2023!");
+        assert_eq!(words.len(), 3);
+        assert_eq!(lm.segment_count(String::from("bankofjordan2023!")), 3);
+        lm.merge_unknown_shorter_than = Some(3);
+        lm.pin("bankof", &["ban", "kof"]).unwrap();
+        for input in ["xqzvkrustisgreat", "bankof jordan", "newyork2023!"] {
+            assert_eq!(
+                lm.segment_count(String::from(input)),
+                lm.split_words(input).len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let dict = [("a", 1.0), ("b", 1.0), ("ab", 2.0)]
+            .into_iter()
+            .map(|(word, cost)| (word.to_string(), cost))
+            .collect();
+        let mut lm: LanguageModel = LanguageModel {
+            cost_dict: Some(Arc::new((dict, 2))),
+            ..Default::default()
+        };
+        assert_eq!(lm.split(String::from("ab")), "a b");
+        lm.tie_break = TieBreak::LongerWord;
+        assert_eq!(lm.split(String::from("ab")), "ab");
+        assert_eq!(lm.segment_count(String::from("abab")), 2);
+    }
+
+    #[test]
+    fn test_length_penalty_sweep() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.length_penalty(), 0.0);
+        let expected = [
+            (0.0, "it is at the", "it is nowhere"),
+            (5.0, "itis at the", "itis nowhere"),
+            (20.0, "itis atthe", "itis nowhere"),
+        ];
+        for (weight, first, second) in expected {
+            lm.set_length_penalty(weight).unwrap();
+            assert_eq!(lm.length_penalty(), weight);
+            assert_eq!(lm.split(String::from("itisatthe")), first);
+            assert_eq!(lm.split(String::from("itisnowhere")), second);
+        }
+        for weight in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(matches!(
+                lm.set_length_penalty(weight),
+                Err(SplitError::InvalidInput(_))
+            ));
+        }
+        assert_eq!(lm.length_penalty(), 20.0);
+    }
+
+    #[test]
+    fn test_length_penalty() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.segment_count(String::from("itisnowhere")), 3);
+        lm.length_penalty = 5.0;
+        assert_eq!(lm.segment_count(String::from("itisnowhere")), 2);
+        lm.length_penalty = 0.0;
+        assert_eq!(
+            lm.split(String::from("thequickbrownfox")),
+            split(String::from("thequickbrownfox"))
+        );
+    }
+
+    #[test]
+    fn test_numbers_with_separators() {
+        assert_eq!(
+            split(String::from("total1,234.56paid")),
+            "total 1,234.56 paid"
+        );
+        assert_eq!(
+            split(String::from("price1.234,56euros")),
+            "price 1.234,56 euros"
+        );
+        assert_eq!(split(String::from("itcost5.")), "it cost 5 .");
+        let mut lm = LanguageModel::new(String::new());
+        lm.number_separators = vec!['.'];
+        assert_eq!(lm.split(String::from("total1,234.56")), "total 1 , 234.56");
+        lm.beam_width = Some(4);
+        lm.number_separators = vec![',', '.'];
+        assert_eq!(
+            lm.split(String::from("total1,234.56paid")),
+            "total 1,234.56 paid"
+        );
+    }
+
+    #[test]
+    fn test_currency_symbols() {
+        assert_eq!(split(String::from("pay$99now")), "pay $99 now");
+        assert_eq!(
+            split(String::from("costs$1,250.99today")),
+            "costs $1,250.99 today"
+        );
+        let mut lm = LanguageModel::new(String::new());
+        lm.currency_symbols = Vec::new();
+        assert_eq!(lm.split(String::from("pay$99")), "pay $ 99");
+    }
+
+    #[test]
+    fn test_multiple_corpora() {
+        let first = write_corpus("first.txt", "the\ncat\nsat\n");
+        let second = write_corpus("second.txt", "mat\nthe\non\n");
+        let mut one = LanguageModel::from_corpus(first.clone()).unwrap();
+        let scored = one.split_scored(String::from("thecatsatonthemat"));
+        assert_eq!(scored.unknown_words, 2);
+        let paths = [
+            std::path::PathBuf::from(&first),
+            std::path::PathBuf::from(&second),
+        ];
+        let mut both = LanguageModel::from_corpora(&paths).unwrap();
+        assert_eq!(
+            both.split(String::from("thecatsatonthemat")),
+            "the cat sat on the mat"
+        );
+        // "the" keeps its first rank, the words of the second file rank after the first one's
+        let dict = &both.cost_dict.as_ref().unwrap().0;
+        let mut ranked: Vec<&String> = dict.keys().collect();
+        ranked.sort_by(|a, b| dict[*a].total_cmp(&dict[*b]));
+        assert_eq!(ranked, ["the", "cat", "sat", "mat", "on"]);
+    }
+
+    #[test]
+    fn test_add_corpus_weighted() {
+        // "ab" ranks last in both corpora, so "a b" is cheaper unless the domain has priority
+        let mut general = vec![String::from("a"), String::from("b")];
+        general.extend((0..30).map(|i| format!("x{}", i)));
+        general.push(String::from("ab"));
+        let general = write_corpus("general.txt", &general.join("\n"));
+        let mut domain: Vec<String> = (0..30).map(|i| format!("y{}", i)).collect();
+        domain.push(String::from("ab"));
+        let domain = write_corpus("domain.txt", &domain.join("\n"));
+
+        let mut lm = LanguageModel::new(general.clone());
+        lm.add_corpus_weighted(&domain, 1.0).unwrap();
+        assert_eq!(lm.split(String::from("ab")), "a b");
+        assert!(lm.cost_dict.as_ref().unwrap().0.contains_key("y0"));
+
+        let mut lm = LanguageModel::new(general);
+        let before = lm.split(String::from("ab"));
+        lm.add_corpus_weighted(&domain, 2.0).unwrap();
+        assert_eq!(before, "a b");
+        assert_eq!(lm.split(String::from("ab")), "ab");
+        // A weight above 1 never makes a word dearer, even the first ones of a short corpus
+        let short = write_corpus("short.txt", "pa\nqa\nra");
+        let mut plain = LanguageModel::new(String::new());
+        plain.add_corpus_weighted(&short, 1.0).unwrap();
+        let mut weighted = LanguageModel::new(String::new());
+        weighted.add_corpus_weighted(&short, 4.0).unwrap();
+        for word in ["pa", "qa", "ra"] {
+            let (plain, weighted) = (plain.cost(word).unwrap(), weighted.cost(word).unwrap());
+            assert!((0.0..=plain).contains(&weighted), "{}", word);
+        }
+        for weight in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                lm.add_corpus_weighted(&domain, weight),
+                Err(SplitError::InvalidInput(_))
+            ));
+        }
+        assert_eq!(lm.split(String::from("ab")), "ab");
+    }
+
+    #[test]
+    fn test_with_corpora() {
+        let mut general = vec![String::from("a"), String::from("b")];
+        general.extend((0..30).map(|i| format!("x{}", i)));
+        general.push(String::from("ab"));
+        let general = write_corpus("with_corpora.txt", &general.join("\n"));
+        let domain: Vec<String> = (0..30).map(|i| format!("y{}", i)).collect();
+        let mut domain: Vec<&str> = domain.iter().map(String::as_str).collect();
+        domain.push("ab");
+
+        let sources = |weight| {
+            LanguageModel::with_corpora(&[
+                (CorpusSource::File(std::path::PathBuf::from(&general)), 1.0),
+                (CorpusSource::Words(&domain), weight),
+            ])
+            .unwrap()
+        };
+        assert_eq!(sources(1.0).split(String::from("ab")), "a b");
+        assert_eq!(sources(2.0).split(String::from("ab")), "ab");
+
+        let mut lm = LanguageModel::new(general);
+        lm.add_words(&domain, 2.0).unwrap();
+        assert_eq!(lm.split(String::from("ab")), "ab");
+        assert!(matches!(
+            lm.add_words(&["a", "b"], f32::NAN),
+            Err(SplitError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            LanguageModel::with_corpora(&[(CorpusSource::Words(&domain), -1.0)]),
+            Err(SplitError::InvalidInput(_))
+        ));
+        // A word added after a split is not hidden by the cache
+        let mut lm = LanguageModel::new(String::new());
+        lm.cache_capacity = Some(8);
+        assert_eq!(lm.split(String::from("ferris")), "ferris");
+        lm.add_words(&["ferr", "is"], 100.0).unwrap();
+        assert_eq!(lm.split(String::from("ferris")), "ferr is");
+
+        assert!(matches!(
+            LanguageModel::with_corpora(&[(CorpusSource::Words(&[]), 1.0)]),
+            Err(SplitError::EmptyCorpus)
+        ));
+
+        // A single added word costs as much as a word can, the text after it still splits
+        let mut lm = LanguageModel::new(String::new());
+        lm.add_words(&["rustacean"], 1.0).unwrap();
+        assert_eq!(
+            lm.split(String::from("myfriendisarustaceanandyou")),
+            "my friend is a rustacean and you"
+        );
+        let mut lm = LanguageModel::with_corpora(&[
+            (CorpusSource::Bundled(Language::English), 1.0),
+            (CorpusSource::Words(&["acme"]), 5.0),
+        ])
+        .unwrap();
+        assert_eq!(
+            lm.split(String::from("buyacmeproductsnow")),
+            "buy acme products now"
+        );
+    }
+
+    #[test]
+    fn test_split_unicode_words() {
+        assert_eq!(
+            split_unicode_words("Thequickbrownfox, здравствуй мир; can't stop 3.14 カタカナ語"),
+            [
+                "The",
+                "quick",
+                "brown",
+                "fox",
+                "здравствуй",
+                "мир",
+                "can't",
+                "stop",
+                "3.14",
+                "カタカナ",
+                "語"
+            ]
+        );
+        assert_eq!(
+            split_unicode_words("snake_case a.b. «quoted»"),
+            ["snake_case", "a.b", "quoted"]
+        );
+        assert!(split_unicode_words(" ...!? ").is_empty());
+        // A combining mark or a format character stays in the word of the letter before it
+        assert_eq!(
+            split_unicode_words("e\u{301}tude, co\u{ad}operate"),
+            ["e\u{301}tude", "co\u{ad}operate"]
+        );
+    }
+
+    #[test]
+    fn test_acronyms() {
+        let corpus = write_corpus("acronyms.txt", "visit\nthe\nsoon\nus\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert_eq!(
+            lm.split(String::from("visittheUSAsoon")),
+            "visit the US A soon"
+        );
+        lm.acronym_min_length = Some(2);
+        assert_eq!(
+            lm.split(String::from("visittheUSAsoon")),
+            "visit the USA soon"
+        );
+        lm.acronym_min_length = Some(4);
+        assert_eq!(
+            lm.split(String::from("visittheUSAsoon")),
+            "visit the US A soon"
+        );
+        let mut lm = LanguageModel::new(String::new());
+        lm.acronym_min_length = Some(2);
+        assert_eq!(lm.split(String::from("XKCDcomics")), "XKCD comics");
+        assert_eq!(
+            lm.split(String::from("visittheUKsoon")),
+            "visit the UK soon"
+        );
+    }
+
+    #[test]
+    fn test_corpus_casing() {
+        let corpus = write_corpus("corpus_casing.txt", "visit\nLondon\nthe\nparis\nParis\n");
+        let mut lm = LanguageModel::new(corpus);
+        assert_eq!(lm.split(String::from("visitlondon")), "visit london");
+        lm.case_mode = CaseMode::Corpus;
+        assert_eq!(lm.split(String::from("visitlondon")), "visit London");
+        assert_eq!(lm.split(String::from("VISITLONDON")), "VISIT London");
+        // "paris" comes first in the corpus, so the input casing is kept
+        assert_eq!(lm.split(String::from("visitParis")), "visit Paris");
+        assert_eq!(lm.split(String::from("visitparis")), "visit paris");
+    }
+
+    #[test]
+    fn test_is_valid_segmentation() {
+        let corpus = write_corpus("valid_segmentation.txt", "the\ncat\nsat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert!(lm.is_valid_segmentation(&["the", "Cat", "sat"]));
+        assert!(lm.is_valid_segmentation(&["the", "x", "cat"]));
+        assert!(lm.is_valid_segmentation(&[]));
+        assert!(!lm.is_valid_segmentation(&["the", "catsat"]));
+        assert!(!lm.is_valid_segmentation(&["xy", "cat"]));
+        lm.unknown_threshold = 3;
+        assert!(lm.is_valid_segmentation(&["xy", "cat"]));
+    }
+
+    #[test]
+    fn test_split_greedy() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(
+            lm.split_greedy(String::from("rustisgreat")),
+            "rust is great"
+        );
+        assert_eq!(
+            lm.split_greedy(String::from("the cat\tsat")),
+            "the cat\tsat"
+        );
+        // The longest word first is not always the cheapest split
+        assert_eq!(
+            lm.split_greedy(String::from("theyoungman")),
+            "they ou ng man"
+        );
+        assert_eq!(lm.split(String::from("theyoungman")), "the young man");
+        lm.max_input_len = Some(8);
+        assert_eq!(
+            lm.split_greedy(String::from("theyoungman")),
+            lm.split("theyoungman")
+        );
+        lm.max_input_len = None;
+
+        let text = "thequickbrownfoxjumpsoverthelazydog".repeat(50);
+        let start = std::time::Instant::now();
+        lm.split(text.clone());
+        let optimal = start.elapsed();
+        let start = std::time::Instant::now();
+        lm.split_greedy(text);
+        let greedy = start.elapsed();
+        assert!(greedy < optimal, "{:?} >= {:?}", greedy, optimal);
+    }
+
+    #[test]
+    fn test_split_with_algorithm() {
+        let mut lm = LanguageModel::new(String::new());
+        let text = "theyoungmanisgreat";
+        assert_eq!(
+            lm.split_with_algorithm(String::from(text), Algorithm::OptimalDp),
+            lm.split(String::from(text))
+        );
+        assert_eq!(
+            lm.split_with_algorithm(String::from(text), Algorithm::Greedy),
+            lm.split_greedy(String::from(text))
+        );
+        assert_eq!(
+            lm.split_with_algorithm(String::from(text), Algorithm::Bigram),
+            lm.split_bigram(String::from(text))
+        );
+    }
+
+    #[test]
+    fn test_split_bigram() {
+        let corpus = write_corpus("bigram_words.txt", "no\nwhere\nnow\nhere\ni\nam\n");
+        let pairs = write_corpus(
+            "bigram_pairs.txt",
+            "# first second count\nnow here 10\ni am 3\n",
+        );
+        let mut lm = LanguageModel::new(corpus);
+        assert_eq!(
+            lm.split_bigram(String::from("iam nowhere")),
+            "i am no where"
+        );
+        lm.load_bigrams(pairs).unwrap();
+        // "no where" is cheaper word by word, but "now here" is a known pair
+        assert_eq!(lm.split(String::from("iam nowhere")), "i am no where");
+        assert_eq!(
+            lm.split_bigram(String::from("iam nowhere")),
+            "i am now here"
+        );
+        assert_eq!(
+            lm.split_with_algorithm(String::from("nowhere"), Algorithm::Bigram),
+            "now here"
+        );
+        let empty = write_corpus("bigram_empty.txt", "now here\nnow here inf\ni am NaN\n");
+        assert!(matches!(
+            lm.load_bigrams(empty),
+            Err(SplitError::EmptyCorpus)
+        ));
+
+        let mut lm = LanguageModel::new(String::new());
+        let text = "Thequickbrownfoxjumpsoverthelazydog";
+        assert_eq!(
+            lm.split_bigram(String::from(text)),
+            lm.split(String::from(text))
+        );
+        lm.max_word_len = Some(3);
+        let split = lm.split_bigram(String::from(text));
+        assert!(split.split(' ').all(|word| word.chars().count() <= 3));
+        assert_eq!(split, lm.split(String::from(text)));
+    }
+
+    #[test]
+    fn test_split_auto() {
+        let english = write_corpus("auto_english.txt", "the\ncat\nis\non\nmat\nsleeps\n");
+        let french = write_corpus("auto_french.txt", "le\nchat\nest\nsur\nla\ntable\n");
+        let english = LanguageModel::from_corpus(english).unwrap();
+        let french = LanguageModel::from_corpus(french).unwrap();
+        let models = [&english, &french];
+        assert_eq!(
+            split_auto("thecatsleeps", &models),
+            Some((String::from("the cat sleeps"), 0))
+        );
+        assert_eq!(
+            split_auto("lechatest", &models),
+            Some((String::from("le chat est"), 1))
+        );
+        assert_eq!(split_auto("cat", &[&french, &english]).unwrap().1, 1);
+        assert_eq!(split_auto("cat", &[]), None);
+    }
+
+    #[test]
+    fn test_split_with_dictionary() {
+        let dict: HashMap<String, f32> = [("a", 1.0), ("b", 2.0), ("ab", 2.5)]
+            .into_iter()
+            .map(|(word, cost)| (word.to_string(), cost))
+            .collect();
+        assert_eq!(split_with_dictionary("ab", &dict, 2), "ab");
+        assert_eq!(split_with_dictionary("ba b", &dict, 2), "b a b");
+        assert_eq!(split_with_dictionary("AB", &dict, 2), "AB");
+        assert_eq!(split_with_dictionary("", &dict, 2), "");
+    }
+
+    #[test]
+    fn test_dictionary_cache() {
+        let corpus = write_corpus("cached.txt", "a\nb\n");
+        let first = LanguageModel::from_corpus_cached(corpus.clone()).unwrap();
+        // Another spelling of the same path
+        let file_name = std::path::Path::new(&corpus).file_name().unwrap();
+        let alias = std::env::temp_dir().join(".").join(file_name);
+        let mut second =
+            LanguageModel::from_corpus_cached(alias.to_string_lossy().to_string()).unwrap();
+        let shared = |lm: &LanguageModel| Arc::clone(lm.cost_dict.as_ref().unwrap());
+        assert!(Arc::ptr_eq(&shared(&first), &shared(&second)));
+        assert_eq!(second.split(String::from("ab")), "a b");
+
+        // Changing a shared dictionary copies it
+        let extra = write_corpus("cached_extra.txt", "ab\n");
+        second.add_corpus_weighted(extra, 1.0).unwrap();
+        assert!(!Arc::ptr_eq(&shared(&first), &shared(&second)));
+        assert!(!first.cost_dict.as_ref().unwrap().0.contains_key("ab"));
+
+        clear_dictionary_cache();
+        let third = LanguageModel::from_corpus_cached(corpus).unwrap();
+        assert!(!Arc::ptr_eq(&shared(&first), &shared(&third)));
+        assert_eq!(shared(&first), shared(&third));
+        assert!(LanguageModel::from_corpus_cached(String::from("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let lm = LanguageModel::from_corpus(String::new()).unwrap();
+        let perfect = lm.evaluate(&[
+            ("rustisgreat", "rust is great"),
+            ("bankofjordan", "bank of jordan"),
+        ]);
+        assert_eq!(perfect.precision, 1.0);
+        assert_eq!(perfect.recall, 1.0);
+        assert_eq!(perfect.f1, 1.0);
+        assert_eq!(perfect.exact_match, 1.0);
+
+        // The second reference has no boundary after "rust", the split adds one
+        let report = lm.evaluate(&[
+            ("rustisgreat", "rust is great"),
+            ("rustisgreat", "rustis great"),
+        ]);
+        assert_eq!(report.precision, 0.75);
+        assert_eq!(report.recall, 1.0);
+        assert!(report.f1 < 1.0);
+        assert_eq!(report.exact_match, 0.5);
+        assert_eq!(lm.evaluate(&[]).exact_match, 1.0);
+    }
+
+    #[test]
+    fn test_split_strict() {
+        let corpus = write_corpus("strict.txt", "a\nb\nab\nbc\n");
+        let mut lm = LanguageModel::new(corpus);
+        let words = |words: &[&str]| Some(words.iter().map(|w| w.to_string()).collect::<Vec<_>>());
+        assert_eq!(lm.split_strict(String::from("abc")), words(&["a", "bc"]));
+        assert_eq!(
+            lm.split_strict(String::from("AB ba")),
+            words(&["A", "B", "b", "a"])
+        );
+        assert_eq!(lm.split_strict(String::new()), words(&[]));
+        assert_eq!(
+            lm.split_strict(String::from("abca")),
+            words(&["a", "bc", "a"])
+        );
+        assert_eq!(lm.split_strict(String::from("ac")), None);
+        assert_eq!(lm.split_strict(String::from("ab c")), None);
+
+        // The parts of product codes: a number is not a word unless it is one of them
+        let parts = write_corpus("strict_parts.txt", "ab\nx200\nblue\n");
+        let mut lm = LanguageModel::new(parts);
+        assert_eq!(
+            lm.split_strict(String::from("ABX200BLUE")),
+            words(&["AB", "X200", "BLUE"])
+        );
+        assert_eq!(lm.split_strict(String::from("ABX201BLUE")), None);
+        assert_eq!(lm.split_strict(String::from("ABX200RED")), None);
+    }
+
+    #[test]
+    fn test_split_char_spans() {
+        let mut lm = LanguageModel::new(String::new());
+        let text = "rustisgreat  bankofjordan";
+        let spans = lm.split_spans(String::from(text));
+        assert_eq!(
+            spans,
+            [(0, 4), (4, 6), (6, 11), (13, 17), (17, 19), (19, 25)]
+        );
+        assert_eq!(lm.split_char_spans(String::from(text)), spans);
+        // The dynamic program does not split non-ASCII text yet, so the conversion of the
+        // byte offsets is checked on its own
+        let text = "café noir été";
+        assert_eq!(
+            language_model::char_spans(text, &[(0, 5), (6, 10), (11, 16)]),
+            [(0, 4), (5, 9), (10, 13)]
+        );
+    }
+
+    #[test]
+    fn test_max_input_len() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(
+            lm.try_split(String::from("rustisgreat")).unwrap(),
+            "rust is great"
+        );
+        lm.max_input_len = Some(11);
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        assert_eq!(lm.split(String::from("rustisgreat!")), "rustisgreat!");
+        let err = lm.try_split(String::from("rustisgreat!")).unwrap_err();
+        assert!(matches!(
+            err,
+            SplitError::InputTooLong {
+                length: 12,
+                limit: 11
+            }
+        ));
+        assert_eq!(err.to_string(), "the text has 12 bytes, more than 11");
+
+        // Every split path keeps a longer text as it is
+        lm.max_input_len = Some(3);
+        let text = "rustisgreat";
+        assert_eq!(lm.split_bigram(String::from(text)), text);
+        assert_eq!(lm.split_strict(String::from(text)), None);
+        assert_eq!(lm.split_bounded(String::from(text), 4), text);
+        assert_eq!(lm.split_hashtag(text, false), text);
+        assert_eq!(lm.split_identifier(text), text);
+        assert_eq!(lm.split_lower(String::from(text)), text);
+        for algorithm in [Algorithm::OptimalDp, Algorithm::Greedy, Algorithm::Bigram] {
+            assert_eq!(lm.split_with_algorithm(String::from(text), algorithm), text);
+        }
+        assert_eq!(
+            lm.split_sentences(String::from(text), &SentenceRules::default()),
+            [[text]]
+        );
+        assert_eq!(lm.split_unicode_words(text), [text]);
+        assert_eq!(
+            lm.split_retaining_case_map(text),
+            (vec![text.to_string()], vec![text])
+        );
+        assert!(lm.explain(String::from(text)).is_empty());
+    }
+
+    #[test]
+    fn test_collapse_whitespace() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.split(String::from("rust  isgreat")), "rust  is great");
+        lm.collapse_whitespace = true;
+        assert_eq!(lm.split(String::from("rust  isgreat")), "rust is great");
+        assert_eq!(lm.split(String::from("foo  barbaz")), "foo bar baz");
+        assert_eq!(
+            lm.split(String::from(" rust\t\n isgreat ")),
+            " rust is great "
+        );
+        assert_eq!(
+            lm.split_greedy(String::from("rust \t isgreat")),
+            "rust is great"
+        );
+    }
+
+    #[test]
+    fn test_write_corpus() {
+        let corpus = write_corpus("dump.txt", "the\nLondon\ncat\nsat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        let dumped =
+            std::env::temp_dir().join(format!("rsplitter_{}_dumped.txt", std::process::id()));
+        lm.write_corpus(std::fs::File::create(&dumped).unwrap())
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&dumped).unwrap(),
+            "the\nLondon\ncat\nsat\n"
+        );
+        let mut reloaded =
+            LanguageModel::from_corpus(dumped.to_string_lossy().to_string()).unwrap();
+        assert_eq!(reloaded.cost_dict, lm.cost_dict);
+        reloaded.case_mode = CaseMode::Corpus;
+        assert_eq!(reloaded.split(String::from("thecatsat")), "the cat sat");
+        assert_eq!(reloaded.split(String::from("london")), "London");
+
+        // Words added at runtime keep their rank
+        let extra = write_corpus("dump_extra.txt", "mat\n");
+        lm.add_corpus_weighted(extra, 100.0).unwrap();
+        let mut buffer = Vec::new();
+        lm.write_corpus(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "mat\nthe\nLondon\ncat\nsat\n"
+        );
+        // A model not loaded yet is loaded first, or says why it cannot be
+        let mut buffer = Vec::new();
+        LanguageModel::new(String::new())
+            .write_corpus(&mut buffer)
+            .unwrap();
+        assert!(buffer.starts_with(b"the\n"));
+        assert!(matches!(
+            LanguageModel::new(String::from("/nonexistent/words.txt")).write_corpus(Vec::new()),
+            Err(SplitError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_ref() {
+        let lm = LanguageModel::from_corpus(String::new()).unwrap();
+        let text = "Thequickbrownfox jumpsoverthelazydog";
+        let words = lm.split_ref(text);
+        assert_eq!(words.join(" "), split(String::from(text)));
+        assert!(words
+            .iter()
+            .all(|word| text.as_bytes().as_ptr_range().contains(&word.as_ptr())));
+        assert!(lm.split_ref("").is_empty());
+        // The words are the ones of `split`, whatever the settings of the split
+        let mut lm = LanguageModel::new(write_corpus("ref_phrases", "i\nlove\nnew york\n"));
+        lm.try_load().unwrap();
+        assert_eq!(lm.split_ref("ilovenewyork"), ["i", "love", "new", "york"]);
+        assert_eq!(
+            lm.split_ref("ilovenewyork").join(" "),
+            lm.split("ilovenewyork")
+        );
+        let mut lm = LanguageModel::new(String::new());
+        lm.try_load().unwrap();
+        lm.keep_punctuation = true;
+        lm.case_mode = CaseMode::TitleCase;
+        for text in ["bank-of-jordan2023! ÜnïcödeIsMyBank", "rust,isgreat😀"] {
+            let words: Vec<String> = lm
+                .split_ref(text)
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect();
+            let expected: Vec<String> = lm
+                .split_words(text)
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect();
+            assert_eq!(words, expected);
+        }
+        assert_eq!(
+            lm.split_spans("bankofjordan2023!"),
+            [(0, 4), (4, 6), (6, 17)]
+        );
+        let corpus = write_corpus("ref_normalize", "schon\ntag\nschoen\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        lm.normalize = Some(Box::new(strip_accents));
+        assert_eq!(lm.split_ref("SchöNtag"), ["SchöN", "tag"]);
+        // Words in another length are found in the run
+        lm.normalize = Some(Box::new(|text: &str| text.replace('ö', "oe")));
+        assert_eq!(lm.split_words("Schöntag"), ["Schoen", "tag"]);
+        assert_eq!(lm.split_ref("Schöntag"), ["Schön", "tag"]);
+        lm.max_input_len = Some(4);
+        assert_eq!(lm.split_ref("Schöntag"), ["Schöntag"]);
+        assert_eq!(lm.split_iter("Schöntag").collect::<Vec<_>>(), ["Schöntag"]);
+    }
+
+    #[test]
+    fn test_pathological_cost_array() {
+        use crate::language_model::{Lattice, Run};
+        let mut lm = LanguageModel::new(String::new());
+        lm.ensure_loaded();
+        let text = "rustisgreat";
+        let run = Run::new(text, &[], &[]);
+        // The forward pass goes on from the costs of a prefix, whatever they are
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1.0, f32::MAX] {
+            let lattice = Lattice {
+                cost: vec![value; 5],
+                back: vec![0, 1, 1, 1, 1],
+            };
+            let lattice = lm.extend_cost_array(&run, lattice);
+            let words = lm.minimal_cost(&run, &lattice);
+            assert_eq!(words.concat().len(), text.len());
+        }
+        let lattice = Lattice {
+            cost: vec![0.0, f32::NAN, 0.0, f32::NAN],
+            back: vec![0, 1, 2, 1],
+        };
+        let lattice = lm.extend_cost_array(&run, lattice);
+        assert_eq!(lm.minimal_cost(&run, &lattice).concat().len(), text.len());
+    }
+
+    #[test]
+    fn test_separator() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.separator = String::from("|");
+        assert_eq!(
+            lm.split(String::from("rustisgreat  now")),
+            "rust|is|great  now"
+        );
+        assert_eq!(
+            lm.split_greedy(String::from("rustis\tgreat")),
+            "rust|is\tgreat"
+        );
+        lm.collapse_whitespace = true;
+        assert_eq!(
+            lm.split(String::from("rustisgreat \n now")),
+            "rust|is|great now"
+        );
+    }
+
+    #[test]
+    fn test_prefix_costs() {
+        let mut lm = LanguageModel::new(String::new());
+        let mut prefix = lm.prefix_costs("examplecom");
+        assert_eq!(prefix.lattice.cost.len(), "examplecom".len() + 1);
+        for text in [
+            "examplecomfoo",
+            "examplecombar",
+            "examplecom",
+            "otherfoo",
+            "examplecom foo",
+        ] {
+            assert_eq!(
+                lm.split_with_prefix(&prefix, String::from(text)),
+                lm.split(String::from(text))
+            );
+        }
+        // The dynamic program over the prefix is used as it is, not computed again
+        prefix.lattice.back.iter_mut().skip(1).for_each(|k| *k = 1);
+        assert_ne!(
+            lm.split_with_prefix(&prefix, String::from("examplecomfoo")),
+            "example com foo"
+        );
+        // A number running past the prefix needs the costs of the whole text
+        let prefix = lm.prefix_costs("pay$");
+        assert_eq!(
+            lm.split_with_prefix(&prefix, String::from("pay$99now")),
+            "pay $99 now"
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_dictionary() {
+        let corpus = write_corpus("case_insensitive.txt", "The\nNASA\nlaunch\nrocket\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        // Mixed-case input hits the lowercased keys and keeps its own casing
+        assert_eq!(lm.split(String::from("thenasalaunch")), "the nasa launch");
+        assert_eq!(lm.split(String::from("THENASALAUNCH")), "THE NASA LAUNCH");
+        assert_eq!(lm.split(String::from("TheNasaRocket")), "The Nasa Rocket");
+        assert_eq!(
+            lm.split_strict(String::from("TheNasaRocket")),
+            Some(vec![
+                String::from("The"),
+                String::from("Nasa"),
+                String::from("Rocket")
+            ])
+        );
+        assert!(lm.is_valid_segmentation(&["THE", "nasa", "Launch"]));
+        // The corpus casing replaces the input one only when asked
+        lm.case_mode = CaseMode::Corpus;
+        assert_eq!(lm.split(String::from("thenasalaunch")), "The NASA launch");
+        assert_eq!(lm.split(String::from("TheNasaRocket")), "The NASA Rocket");
+    }
+
+    #[test]
+    fn test_deletion_cost() {
+        let mut lm = LanguageModel::new(String::new());
+        assert!(lm.split(String::from("hello#world")).contains('#'));
+        lm.deletion_cost = Some(1.0);
+        assert_eq!(lm.split(String::from("hello#world")), "hello world");
+        assert_eq!(
+            lm.split_spans(String::from("hello#world")),
+            vec![(0, 5), (6, 11)]
+        );
+        assert_eq!(lm.segment_count(String::from("hello#world")), 2);
+        // Numbers are kept whatever the deletion cost
+        assert_eq!(lm.split(String::from("pay$5#now")), "pay $5 now");
+    }
+
+    #[test]
+    fn test_split_bounded() {
+        let mut lm = LanguageModel::new(String::new());
+        let bounded = lm.split_bounded(String::from("rustisgreat"), 2);
+        assert!(bounded.split(' ').all(|word| word.len() <= 2));
+        assert_eq!(bounded.replace(' ', ""), "rustisgreat");
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        // A bound below 1 still moves through the text
+        assert_eq!(lm.split_bounded(String::from("abc"), 0), "a b c");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let corpus = write_corpus("normalize.txt", "schoen\nschon\ntag\ngood\ncool\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert!(lm.normalize.is_none());
+        assert_eq!(lm.split(String::from("schoentag")), "schoen tag");
+        lm.normalize = Some(Box::new(|text: &str| text.replace('ö', "oe")));
+        assert_eq!(lm.split(String::from("schöntag")), "schoen tag");
+        // One character for one, the input is written back as it is
+        lm.normalize = Some(Box::new(strip_accents));
+        assert_eq!(lm.split(String::from("Schöntag")), "Schön tag");
+        lm.normalize = Some(Box::new(collapse_elongations));
+        assert_eq!(lm.split(String::from("gooooodcoool")), "good cool");
+    }
+
+    #[test]
+    fn test_capacity_hint() {
+        let corpus = write_corpus("capacity_hint.txt", "the 50\ncat 20\nsat 10\n");
+        let mut sized = LanguageModel::new(corpus.clone());
+        sized.cost_model = CostModel::Frequency;
+        sized.capacity_hint = Some(1000);
+        sized.try_load().unwrap();
+        assert!(sized.cost_dict.as_ref().unwrap().0.capacity() >= 1000);
+        let mut plain = LanguageModel::new(corpus);
+        plain.cost_model = CostModel::Frequency;
+        plain.try_load().unwrap();
+        assert_eq!(
+            sized.cost_dict.as_ref().unwrap().0,
+            plain.cost_dict.as_ref().unwrap().0
+        );
+    }
+
+    #[test]
+    fn test_split_with_coverage() {
+        let corpus = write_corpus("coverage.txt", "the\ncat\nsat\non\nmat\npay\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert_eq!(
+            lm.split_with_coverage(String::from("thecatsat onthemat")),
+            (String::from("the cat sat on the mat"), true)
+        );
+        // A number is not in the dictionary but is recognized
+        assert!(lm.split_with_coverage(String::from("pay $1,000")).1);
+        assert!(
+            !lm.split_with_coverage(String::from("the cat sat on the blorp"))
+                .1
+        );
+    }
+
+    #[test]
+    fn test_oov_char_count() {
+        let corpus = write_corpus("oov_chars.txt", "the\ncat\nsat\non\nmat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        assert_eq!(lm.oov_char_count(String::from("the cat sat on the mat")), 0);
+        assert_eq!(
+            lm.oov_char_count(String::from("the cat sat on the blorp")),
+            5
+        );
+        assert_eq!(lm.oov_char_count(String::from("blorp thecat blorp")), 10);
+    }
+
+    #[test]
+    fn test_split_retaining_case_map() {
+        let corpus = write_corpus("case_map.txt", "schon\ntag\ngut\nschoen\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        lm.normalize = Some(Box::new(strip_accents));
+        let text = " SchöNTAG  gÜt";
+        let (words, slices) = lm.split_retaining_case_map(text);
+        assert_eq!(words, ["SchöN", "TAG", "gÜt"]);
+        assert_eq!(slices, [" SchöN", "TAG  ", "gÜt"]);
+        assert_eq!(slices.concat(), text);
+        // The words of a run in another length keep the run in one slice
+        lm.normalize = Some(Box::new(|text: &str| text.replace('ö', "oe")));
+        let (words, slices) = lm.split_retaining_case_map("Schöntag");
+        assert_eq!(words, ["Schoen", "tag"]);
+        assert_eq!(slices, ["Schöntag", ""]);
+    }
+
+    #[test]
+    fn test_fuzzy_cost() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(
+            lm.split(String::from("thequickbronw fox")),
+            "the quick bron w fox"
+        );
+        lm.fuzzy_cost = Some(8.0);
+        // "bronw" is "brown" with two adjacent characters swapped, and "fotball" misses one
+        assert_eq!(
+            lm.split(String::from("thequickbronw fox")),
+            "the quick bronw fox"
+        );
+        assert_eq!(
+            lm.split(String::from("ilovewatchingfotball")),
+            "i love watching fotball"
+        );
+        assert_eq!(
+            lm.split(String::from("thequickbrownfox")),
+            "the quick brown fox"
+        );
+
+        let mut lm = LanguageModel::builder().fuzzy_cost(8.0).build().unwrap();
+        assert_eq!(lm.split("bankofjordn"), "bank of jordn");
+        assert_eq!(lm.split("rustisgreat"), "rust is great");
+        assert!(matches!(
+            LanguageModel::builder().fuzzy_cost(-1.0).build(),
+            Err(SplitError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.split(String::from("rustisgreat"));
+        assert_eq!(lm.cache_stats(), CacheStats::default());
+        lm.cache_capacity = Some(2);
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        let stats = lm.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (1, 1, 1));
+        lm.split(String::from("thecatsat"));
+        let stats = lm.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (1, 2, 2));
+        // A full cache drops the text used least recently
+        lm.split(String::from("helloworld"));
+        let stats = lm.cache_stats();
+        assert_eq!((stats.size, stats.evictions), (2, 1));
+        lm.split(String::from("thecatsat"));
+        assert_eq!(lm.cache_stats().hits, 2);
+        lm.split(String::from("rustisgreat"));
+        let stats = lm.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 4, 2));
+        assert_eq!(stats.hit_ratio(), 2.0 / 6.0);
+        lm.split(String::from("thecatsat"));
+        assert_eq!(lm.cache_stats().hits, 3);
+        lm.cache_capacity = Some(1);
+        lm.split(String::from("bankofjordan"));
+        assert_eq!(lm.cache_stats().size, 1);
+        assert_eq!(lm.split(String::from("bankofjordan")), "bank of jordan");
+        assert_eq!(lm.cache_stats().hits, 4);
+        lm.clear_cache();
+        assert_eq!(lm.cache_stats(), CacheStats::default());
+
+        // A setting changed after a hit is not hidden by the cache
+        let mut lm = LanguageModel::new(String::new());
+        lm.cache_capacity = Some(10);
+        let text = "thequickbrownfox";
+        lm.split(text);
+        assert_eq!(lm.split(text), "the quick brown fox");
+        lm.case_mode = CaseMode::TitleCase;
+        assert_eq!(lm.split(text), "The Quick Brown Fox");
+        lm.case_mode = CaseMode::Original;
+        lm.separator = String::from("_");
+        assert_eq!(lm.split(text), "the_quick_brown_fox");
+        assert_eq!(lm.cache_stats().hits, 1);
+        lm.separator = String::from(" ");
+        assert_eq!(lm.split("itisatthe"), "it is at the");
+        lm.set_length_penalty(20.0).unwrap();
+        assert_eq!(lm.split("itisatthe"), "itis atthe");
+    }
+
+    #[test]
+    fn test_corpus_phrases() {
+        let corpus = write_corpus("phrases.txt", "i\nlove\nnew  york\nnew\nNew York Times\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        let dict = &lm.cost_dict.as_ref().unwrap();
+        assert!(dict.0.contains_key("newyork"));
+        // A phrase is as long as its letters
+        assert_eq!(dict.1, 12);
+        assert_eq!(lm.split(String::from("ilovenewyork")), "i love new york");
+        assert_eq!(lm.split(String::from("ILoveNewYork")), "I Love New York");
+        lm.case_mode = CaseMode::Corpus;
+        assert_eq!(
+            lm.split(String::from("ilovenewyorktimes")),
+            "i love New York Times"
+        );
+        let corpus = write_corpus("frequency_phrases.txt", "i 50\nlove 40\nnew york 30\n");
+        let mut lm = LanguageModel::new(corpus);
+        lm.cost_model = CostModel::Frequency;
+        assert_eq!(lm.split(String::from("ilovenewyork")), "i love new york");
+    }
+
+    #[test]
+    fn test_min_frequency() {
+        let corpus = write_corpus(
+            "min_frequency.txt",
+            "the 1000\ncat 800\nsat 700\nat 600\nxqzcatsa 1\n",
+        );
+        let mut lm = LanguageModel::new(corpus.clone());
+        lm.cost_model = CostModel::Frequency;
+        // The rare word matches the junk
+        assert_ne!(lm.split(String::from("thexqzcatsat")), "the xqz cat sat");
+        let mut filtered = LanguageModel::new(corpus);
+        filtered.cost_model = CostModel::Frequency;
+        filtered.min_frequency = Some(2.0);
+        assert_eq!(
+            filtered.split(String::from("thexqzcatsat")),
+            "the xqz cat sat"
+        );
+        assert_eq!(filtered.cost_dict.as_ref().unwrap().1, 3);
+
+        let ranked = write_corpus("max_rank.txt", "the\ncat\nsat\nat\nxqzcatsa\n");
+        let mut lm = LanguageModel::new(ranked);
+        lm.max_rank = Some(4);
+        lm.try_load().unwrap();
+        let dict = lm.cost_dict.as_ref().unwrap();
+        assert_eq!((dict.0.len(), dict.1), (4, 3));
+    }
+
+    #[test]
+    fn test_split_lower() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.case_mode = CaseMode::SentenceCase;
+        assert_eq!(lm.split_lower(String::from("RustIsGreat")), "rust is great");
+        assert_eq!(
+            lm.split_lower(String::from("RUST IsGreat")),
+            "rust is great"
+        );
+        let corpus = write_corpus("split_lower.txt", "ete\nchaud\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        lm.normalize = Some(Box::new(strip_accents));
+        assert_eq!(lm.split_lower(String::from("ÉTÉChaud")), "été chaud");
+    }
+
+    #[test]
+    fn test_load_and_split_many() {
+        let texts: Vec<String> = ["rustisgreat", "thecatsat on themat", "", "it'sa 2024thing"]
+            .iter()
+            .map(|text| text.to_string())
+            .collect();
+        let mut serial = LanguageModel::from_corpus(String::new()).unwrap();
+        let expected: Vec<String> = texts
+            .iter()
+            .map(|text| serial.split(text.clone()))
+            .collect();
+        let mut parallel = LanguageModel::new(String::new());
+        assert_eq!(parallel.load_and_split_many(&texts).unwrap(), expected);
+        assert_eq!(parallel.cost_dict, serial.cost_dict);
+        assert_eq!(serial.split_many(&texts), expected);
+        // Whatever the number of cores of the machine running the tests
+        assert_eq!(serial.split_chunks(&texts, 3), expected);
+        let lines = serial.lines_from_file().unwrap();
+        assert_eq!(
+            Some(&serial.parallel_cost_dict(&lines, 3)),
+            serial.cost_dict.as_deref()
+        );
+
+        let corpus = write_corpus("parallel_frequency.txt", "the 50\nThe 20\ncat 20\nsat 10\n");
+        let mut serial = LanguageModel::new(corpus.clone());
+        serial.cost_model = CostModel::Frequency;
+        serial.try_load().unwrap();
+        let mut parallel = LanguageModel::new(corpus);
+        parallel.cost_model = CostModel::Frequency;
+        parallel.load_and_split_many(&texts).unwrap();
+        assert_eq!(parallel.cost_dict, serial.cost_dict);
+        let lines = serial.lines_from_file().unwrap();
+        assert_eq!(
+            Some(&serial.parallel_cost_dict(&lines, 3)),
+            serial.cost_dict.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_keep_cheaper_input() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.keep_cheaper_input = true;
+        assert_eq!(lm.split(String::from("hello world")), "hello world");
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        assert!(
+            lm.tokenization_cost(&["rust", "is", "great"]) < lm.tokenization_cost(&["rustisgreat"])
+        );
+        assert_eq!(lm.tokenization_cost(&["2024"]), lm.length_penalty);
+    }
+
+    #[test]
+    fn test_split_tokens() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.case_mode = CaseMode::SentenceCase;
+        for text in [
+            "rustisgreat",
+            " thequickbrownfox\tjumps ",
+            "2024 rustisgreat",
+            "",
+        ] {
+            let tokens = lm.split_tokens(String::from(text));
+            let joined: String = tokens.iter().map(Token::as_str).collect();
+            assert_eq!(joined, lm.split(String::from(text)));
+        }
+        lm.separator = String::from("_");
+        let tokens = lm.split_tokens(String::from("rustis great"));
+        assert_eq!(
+            tokens,
+            [
+                Token::Word(String::from("Rust")),
+                Token::Separator(String::from("_")),
+                Token::Word(String::from("is")),
+                Token::Separator(String::from(" ")),
+                Token::Word(String::from("great")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_unknown_shorter_than() {
+        let path = write_corpus("merge_unknown", "the\ncat\nsat\n");
+        let mut lm = LanguageModel::new(path);
+        assert!(lm.split(String::from("xqzwk")).contains(' '));
+        lm.merge_unknown_shorter_than = Some(4);
+        assert_eq!(lm.split(String::from("xqzwk")), "xqzwk");
+        assert_eq!(lm.split(String::from("thecat xqzwk")), "the cat xqzwk");
+        lm.merge_unknown_shorter_than = Some(1);
+        assert!(lm.split(String::from("xqzwk")).contains(' '));
+    }
+
+    #[test]
+    fn test_split_is_deterministic() {
+        let texts = [
+            "thecatsat onthemat",
+            "thequickbrownfox",
+            "visittheUKsoon for $1,000",
+            "xqzwk helloworld",
+        ];
+        let mut lm = LanguageModel::new(String::new());
+        let expected: Vec<String> = texts
+            .iter()
+            .map(|text| lm.split(String::from(*text)))
+            .collect();
+        for _ in 0..20 {
+            for (text, split) in texts.iter().zip(&expected) {
+                assert_eq!(&lm.split(String::from(*text)), split);
+            }
+        }
+        // Other seeds, so other iteration orders of the dictionary
+        for _ in 0..3 {
+            let mut lm = LanguageModel::with_hasher(
+                String::new(),
+                std::collections::hash_map::RandomState::new(),
+            );
+            for (text, split) in texts.iter().zip(&expected) {
+                assert_eq!(&lm.split(String::from(*text)), split);
+            }
+            lm.beam_width = Some(3);
+            let beam = lm.split(String::from(texts[0]));
+            assert_eq!(lm.split(String::from(texts[0])), beam);
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        const CORPUS: &[u8] = b"\xef\xbb\xbfthe\r\ncat\n# a comment\nsat\n";
+        let mut lm = LanguageModel::from_bytes(CORPUS).unwrap();
+        assert_eq!(lm.split(String::from("thecatsat")), "the cat sat");
+        let cost_dict = get_cost_dict_from_bytes(CORPUS).unwrap();
+        assert_eq!(cost_dict.0.len(), 3);
+        assert_eq!(cost_dict.1, 3);
+        match LanguageModel::from_bytes(b"the\n\xff\n") {
+            Err(SplitError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidData),
+            _ => panic!("invalid UTF-8 is an error"),
+        }
+        assert!(matches!(
+            LanguageModel::from_bytes(b"\n"),
+            Err(SplitError::EmptyCorpus)
+        ));
+    }
+
+    #[test]
+    fn test_position_weight() {
+        let path = write_corpus("position_weight", "c\nab\nbc\na\n");
+        let mut lm = LanguageModel::new(path);
+        assert_eq!(lm.split(String::from("abc")), "ab c");
+        lm.position_weight = Some(Box::new(|_| 1.0));
+        assert_eq!(lm.split(String::from("abc")), "ab c");
+        // "c" starting later than "bc", it costs the most
+        lm.position_weight = Some(Box::new(|position| (2.0 * position as f32).exp()));
+        assert_eq!(lm.split(String::from("abc")), "a bc");
+    }
+
+    #[test]
+    fn test_split_with_stats() {
+        let mut lm = LanguageModel::new(String::new());
+        for text in ["rustisgreat", "thequickbrownfox jumps", ""] {
+            let (split, stats) = lm.split_with_stats(String::from(text));
+            assert_eq!(split, lm.split(String::from(text)));
+            assert_eq!(stats.positions, text.replace(' ', "").len());
+            assert!(stats.hits <= stats.lookups);
+            assert!(stats.lookups >= stats.positions);
+        }
+        let (_, stats) = lm.split_with_stats(String::from("rustisgreat"));
+        assert!(stats.hits >= 3);
+        assert!(stats.hit_ratio() > 0.0 && stats.hit_ratio() <= 1.0);
+        assert_eq!(SplitStats::default().hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut bounded = LanguageModel::builder().max_word_len(3).build().unwrap();
+        let mut spaced = LanguageModel::builder()
+            .separator("-")
+            .case_mode(CaseMode::SentenceCase)
+            .build()
+            .unwrap();
+        let words = bounded.split_words(String::from("rustisgreat"));
+        assert!(words.iter().all(|word| word.len() <= 3));
+        assert_eq!(words.concat(), "rustisgreat");
+        assert_eq!(spaced.split(String::from("rustisgreat")), "Rust-is-great");
+        assert_eq!(
+            spaced.split_words(String::from("rustis great")),
+            ["Rust", "is", "great"]
+        );
+        assert!(matches!(
+            LanguageModel::builder()
+                .corpus_path("does/not/exist.txt")
+                .build(),
+            Err(SplitError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors_instead_of_panics() {
+        let mut missing = LanguageModel::new(String::from("does/not/exist.txt"));
+        assert!(matches!(
+            missing.try_split(String::from("rustisgreat")),
+            Err(SplitError::Io(_))
+        ));
+        // The lazy loading splits with an empty dictionary and keeps the error for `try_load`
+        assert_eq!(missing.split("rustis"), "r u s t i s");
+        assert_eq!((missing.vocab_size(), missing.cost("rust")), (0, None));
+        assert_eq!(missing.remove_word("rust"), None);
+        assert!(matches!(
+            missing.insert_word("rust", 1.0),
+            Err(SplitError::Io(_))
+        ));
+        assert!(matches!(
+            missing.add_words(&["rust"], 1.0),
+            Err(SplitError::Io(_))
+        ));
+        assert!(matches!(missing.try_load(), Err(SplitError::Io(_))));
+        // and reads the corpus again once it exists
+        missing.corpus_path = write_corpus("errors_late", "rust\nis\n");
+        assert_eq!(missing.split("rustis"), "rust is");
+        let empty = write_corpus("errors_empty", "\n# only a comment\n");
+        let mut empty = LanguageModel::new(empty);
+        assert!(matches!(
+            empty.try_split(String::from("rustisgreat")),
+            Err(SplitError::EmptyCorpus)
+        ));
+        let mut lm = LanguageModel::new(String::new());
+        assert!(matches!(
+            lm.set_length_penalty(f32::NAN),
+            Err(SplitError::InvalidInput(_))
+        ));
+        assert_eq!(lm.length_penalty(), 0.0);
+        assert!(lm.set_length_penalty(2.0).is_ok());
+        assert!(matches!(
+            LanguageModel::builder().length_penalty(f32::NAN).build(),
+            Err(SplitError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_multibyte_runs() {
+        let path = write_corpus("multibyte", "café\nnoir\nпривет\nмир\ncrème\nbrûlée\n");
+        let mut lm = LanguageModel::new(path.clone());
+        assert_eq!(lm.split(String::from("cafénoir")), "café noir");
+        assert_eq!(lm.split(String::from("приветмир")), "привет мир");
+        assert_eq!(lm.split(String::from("CRÈMEbrûlée")), "CRÈME brûlée");
+        assert_eq!(lm.split_spans(String::from("cafénoir")), [(0, 5), (5, 9)]);
+        assert_eq!(
+            lm.split_char_spans(String::from("le приветмир")),
+            [(0, 2), (3, 9), (9, 12)]
+        );
+        assert_eq!(lm.explain(String::from("cafénoir")).len(), 8);
+        lm.beam_width = Some(4);
+        assert_eq!(lm.split(String::from("crèmebrûlée")), "crème brûlée");
+        let mut trie = LanguageModel::new(path);
+        trie.backend = DictionaryBackend::Trie;
+        assert_eq!(trie.split(String::from("brûléecafé")), "brûlée café");
+        // A letter missing from the corpus is an unknown word, the words after it still split
+        assert_eq!(split(String::from("cafénoir")), "caf é noir");
+        assert_eq!(split(String::from("rustéisgreat")), "rust é is great");
+        assert_eq!(split(String::from("überfast")), "ü ber fast");
+        let corpus = write_corpus("unknown_letters.txt", "the\ncat\nsat\n");
+        let mut lm = LanguageModel::new(corpus);
+        assert_eq!(lm.split(String::from("thexqzcatsat")), "the xqz cat sat");
+    }
+
+    #[test]
+    fn test_free_split_words_and_spans() {
+        let text = "thequickbrownfox  jumps";
+        let words = split_words(text);
+        assert_eq!(words, ["the", "quick", "brown", "fox", "jumps"]);
+        assert_eq!(
+            words.join(" "),
+            split(String::from("thequickbrownfox jumps"))
+        );
+        let spans = split_spans(text);
+        let sliced: Vec<&str> = spans
+            .iter()
+            .map(|(start, end)| &text[*start..*end])
+            .collect();
+        assert_eq!(sliced, words);
+        assert_eq!(split_char_spans(text), spans);
+        assert_eq!(split_char_spans("éte fox"), [(0, 1), (1, 3), (4, 7)]);
+        assert_eq!(split_spans("éte fox"), [(0, 2), (2, 4), (5, 8)]);
+    }
+
+    #[test]
+    fn test_keep_punctuation() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_ne!(
+            lm.split(String::from("bank-of-jordan2023!")),
+            "bank-of-jordan2023!"
+        );
+        lm.keep_punctuation = true;
+        assert_eq!(
+            lm.split(String::from("bank-of-jordan2023!")),
+            "bank-of-jordan2023!"
+        );
+        assert_eq!(
+            lm.split(String::from("bankofjordan2023!")),
+            "bank of jordan2023!"
+        );
+        assert_eq!(lm.split(String::from("BankOfJordan")), "Bank Of Jordan");
+        assert_eq!(lm.split(String::from("(rustisgreat)")), "(rust is great)");
+        assert_eq!(
+            lm.split(String::from("rust.isgreat 42")),
+            "rust.is great 42"
+        );
+        assert_eq!(lm.split(String::from("...")), "...");
+    }
+
+    #[test]
+    fn test_bigram_weight() {
+        let corpus = write_corpus(
+            "bigram_weight_words.txt",
+            "sex\nchange\nexpert\nexperts\nexchange\n",
+        );
+        let pairs = write_corpus(
+            "bigram_weight_pairs.txt",
+            "experts exchange 20\nsex change 1\n",
+        );
+        let mut lm = LanguageModel::new(corpus);
+        assert_eq!(
+            lm.split_bigram(String::from("expertsexchange")),
+            "expert sex change"
+        );
+        lm.load_bigrams(pairs).unwrap();
+        assert_eq!(
+            lm.split_bigram(String::from("expertsexchange")),
+            "experts exchange"
+        );
+        lm.bigram_weight = Some(0.9);
+        assert_eq!(
+            lm.split_bigram(String::from("expertsexchange")),
+            "experts exchange"
+        );
+        // The word costs alone
+        lm.bigram_weight = Some(0.0);
+        assert_eq!(
+            lm.split_bigram(String::from("expertsexchange")),
+            "expert sex change"
+        );
+    }
+
+    #[test]
+    fn test_train_from_text() {
+        let documents = vec![
+            String::from("Acute renal failure. Renal failure, acute."),
+            String::from("No renal failure; acute pain."),
+            String::from("A rare word."),
+        ];
+        let mut lm = LanguageModel::train_from_text(&documents, 1, None).unwrap();
+        assert_eq!(lm.cost_model, CostModel::Frequency);
+        assert_eq!(
+            lm.split(String::from("acuterenalfailure")),
+            "acute renal failure"
+        );
+        let costs = &lm.cost_dict.as_ref().unwrap().0;
+        assert!(costs["renal"] < costs["rare"]);
+        let frequent = LanguageModel::train_from_text(&documents, 2, None).unwrap();
+        let costs = &frequent.cost_dict.as_ref().unwrap().0;
+        assert_eq!(costs.len(), 3);
+        assert!(!costs.contains_key("rare"));
+        let small = LanguageModel::train_from_text(&documents, 1, Some(2)).unwrap();
+        let mut words: Vec<&String> = small.cost_dict.as_ref().unwrap().0.keys().collect();
+        words.sort();
+        // Three words are seen three times, the ties go by word
+        assert_eq!(words, ["acute", "failure"]);
+        assert!(matches!(
+            LanguageModel::train_from_text(["..."], 1, None),
+            Err(SplitError::EmptyCorpus)
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let corpus = write_corpus("save_load_corpus", "the\ncat\nsat\non\nmat\n");
+        let path = std::env::temp_dir().join(format!("rsplitter_model_{}.bin", std::process::id()));
+        let mut built = LanguageModel::new(corpus);
+        built.save(&path).unwrap();
+        let mut loaded = LanguageModel::load(&path).unwrap();
+        assert_eq!(loaded.cost_dict, built.cost_dict);
+        assert_eq!(
+            loaded.split(String::from("thecatsat onthemat")),
+            "the cat sat on the mat"
+        );
+        std::fs::write(&path, b"not a model").unwrap();
+        assert!(matches!(
+            LanguageModel::load(&path),
+            Err(SplitError::InvalidModel(_))
+        ));
+        assert!(matches!(
+            LanguageModel::load("does/not/exist.bin"),
+            Err(SplitError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_scored() {
+        let mut lm = LanguageModel::new(String::new());
+        let text = "thequickbrownfox jumpsoverthelazydog";
+        let result = lm.split_scored(String::from(text));
+        assert_eq!(result.words, lm.split_words(String::from(text)));
+        assert_eq!(result.word_costs.len(), result.words.len());
+        assert_eq!(result.total_cost, result.word_costs.iter().sum::<f32>());
+        // An unknown word costs more than any dictionary word
+        let corpus = write_corpus("scored.txt", "rust\nis\ngreat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        let result = lm.split_scored(String::from("rust xqzvk"));
+        assert_eq!(result.words, ["rust", "xqzvk"]);
+        assert!(result.word_costs[1] > result.word_costs[0]);
+        assert_eq!(result.unknown_words, 1);
+        let known = lm.split_scored(String::from("rustisgreat"));
+        assert_eq!(known.unknown_words, 0);
+        assert!(known.mean_cost() < result.mean_cost());
+        assert!(result.word_costs.iter().all(|cost| cost.is_finite()));
+        assert_eq!(lm.split_scored(String::new()), SplitResult::default());
+    }
+
+    #[test]
+    fn test_split_top_k() {
+        let corpus = write_corpus("top_k.txt", "the\ncat\nsat\ncats\nat\n");
+        let mut lm = LanguageModel::from_corpus(corpus).unwrap();
+        let splits = lm.split_top_k("thecatsat", 5);
+        let words: Vec<Vec<String>> = splits.iter().map(|(words, _)| words.clone()).collect();
+        assert_eq!(words[0], lm.split_words(String::from("thecatsat")));
+        assert!(words.contains(&vec![
+            String::from("the"),
+            String::from("cat"),
+            String::from("sat")
+        ]));
+        assert!(words.contains(&vec![
+            String::from("the"),
+            String::from("cats"),
+            String::from("at")
+        ]));
+        assert!(splits.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(words.iter().all(|split| split.concat() == "thecatsat"));
+        assert_eq!(
+            splits[0].1,
+            lm.tokenization_cost(&["the", "cat", "sat"])
+                .min(lm.tokenization_cost(&["the", "cats", "at"]))
+        );
+        // The runs between whitespace are combined
+        let splits = lm.split_top_k("thecat sat", 2);
+        assert_eq!(splits[0].0, ["the", "cat", "sat"]);
+        assert_eq!(splits.len(), 2);
+        assert!(lm.split_top_k("thecat", 0).is_empty());
+        assert_eq!(lm.split_top_k("", 3), [(Vec::new(), 0.0)]);
+    }
+
+    #[test]
+    fn test_cost_function() {
+        // The corpus costs, with a brand name that is not in the corpus
+        struct Brands(Arc<CostDict>);
+        impl WordCost for Brands {
+            fn cost(&self, word: &str) -> f32 {
+                match word {
+                    "rustacean" => 1.0,
+                    _ => self.0 .0.cost(word),
+                }
+            }
+        }
+        let mut lm = LanguageModel::from_corpus(String::new()).unwrap();
+        assert_ne!(lm.split(String::from("hirustacean")), "hi rustacean");
+        let dict = lm.cost_dict.clone().unwrap();
+        let mut lm = LanguageModel::builder()
+            .cost_function(Brands(dict))
+            .build()
+            .unwrap();
+        assert_eq!(lm.split(String::from("hirustacean")), "hi rustacean");
+        assert_eq!(lm.split(String::from("rustisgreat")), "rust is great");
+        lm.backend = DictionaryBackend::Trie;
+        lm.try_load().unwrap();
+        assert_eq!(lm.split(String::from("hirustacean")), "hi rustacean");
+    }
+
+    #[test]
+    fn test_split_batch() {
+        let texts: Vec<String> = (0..100)
+            .map(|i| format!("rustisgreat{}thequickbrownfox", i))
+            .collect();
+        let expected: Vec<String> = texts.iter().map(|text| split(text.clone())).collect();
+        assert_eq!(split_batch(&texts), expected);
+        assert!(split_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_split_stream() {
+        let mut lm = LanguageModel::new(String::new());
+        let text = format!(
+            "{} rustis great\n{}",
+            "itwasthebestoftimesitwastheworstoftimes".repeat(40),
+            "wethepeopleoftheunitedstates".repeat(40)
+        );
+        let streamed: Vec<String> = lm.split_stream(text.chars()).collect();
+        assert_eq!(streamed, lm.split_words(text.clone()));
+        // A long unknown run is cut to bound the buffer
+        let noise = "x".repeat(10_000);
+        assert_eq!(lm.split_stream(noise.chars()).collect::<String>(), noise);
+        assert_eq!(lm.split_stream("  ".chars()).count(), 0);
+    }
+
+    #[test]
+    fn test_for_language() {
+        let mut lm = LanguageModel::for_language(Language::English);
+        assert_eq!(lm.split(String::from("bankofjordan")), "bank of jordan");
+        #[cfg(feature = "lang-fr")]
+        {
+            let mut lm = LanguageModel::for_language(Language::French);
+            assert_eq!(
+                lm.split(String::from("jepensedoncjesuis")),
+                "je pense donc je suis"
+            );
+            assert_eq!(
+                lm.split(String::from("lamaisonestgrande")),
+                "la maison est grande"
+            );
+        }
+        #[cfg(feature = "lang-de")]
+        {
+            let mut lm = LanguageModel::for_language(Language::German);
+            assert_eq!(
+                lm.split(String::from("ichweißesnicht")),
+                "ich weiß es nicht"
+            );
+        }
+        #[cfg(feature = "lang-es")]
+        {
+            let mut lm = LanguageModel::builder()
+                .language(Language::Spanish)
+                .build()
+                .unwrap();
+            assert_eq!(
+                lm.split(String::from("lacasaesmuygrande")),
+                "la casa es muy grande"
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language(""), Language::English);
+        assert_eq!(detect_language("1234 !?"), Language::English);
+        assert_eq!(
+            split_auto_language("thequickbrownfox"),
+            (String::from("the quick brown fox"), Language::English)
+        );
+        #[cfg(all(feature = "lang-fr", feature = "lang-de", feature = "lang-es"))]
+        {
+            assert_eq!(detect_language("jepensedoncjesuis"), Language::French);
+            assert_eq!(detect_language("ichweißesnicht"), Language::German);
+            assert_eq!(detect_language("lacasaesmuygrande"), Language::Spanish);
+            assert_eq!(detect_language("thehouseisverybig"), Language::English);
+            assert_eq!(
+                split_auto_language("lamaisonestgrande"),
+                (String::from("la maison est grande"), Language::French)
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_hashtag() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.split_hashtag("#iloverust", false), "i love rust");
+        assert_eq!(lm.split_hashtag("#iloverust", true), "#i love rust");
+        assert_eq!(
+            lm.split_hashtag("@bank_of_jordan #throwback2019🔥", false),
+            "bank of jordan throwback 2019 🔥"
+        );
+        assert_eq!(
+            lm.split_hashtag("rustisgreat😀thanks", false),
+            "rust is great 😀 thanks"
+        );
+        // A lone sigil or a sigil inside a word is kept
+        assert_eq!(lm.split_hashtag("# and C#", false), "# and C #");
+        assert_eq!(lm.split_hashtag("", true), "");
+    }
+
+    #[test]
+    fn test_split_identifier() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(
+            lm.split_identifier("parseHTTPResponse2JSON"),
+            "parse HTTP Response 2 JSON"
+        );
+        assert_eq!(lm.split_identifier("readfileFromDB"), "read file From DB");
+        assert_eq!(lm.split_identifier("snake_case_name"), "snake case name");
+        assert_eq!(lm.split_identifier("getAValue"), "get A Value");
+        assert_eq!(
+            lm.split_identifier("let maxlength = 10;"),
+            "let max length = 10 ;"
+        );
+        assert_eq!(lm.split_identifier("__main__"), "main");
+        assert_eq!(lm.split_identifier(""), "");
+    }
+
+    #[test]
+    fn test_split_domain() {
+        let mut lm = LanguageModel::new(String::new());
+        let domain = lm.split_domain("https://user@blog.choosespain.co.uk:8080/a/b?c=d");
+        assert_eq!(domain.host, "blog.choosespain.co.uk");
+        assert_eq!(domain.subdomains, [["blog"]]);
+        assert_eq!(domain.words, ["choose", "spain"]);
+        assert_eq!(domain.suffix, "co.uk");
+        assert_eq!(
+            lm.split_domain("best-buydeals.com").name(),
+            "best buy deals"
+        );
+        assert_eq!(lm.split_domain("THEGUARDIAN.COM.").name(), "the guardian");
+        // Punycode is decoded, a label that does not decode is kept
+        assert_eq!(lm.split_domain("xn--bcher-kva.de").host, "bücher.de");
+        assert_eq!(lm.split_domain("xn--mnchen-3ya.de").host, "münchen.de");
+        assert_eq!(lm.split_domain("xn--a.com").host, "xn--a.com");
+        assert_eq!(lm.split_domain("xn--b_c.com").host, "xn--b_c.com");
+        let domain = lm.split_domain("localhost");
+        assert_eq!(
+            (domain.name(), domain.suffix),
+            (String::from("local host"), String::new())
+        );
+        assert_eq!(lm.split_domain(""), DomainSplit::default());
+        // An IP address is kept whole
+        let ip = |host: &str| DomainSplit {
+            host: String::from(host),
+            ..DomainSplit::default()
+        };
+        assert_eq!(lm.split_domain("http://[::1]:80/"), ip("[::1]"));
+        assert_eq!(
+            lm.split_domain("https://[2001:DB8::1]/a"),
+            ip("[2001:db8::1]")
+        );
+        assert_eq!(lm.split_domain("::1"), ip("::1"));
+        assert_eq!(lm.split_domain("192.168.0.1"), ip("192.168.0.1"));
+        assert_eq!(lm.split_domain("http://u@10.0.0.1:8080/x"), ip("10.0.0.1"));
+        assert_eq!(split_domain("www.rustisgreat.org").name(), "rust is great");
+    }
+
+    #[test]
+    fn test_oov_policy() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.oov_policy, OovPolicy::Split);
+        assert_eq!(
+            lm.split(String::from("xylografmachine")),
+            "xy log raf machine"
+        );
+        lm.oov_policy = OovPolicy::KeepWhole;
+        assert_eq!(
+            lm.split(String::from("xylografmachine")),
+            "xylograf machine"
+        );
+        assert_eq!(
+            lm.split(String::from("#throwbackthursday")),
+            "# throwback thursday"
+        );
+        assert_eq!(
+            lm.split(String::from("thequickbrownfox")),
+            "the quick brown fox"
+        );
+        // The cheaper the characters, the more an unknown word swallows
+        lm.oov_policy = OovPolicy::CharPenalty(0.5);
+        assert_eq!(lm.split(String::from("rustisgreat")), "rustisgreat");
+        let mut lm = LanguageModel::builder()
+            .oov_policy(OovPolicy::CharPenalty(2.0))
+            .build()
+            .unwrap();
+        assert_eq!(
+            lm.split(String::from("kubernetesdeployment")),
+            "kubernetes deployment"
+        );
+    }
+
+    #[test]
+    fn test_insert_remove_word() {
+        let mut lm = LanguageModel::new(write_corpus("insert_remove.txt", "a\nb\nxy"));
+        // Neither the cache nor the trie hide the changes
+        lm.cache_capacity = Some(8);
+        lm.backend = DictionaryBackend::Trie;
+        assert_eq!(lm.split(String::from("abxy")), "a b xy");
+        assert_eq!(lm.insert_word("abXY", 0.1).unwrap(), None);
+        assert_eq!(lm.split(String::from("abxy")), "abxy");
+        assert_eq!(lm.casings.get("abxy").map(String::as_str), Some("abXY"));
+        // The longest word is the next one once it is removed
+        assert_eq!(lm.cost_dict.as_ref().unwrap().1, 4);
+        assert_eq!(lm.remove_word("ABXY"), Some(0.1));
+        assert_eq!(lm.remove_word("abxy"), None);
+        assert!(lm.casings.is_empty());
+        assert_eq!(lm.cost_dict.as_ref().unwrap().1, 2);
+        assert_eq!(lm.split(String::from("abxy")), "a b xy");
+        assert!(lm.insert_word("b", 1.5).unwrap().is_some());
+        assert!(matches!(
+            lm.insert_word(" ", 1.0),
+            Err(SplitError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            lm.insert_word("c", f32::NAN),
+            Err(SplitError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_case_sensitive() {
+        // "US" is more frequent than "us", which "usd" outranks
+        let corpus = write_corpus("case_sensitive.txt", "US\nusd\nata\nus\ndata\nx\ny\nz");
+        let mut lm = LanguageModel::new(corpus.clone());
+        assert_eq!(lm.split(String::from("USData")), "US Data");
+        assert_eq!(lm.split(String::from("usdata")), "us data");
+        let mut lm = LanguageModel::builder()
+            .corpus_path(corpus.clone())
+            .case_sensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(lm.split(String::from("USData")), "US Data");
+        assert_eq!(lm.split(String::from("usdata")), "usd ata");
+        // Casings the corpus does not have cost as the lowercase word
+        assert_eq!(lm.split(String::from("UsData")), "UsD ata");
+        let dict = &lm.cost_dict.as_ref().unwrap().0;
+        assert!(dict["US"] < dict["usd"] && dict["usd"] < dict["us"]);
+
+        let corpus = write_corpus(
+            "case_frequency.txt",
+            "US 80\nusd 40\nata 40\nus 10\ndata 30",
+        );
+        let mut lm = LanguageModel::new(corpus);
+        lm.cost_model = CostModel::Frequency;
+        lm.case_sensitive = true;
+        assert_eq!(lm.split(String::from("USData")), "US Data");
+        assert_eq!(lm.split(String::from("usdata")), "usd ata");
+    }
+
+    #[test]
+    fn test_word_queries() {
+        let corpus = write_corpus("word_queries.txt", "the\nJordan\nrust");
+        let mut lm = LanguageModel::new(corpus);
+        assert_eq!(lm.vocab_size(), 3);
+        assert!(lm.contains("jordan") && lm.contains("RUST"));
+        assert!(!lm.contains("ferris"));
+        assert!(lm.cost("the").unwrap() < lm.cost("jordan").unwrap());
+        assert_eq!(lm.cost("ferris"), None);
+        let probability = lm.probability("the").unwrap();
+        assert!((probability - 1.0 / 3f32.ln()).abs() < 1e-6);
+        lm.insert_word("ferris", 1.0).unwrap();
+        assert_eq!((lm.cost("ferris"), lm.vocab_size()), (Some(1.0), 4));
+
+        let mut words = HashMap::new();
+        words.insert(String::from("ferris"), 2.0);
+        lm.cost_function = Some(Box::new(words));
+        assert_eq!((lm.cost("ferris"), lm.cost("the")), (Some(2.0), None));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut lm = LanguageModel::new(String::new());
+        let result = lm.split_scored(String::from("rustisgreat"));
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.starts_with(r#"{"words":["rust","is","great"],"total_cost":"#));
+        assert_eq!(serde_json::from_str::<SplitResult>(&json).unwrap(), result);
+        let tokens = lm.split_tokens(String::from("rust isgreat"));
+        let json = serde_json::to_string(&tokens).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Token>>(&json).unwrap(), tokens);
+
+        let json = serde_json::to_string(&OovPolicy::CharPenalty(2.0)).unwrap();
+        assert_eq!(json, r#"{"CharPenalty":2.0}"#);
+        let rules: SentenceRules =
+            serde_json::from_str(r#"{"terminators":["."],"capital_starts_sentence":false}"#)
+                .unwrap();
+        assert!(!rules.capital_starts_sentence);
+        let case_mode: CaseMode = serde_json::from_str(r#""SentenceCase""#).unwrap();
+        assert_eq!(case_mode, CaseMode::SentenceCase);
+    }
+
+    #[test]
+    fn test_str_api() {
+        let text = "rustisgreat now";
+        assert_eq!(split(text), split(String::from(text)));
+        let mut lm = LanguageModel::new(String::new());
+        lm.cache_capacity = Some(4);
+        assert_eq!(lm.split(text), "rust is great now");
+        assert_eq!(lm.split(String::from(text)), "rust is great now");
+        assert_eq!(lm.cache_stats().hits, 1);
+        assert_eq!(lm.split_words(text), ["rust", "is", "great", "now"]);
+        assert_eq!(lm.split_spans(text), [(0, 4), (4, 6), (6, 11), (12, 15)]);
+        let ranges = lm.split_ranges(text);
+        assert_eq!(ranges, [0..4, 4..6, 6..11, 12..15]);
+        let words: Vec<&str> = ranges.into_iter().map(|range| &text[range]).collect();
+        assert_eq!(words, lm.split_ref(text));
+        assert_eq!(lm.split_char_spans("é rustisgreat")[1], (2, 6));
+    }
+
+    #[test]
+    fn test_shared_model() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LanguageModel>();
+        assert_send_sync::<SharedModel>();
+
+        let mut lm = LanguageModel::new(String::new());
+        lm.backend = DictionaryBackend::Trie;
+        lm.max_input_len = Some(20);
+        let shared: SharedModel = lm.into();
+        let clone = shared.clone();
+        assert!(std::ptr::eq(&*shared, &*clone));
+        assert_eq!(clone.split("rust isgreat"), "rust is great");
+        assert_eq!(clone.split("x".repeat(21)), "x".repeat(21));
+        assert_eq!(shared.split_words("rustisgreat"), ["rust", "is", "great"]);
+        assert_eq!(
+            shared.split_spans("rust isgreat"),
+            [(0, 4), (5, 7), (7, 12)]
+        );
+        assert_eq!(shared.split_ref("Rustisgreat"), ["Rust", "is", "great"]);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let model = shared.clone();
+                scope.spawn(move || {
+                    assert_eq!(model.split("thequickbrownfox"), "the quick brown fox")
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_bundled_corpus() {
+        let corpus = std::fs::read_to_string("src/corpus.txt").unwrap();
+        assert_eq!(default_corpus(), corpus);
+        assert!(ENCODED_CORPUS.len() < corpus.len() * 3 / 5);
+    }
+
+    #[test]
+    fn test_unusual_input() {
+        assert_eq!(split(""), "");
+        assert!(split_words("").is_empty());
+        assert_eq!(split(" \t\n"), " \t\n");
+        assert_eq!(split_words("123456"), ["123456"]);
+        assert_eq!(split("born1990inparis"), "born 1990 in paris");
+        // Text already split keeps its spaces, every token is split again
+        assert_eq!(split("rust is great"), "rust is great");
+        assert_eq!(
+            split("  rustisgreat  bankofjordan "),
+            "  rust is great  bank of jordan "
+        );
+        // A symbol does not make the rest of the run fall apart into letters
+        assert_eq!(split("rust-great"), "rust - great");
+        assert_eq!(split("hello,world"), "hello , world");
+        assert_eq!(split("\u{0}great"), "\u{0} great");
+    }
+
+    #[test]
+    fn test_split_text() {
+        let path = write_corpus(
+            "split_text",
+            "to\ngether\nbank\nof\njordan\nthanks\ntogether\n",
+        );
+        let mut lm = LanguageModel::new(path);
+        assert_eq!(lm.split("together"), "to gether");
+        assert_eq!(
+            lm.split_text("together  bankofjordan\nthanks\n"),
+            "together  bank of jordan\nthanks\n"
+        );
+        assert_eq!(
+            split_text("please visitbankofjordan today"),
+            "please visit bank of jordan today"
+        );
+    }
+
+    #[test]
+    fn test_split_with() {
+        /// The segments of a path, the slashes kept between them
+        struct Path;
+        impl PreTokenizer for Path {
+            fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+                let mut pieces = Vec::new();
+                for (i, segment) in text.split('/').enumerate() {
+                    if i > 0 {
+                        pieces.push(PreToken::Separator("/"));
+                    }
+                    pieces.push(PreToken::Split(segment));
+                }
+                pieces
+            }
+        }
+
+        let mut lm = LanguageModel::new(String::new());
+        let text = "  rustisgreat\tbankofjordan 2019 ";
+        assert_eq!(lm.split_with(&Whitespace, text), split(text));
+        assert_eq!(
+            lm.split_with(&Identifier, "max_wordlength parseJSON"),
+            "max word length parse JSON"
+        );
+        assert_eq!(
+            lm.split_with(&Hashtag, "#throwback2019🔥"),
+            "throwback 2019 🔥"
+        );
+        let tokenizer: &dyn PreTokenizer = &Path;
+        assert_eq!(
+            lm.split_with(tokenizer, "bankofjordan/rustisgreat"),
+            "bank of jordan/rust is great"
+        );
+        lm.separator = String::from("_");
+        assert_eq!(
+            lm.split_with(&Identifier, "parseJSON bankofjordan"),
+            "parse_JSON bank_of_jordan"
+        );
+        lm.max_input_len = Some(8);
+        assert_eq!(lm.split_with(&Identifier, "parseJSONnow"), "parseJSONnow");
+        assert_eq!(
+            Alphabetic.pre_tokenize("v2 ok"),
+            [
+                PreToken::Split("v"),
+                PreToken::Keep("2"),
+                PreToken::Separator(" "),
+                PreToken::Split("ok")
+            ]
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mapped_dictionary() {
+        let path = std::env::temp_dir().join(format!("rsplitter_{}_mapped", std::process::id()));
+        let mut lm = LanguageModel::new(String::new());
+        lm.save_mapped(&path).unwrap();
+        let mut mapped = LanguageModel::open_mapped(&path).unwrap();
+        assert_eq!(mapped.vocab_size(), lm.vocab_size());
+        for text in ["rustisgreat", "bankofjordan 2019", "thequickbrownfox-jumps"] {
+            assert_eq!(mapped.split(text), lm.split(text));
+        }
+        let dictionary = MappedDictionary::open(&path).unwrap();
+        assert_eq!(dictionary.get("jordan"), lm.cost("jordan"));
+        assert_eq!(dictionary.get("xqzwk"), None);
+
+        std::fs::write(&path, b"RSPM\x01\0\0\0\0\xff\xff\xff\xff\0\0\0\0").unwrap();
+        assert!(matches!(
+            MappedDictionary::open(&path),
+            Err(SplitError::InvalidModel(_))
+        ));
+    }
+
+    #[test]
+    fn test_contractions() {
+        assert_eq!(split("whatsupdon'tworry"), "what sup don't worry");
+        assert_eq!(split("we'rehomeandyou'llsee"), "we're home and you'll see");
+        // A typographic apostrophe is looked up as `'` and written as it is
+        assert_eq!(split("don\u{2019}tstop"), "don\u{2019}t stop");
+        assert_eq!(split_words("it\u{2019}sgreat"), ["it\u{2019}s", "great"]);
+        let path = write_corpus("contractions", "we\nre\nhome\nwe\u{2019}re\n");
+        let mut lm = LanguageModel::new(path);
+        assert_eq!(lm.split("we'rehome"), "we're home");
+        assert_eq!(lm.split_with(&Alphabetic, "we're2home"), "we're 2 home");
+    }
+
+    #[test]
+    fn test_unspaced_scripts() {
+        let path = write_corpus(
+            "jieba",
+            "我 1000 r\n爱 800 v\n北京 500 ns\n天安门 400 ns\n北 10 n\n京 10 n\n",
+        );
+        let mut lm = LanguageModel::new(path);
+        lm.cost_model = CostModel::Frequency;
+        assert_eq!(lm.split("我爱北京天安门"), "我 爱 北京 天安门");
+        // "和" is not in the dictionary, the words after it are still found
+        assert_eq!(lm.split("我爱北京和天安门"), "我 爱 北京 和 天安门");
+        assert_eq!(lm.split_char_spans("我爱北京"), [(0, 1), (1, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn test_from_corpus_reader() {
+        let mut lm =
+            LanguageModel::from_corpus_reader("\u{feff}rust\nis\ngreat\n".as_bytes()).unwrap();
+        assert_eq!(lm.split("rustisgreat"), "rust is great");
+        assert!(matches!(
+            LanguageModel::from_corpus_reader(&b"\xff\xfe"[..]),
+            Err(SplitError::Io(_))
+        ));
+        assert!(matches!(
+            LanguageModel::from_corpus_reader("\n\n".as_bytes()),
+            Err(SplitError::EmptyCorpus)
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_async_readers() {
+        /// Poll a future reading from memory, which is ready at once
+        fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+            let mut future = std::pin::pin!(future);
+            let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+            match future.as_mut().poll(&mut context) {
+                std::task::Poll::Ready(value) => value,
+                std::task::Poll::Pending => panic!("the reader is in memory"),
+            }
+        }
+
+        let corpus: &[u8] = b"ban\nkof\njordan\n";
+        let mut lm = ready(LanguageModel::from_corpus_reader_async(corpus)).unwrap();
+        assert_eq!(lm.split("bankofjordan"), "ban kof jordan");
+        let mut compiled = Vec::new();
+        lm.to_writer(&mut compiled).unwrap();
+        let mut lm = ready(LanguageModel::from_reader_async(compiled.as_slice())).unwrap();
+        assert_eq!(lm.split("bankofjordan"), "ban kof jordan");
+    }
+
+    #[test]
+    fn test_debug_split() {
+        let mut lm = LanguageModel::new(String::new());
+        let debug = lm.debug_split("rustisgreat now");
+        assert_eq!(debug.split, "rust is great now");
+        assert_eq!(debug.lattice.len(), "rustisgreatnow".len());
+        // The positions count the characters of the whole text, whitespace included
+        let winners: Vec<&str> = [4, 6, 11, 15]
+            .iter()
+            .map(|position| {
+                let explanation = debug
+                    .lattice
+                    .iter()
+                    .find(|explanation| explanation.position == *position)
+                    .unwrap();
+                explanation.candidates[explanation.winner].word.as_str()
+            })
+            .collect();
+        assert_eq!(winners, ["rust", "is", "great", "now"]);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config() {
+        let config = Config::from_toml(
+            "language = \"English\"\noov_policy = \"KeepWhole\"\npre_tokenizer = \"Hashtag\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.oov_policy, OovPolicy::KeepWhole);
+        assert_eq!(config.output, OutputFormat::Text);
+        let mut lm = LanguageModel::from_config(&config).unwrap();
+        assert_eq!(
+            lm.split_with(&config.pre_tokenizer, "#iloverust"),
+            "i love rust"
+        );
+        assert_eq!(lm.split("xylografmachine"), "xylograf machine");
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+        let config = Config::from_toml("oov_policy = { CharPenalty = 2.0 }").unwrap();
+        assert_eq!(config.oov_policy, OovPolicy::CharPenalty(2.0));
+        assert!(matches!(
+            Config::from_toml("corpus = \"words.txt\""),
+            Err(SplitError::InvalidInput(_))
+        ));
+        let config = Config {
+            corpus_paths: vec![std::path::PathBuf::from("/nonexistent/words.txt")],
+            ..Config::default()
+        };
+        assert!(matches!(
+            LanguageModel::from_config(&config),
+            Err(SplitError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_reload() {
+        let corpus =
+            std::env::temp_dir().join(format!("rsplitter_reload_{}.txt", std::process::id()));
+        std::fs::write(&corpus, "the\ncat\nsat\n").unwrap();
+        let mut lm = LanguageModel::from_corpus(corpus.to_str().unwrap().to_string()).unwrap();
+        let unknown = |lm: &mut LanguageModel| {
+            lm.split_scored(String::from("thecatsatonthemat"))
+                .unknown_words
+        };
+        assert_eq!(unknown(&mut lm), 2);
+        std::fs::write(&corpus, "the\ncat\nsat\non\nmat\n").unwrap();
+        lm.reload().unwrap();
+        assert_eq!(lm.split("thecatsatonthemat"), "the cat sat on the mat");
+        assert_eq!(unknown(&mut lm), 0);
+        std::fs::write(&corpus, "\n").unwrap();
+        assert!(matches!(lm.reload(), Err(SplitError::EmptyCorpus)));
+        assert_eq!(lm.split("thecatsatonthemat"), "the cat sat on the mat");
+        std::fs::remove_file(&corpus).unwrap();
+
+        let handle = Arc::new(ModelHandle::new(LanguageModel::new(String::new())));
+        let old = handle.load();
+        let mut fresh = LanguageModel::new(String::new());
+        fresh.add_words(&["bankofjordan"], 1.0).unwrap();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = Arc::clone(&handle);
+                std::thread::spawn(move || {
+                    let split = handle.split("bankofjordan");
+                    assert!(split == "bank of jordan" || split == "bankofjordan");
+                })
+            })
+            .collect();
+        handle.store(fresh);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(old.split("bankofjordan"), "bank of jordan");
+        assert_eq!(handle.split("bankofjordan"), "bankofjordan");
+    }
+
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn test_grapheme_clusters() {
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(
+            lm.split("i👍🏽love👨\u{200d}👩\u{200d}👧family"),
+            "i 👍🏽 love 👨\u{200d}👩\u{200d}👧 family"
+        );
+        assert_eq!(lm.split("rust🇫🇷great"), "rust 🇫🇷 great");
+        // The words after an emoji are split on their own, whatever the unknown word before
+        assert_eq!(lm.split("niño👍grande"), "ni ñ o 👍 grande");
+        assert_eq!(lm.split("cafe\u{301}latte"), "cafe\u{301} latte");
+        let text = "nin\u{303}o👍\u{fe0f}grande";
+        for (start, end) in lm.split_spans(text) {
+            assert!(text.is_char_boundary(start) && text.is_char_boundary(end));
+            assert!(!text[end..].starts_with(['\u{303}', '\u{fe0f}']));
+        }
+        assert!(!lm.split(text).contains(" \u{303}"));
+    }
+
+    #[test]
+    fn test_split_iter() {
+        let mut lm = LanguageModel::new(String::new());
+        for text in [
+            "",
+            "  ",
+            "rustisgreat",
+            " thequick\tbrownfox  jumps ",
+            "Ünïcödeismybank",
+        ] {
+            let words: Vec<&str> = lm.split_iter(text).collect();
+            assert_eq!(words, lm.split_ref(text));
+        }
+        let domain = "thequickbrownfoxjumpsoverthelazydog ".repeat(100);
+        let mut words = lm.split_iter(&domain);
+        assert_eq!(
+            words.by_ref().take(5).collect::<Vec<_>>(),
+            ["the", "quick", "brown", "fox", "jumps"]
+        );
+        assert_eq!(words.count(), 9 * 100 - 5);
+    }
+
+    #[test]
+    fn test_split_limited() {
+        let long = "pneumonoultramicroscopicsilicovolcanoconiosis";
+        let corpus = write_corpus(
+            "limited.txt",
+            &format!("the\ncat\nsat\non\nmat\n{}\n", long),
+        );
+        let mut lm = LanguageModel::from_corpus(corpus.clone()).unwrap();
+        assert_eq!(lm.max_word_len, None);
+        let text = "thecatsatonthemat".repeat(4);
+        let expected = lm.split(&text);
+        let limits = SplitLimits {
+            max_word_len: Some(3),
+            ..SplitLimits::default()
+        };
+        assert_eq!(lm.split_limited(&text, limits), expected);
+        assert_eq!(
+            lm.split_limited(long, limits),
+            lm.split_bounded(long.to_string(), 3)
+        );
+        let limits = SplitLimits {
+            beam_width: Some(4),
+            ..limits
+        };
+        assert_eq!(lm.split_limited(&text, limits), expected);
+        assert_eq!(lm.split_limited(&text, SplitLimits::default()), expected);
+        let mut lm = LanguageModel::builder()
+            .corpus_path(corpus)
+            .beam_width(2)
+            .build()
+            .unwrap();
+        assert_eq!(lm.beam_width, Some(2));
+        assert_eq!(lm.split(&text), expected);
+    }
+
+    #[test]
+    fn test_units() {
+        let corpus = write_corpus("units.txt", "of\nrice\nbag\nsugar\nwater\nmen\ngallon\n");
+        let mut lm = LanguageModel::new(String::new());
+        assert_eq!(lm.split("2tbspsugar"), "2 tbs p sugar");
+        lm.units = UNITS.iter().map(|unit| unit.to_string()).collect();
+        assert_eq!(lm.split("2tbspsugar"), "2 tbsp sugar");
+        assert_eq!(lm.split("16gbram"), "16 gb ram");
+        let mut lm = LanguageModel::builder()
+            .corpus_path(corpus)
+            .units(UNITS.iter().copied())
+            .build()
+            .unwrap();
+        assert_eq!(lm.split("5kgofrice"), "5 kg of rice");
+        assert_eq!(lm.split("2tbspsugar"), "2 tbsp sugar");
+        assert_eq!(lm.split("1,5lwater"), "1,5 l water");
+        assert_eq!(lm.split("20°cwater"), "20 °c water");
+        assert_eq!(lm.split("5men"), "5 men");
+        assert_eq!(lm.split("1gallon"), "1 gallon");
+    }
+
+    #[cfg(feature = "wordninja")]
+    #[test]
+    fn test_wordninja_gz() {
+        let path = std::env::temp_dir().join(format!("rsplitter_words_{}.gz", std::process::id()));
+        let mut lm = LanguageModel::from_bytes(b"the\ncat\nsat\non\nmat\n").unwrap();
+        lm.save_wordninja_gz(&path).unwrap();
+        let mut loaded = LanguageModel::from_wordninja_gz(&path).unwrap();
+        assert_eq!(loaded.split("thecatsatonthemat"), "the cat sat on the mat");
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 5;
+        bytes[last] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            LanguageModel::from_wordninja_gz(&path),
+            Err(SplitError::InvalidModel(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_verify() {
+        let lm = LanguageModel::from_corpus(String::new()).unwrap();
+        assert_eq!(lm.verify(&["bank", "of", "jordan"]), Ok(()));
+        assert_eq!(
+            lm.verify(&[
+                String::from("rus"),
+                String::from("tis"),
+                String::from("great")
+            ]),
+            Err(vec![
+                String::from("rust"),
+                String::from("is"),
+                String::from("great")
+            ])
+        );
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn test_word_strategy() {
+        use proptest::strategy::{Strategy, ValueTree};
+        let lm = LanguageModel::from_bytes(b"apple\nbanana\ncherry\ndurian\nelderberry\n").unwrap();
+        let strategy = lm.word_strategy(3, 1..5);
+        let mut runner = proptest::test_runner::TestRunner::deterministic();
+        let words = strategy.new_tree(&mut runner).unwrap().current();
+        assert!(words
+            .iter()
+            .all(|word| ["apple", "banana", "cherry"].contains(&word.as_str())));
+        runner
+            .run(&strategy, |words| {
+                proptest::prop_assert_eq!(lm.verify(&words), Ok(()));
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_load_stats() {
+        assert_eq!(LanguageModel::new(String::new()).load_stats(), None);
+        let mut lm = LanguageModel::new(String::new());
+        lm.try_load().unwrap();
+        let stats = lm.load_stats().unwrap();
+        assert_eq!(stats.words, lm.cost_dict.as_ref().unwrap().0.len());
+        assert!(stats.lines >= stats.words);
+        assert_eq!(stats.max_word_len as i32, lm.cost_dict.as_ref().unwrap().1);
+
+        let mut threaded = LanguageModel::new(String::new());
+        threaded.load_and_split_many(&[]).unwrap();
+        assert_eq!(threaded.load_stats().unwrap().words, stats.words);
+        let corpus = write_corpus("load_stats", "rust\nis\ngreat\n");
+        lm.load_corpora(&[std::path::PathBuf::from(&corpus)])
+            .unwrap();
+        assert_eq!(lm.load_stats().unwrap().words, 3);
+        std::fs::remove_file(corpus).unwrap();
+    }
+
+    #[test]
+    fn test_export() {
+        let mut lm = LanguageModel::from_bytes(b"rust\nis\ngreat\n").unwrap();
+        lm.insert_word("a,b", 30.0).unwrap();
+        let mut csv = Vec::new();
+        lm.export_to(&mut csv, ExportFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "word,cost");
+        assert!(lines[1].starts_with("rust,"));
+        assert!(lines[4].starts_with("\"a,b\","));
+        assert_eq!(lines.len(), 5);
+
+        let stats = lm.dictionary_stats();
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.max_cost, 30.0);
+        assert!(stats.min_cost <= stats.median_cost && stats.median_cost <= stats.mean_cost);
+
+        let path =
+            std::env::temp_dir().join(format!("rsplitter_export_{}.json", std::process::id()));
+        lm.export(&path, ExportFormat::Json).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["stats"]["words"], 4);
+        assert_eq!(json["stats"]["max_word_len"], 5);
+        assert_eq!(json["costs"]["a,b"], 30.0);
+        assert_eq!(json["costs"].as_object().unwrap().len(), 4);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pin() {
+        let mut lm = LanguageModel::new(String::new());
+        lm.cache_capacity = Some(10);
+        assert_eq!(lm.split("penisland"), "penis land");
+        lm.pin("PenIsland", &["pen", "island"]).unwrap();
+        assert_eq!(lm.split("penisland"), "pen island");
+        assert_eq!(lm.split("visit PENISLAND now"), "visit PEN ISLAND now");
+        assert_eq!(lm.split_ref("penisland"), ["pen", "island"]);
+        assert_eq!(lm.split_words("penislandpens"), ["penis", "land", "pens"]);
+        assert!(matches!(
+            lm.pin("penisland", &["pen", "is"]),
+            Err(SplitError::InvalidInput(_))
+        ));
+        assert!(lm.pin("pen island", &["pen", "island"]).is_err());
+        assert!(lm.pin("penisland", &["", "penisland"]).is_err());
+        assert!(lm.unpin("penIsland"));
+        assert!(!lm.unpin("penisland"));
+        assert_eq!(lm.split("penisland"), "penis land");
+    }
 }
@@ -0,0 +1,263 @@
+#[cfg(feature = "config")]
+use rsplitter::{Config, OutputFormat, PreTokenizerMode};
+use rsplitter::{LanguageModel, Token};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: rsplitter [OPTIONS] [TEXT]...
+Split texts into words: the TEXT arguments, one output per argument, or else the files of
+--file, or else the standard input. Files and the standard input are streamed line by line.
+
+Options:
+  --config PATH  Read the corpora, the language, the OOV policy, the pre-tokenizer and the
+                 output format from a TOML file, the other options take precedence.
+                 Needs the config feature.
+  --corpus PATH  One word per line, from the most to the least frequent.
+                 Repeat it to merge several files, the earlier ones ranking first.
+                 The bundled corpus is used by default.
+  --file PATH    Split a file, repeat it to split several.
+  --json         Write a JSON object per input with its text, split and words.
+  --lines        With --json, write an object per line of a file or of the standard
+                 input instead of one for all of it.
+  --             Every later argument is a TEXT, even one starting with --.";
+
+/// The command line options
+struct Options {
+    config: Option<PathBuf>,
+    corpus_paths: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    texts: Vec<String>,
+    json: bool,
+    lines: bool,
+    /// The pre-tokenizer of the configuration, the splits of `LanguageModel::split` when
+    /// it is `Whitespace`
+    #[cfg(feature = "config")]
+    pre_tokenizer: PreTokenizerMode,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        config: None,
+        corpus_paths: Vec::new(),
+        files: Vec::new(),
+        texts: Vec::new(),
+        json: false,
+        lines: false,
+        #[cfg(feature = "config")]
+        pre_tokenizer: PreTokenizerMode::Whitespace,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = args.next().ok_or("--config needs a path")?;
+                options.config = Some(PathBuf::from(path));
+            }
+            "--corpus" => {
+                let path = args.next().ok_or("--corpus needs a path")?;
+                options.corpus_paths.push(PathBuf::from(path));
+            }
+            "--file" => {
+                let path = args.next().ok_or("--file needs a path")?;
+                options.files.push(PathBuf::from(path));
+            }
+            "--json" => options.json = true,
+            "--lines" => options.lines = true,
+            "--" => options.texts.extend(args.by_ref()),
+            _ if arg.starts_with("--") => return Err(format!("unexpected argument {}", arg)),
+            _ => options.texts.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+/// The model of the corpora of the options, the bundled corpus without any
+fn corpus_model(options: &Options) -> Result<LanguageModel, String> {
+    // The dictionary is built once, before the first line is read
+    let model = if options.corpus_paths.is_empty() {
+        LanguageModel::from_corpus(String::new())
+    } else {
+        LanguageModel::from_corpora(&options.corpus_paths)
+    };
+    model.map_err(|err| format!("cannot load the corpus: {}", err))
+}
+
+/// The model of the options with the settings of the configuration file, if any: the
+/// corpora of `--corpus` replace its own, and its output format adds to `--json` and
+/// `--lines`
+#[cfg(feature = "config")]
+fn load_model(options: &mut Options) -> Result<LanguageModel, String> {
+    let Some(path) = &options.config else {
+        return corpus_model(options);
+    };
+    let mut config = Config::load(path)
+        .map_err(|err| format!("cannot read the configuration {}: {}", path.display(), err))?;
+    if !options.corpus_paths.is_empty() {
+        config.corpus_paths = options.corpus_paths.clone();
+    }
+    options.json |= config.output != OutputFormat::Text;
+    options.lines |= config.output == OutputFormat::JsonLines;
+    options.pre_tokenizer = config.pre_tokenizer;
+    LanguageModel::from_config(&config).map_err(|err| format!("cannot load the corpus: {}", err))
+}
+
+#[cfg(not(feature = "config"))]
+fn load_model(options: &mut Options) -> Result<LanguageModel, String> {
+    if options.config.is_some() {
+        return Err(String::from(
+            "--config needs rsplitter built with the config feature",
+        ));
+    }
+    corpus_model(options)
+}
+
+/// Write the split of a text, as a line of text or of JSON
+fn write_split(
+    model: &mut LanguageModel,
+    text: String,
+    options: &Options,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    #[cfg(feature = "config")]
+    if options.pre_tokenizer != PreTokenizerMode::Whitespace {
+        let split = model.split_with(&options.pre_tokenizer, &text);
+        if !options.json {
+            return writeln!(output, "{}", split);
+        }
+        let words: Vec<String> = split.split_ascii_whitespace().map(json_string).collect();
+        return write_json(&text, &split, &words, output);
+    }
+    if !options.json {
+        return writeln!(output, "{}", model.split(text));
+    }
+    let tokens = model.split_tokens(text.clone());
+    let split: String = tokens.iter().map(Token::as_str).collect();
+    let words: Vec<String> = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Word(word) => Some(json_string(word)),
+            Token::Separator(_) => None,
+        })
+        .collect();
+    write_json(&text, &split, &words, output)
+}
+
+/// Write the JSON object of a split, its words already JSON strings
+fn write_json(
+    text: &str,
+    split: &str,
+    words: &[String],
+    output: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "{{\"text\":{},\"split\":{},\"words\":[{}]}}",
+        json_string(text),
+        json_string(split),
+        words.join(",")
+    )
+}
+
+/// A JSON string literal of a text
+fn json_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Split the lines of `input` as they arrive, each output line is flushed right away.
+/// With JSON output and without `lines`, the whole input is a single text instead.
+fn split_stream(
+    model: &mut LanguageModel,
+    mut input: impl BufRead,
+    output: &mut impl Write,
+    options: &Options,
+) -> io::Result<()> {
+    if options.json && !options.lines {
+        let mut text = String::new();
+        input.read_to_string(&mut text)?;
+        write_split(model, text, options, output)?;
+        return output.flush();
+    }
+    for line in input.lines() {
+        write_split(model, line?, options, output)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Split the inputs chosen by the options: the texts, or the files, or the standard input
+fn run(model: &mut LanguageModel, options: &Options) -> io::Result<()> {
+    let mut output = BufWriter::new(io::stdout().lock());
+    if !options.texts.is_empty() {
+        for text in &options.texts {
+            write_split(model, text.clone(), options, &mut output)?;
+        }
+        return output.flush();
+    }
+    if options.files.is_empty() {
+        let stdin = BufReader::new(io::stdin().lock());
+        return split_stream(model, stdin, &mut output, options);
+    }
+    for path in &options.files {
+        let file = File::open(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("cannot read {}: {}", path.display(), err),
+            )
+        })?;
+        split_stream(model, BufReader::new(file), &mut output, options)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options_end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    if args[..options_end]
+        .iter()
+        .any(|arg| arg == "-h" || arg == "--help")
+    {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let mut options = match parse_args(args.into_iter()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("rsplitter: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let mut model = match load_model(&mut options) {
+        Ok(model) => model,
+        Err(message) => {
+            eprintln!("rsplitter: {}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&mut model, &options) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader of the output went away, e.g. `rsplitter | head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("rsplitter: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::error::SplitError;
+use crate::language_model::{CostDict, WordCost};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Identifies a mapped dictionary, followed by the format version
+const MAGIC: &[u8; 4] = b"RSPM";
+const VERSION: u8 = 1;
+/// The magic, the version, `max_word` as an `i32` and the number of words as a `u64`
+const HEADER_LEN: usize = 17;
+
+/// A dictionary read from a file mapped in memory, see `LanguageModel::open_mapped`.
+///
+/// Opening one reads the header only: the pages of the file are loaded by the system as
+/// the words are looked up, and processes mapping the same file share them. A lookup is a
+/// binary search over the sorted words, slower than a hash lookup but with no dictionary
+/// to build or hold in memory.
+pub struct MappedDictionary {
+    map: Mmap,
+    max_word: i32,
+    words: usize,
+}
+
+impl MappedDictionary {
+    /// Map a dictionary written by `LanguageModel::save_mapped`
+    /// # Returns
+    /// An `InvalidModel` error if the file is not a mapped dictionary, or is too short for
+    /// the words its header counts
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SplitError> {
+        let file = File::open(path)?;
+        // The file must not change while it is mapped, like a model file is not written by
+        // the processes reading it
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_LEN || &map[..4] != MAGIC {
+            return Err(SplitError::InvalidModel("missing magic number".to_string()));
+        }
+        if map[4] != VERSION {
+            return Err(SplitError::InvalidModel(format!(
+                "unsupported version {}",
+                map[4]
+            )));
+        }
+        let max_word = i32::from_le_bytes(map[5..9].try_into().unwrap());
+        let words = usize::try_from(u64::from_le_bytes(map[9..17].try_into().unwrap()))
+            .ok()
+            .filter(|words| {
+                words
+                    .checked_mul(8)
+                    .is_some_and(|index| index <= map.len() - HEADER_LEN)
+            })
+            .ok_or_else(|| SplitError::InvalidModel("truncated word index".to_string()))?;
+        Ok(MappedDictionary {
+            map,
+            max_word,
+            words,
+        })
+    }
+
+    /// The length of the longest word, in characters
+    pub fn max_word(&self) -> i32 {
+        self.max_word
+    }
+
+    /// The number of words
+    pub fn len(&self) -> usize {
+        self.words
+    }
+
+    /// Whether the dictionary has no word
+    pub fn is_empty(&self) -> bool {
+        self.words == 0
+    }
+
+    /// The cost of a word, `None` when it is not in the dictionary
+    pub fn get(&self, word: &str) -> Option<f32> {
+        let (mut low, mut high) = (0, self.words);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (entry, cost) = self.entry(middle)?;
+            match entry.cmp(word.as_bytes()) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(cost),
+            }
+        }
+        None
+    }
+
+    /// The bytes and the cost of the i-th word, `None` if the file is corrupted there
+    fn entry(&self, i: usize) -> Option<(&[u8], f32)> {
+        let at = HEADER_LEN + i * 8;
+        let offset = usize::try_from(u64::from_le_bytes(
+            self.map.get(at..at + 8)?.try_into().ok()?,
+        ))
+        .ok()?;
+        let length = u32::from_le_bytes(self.map.get(offset..offset + 4)?.try_into().ok()?);
+        let start = offset + 4;
+        let end = start.checked_add(length as usize)?;
+        let word = self.map.get(start..end)?;
+        let cost = f32::from_le_bytes(self.map.get(end..end + 4)?.try_into().ok()?);
+        Some((word, cost))
+    }
+}
+
+impl WordCost for MappedDictionary {
+    fn cost(&self, word: &str) -> f32 {
+        self.get(word).unwrap_or(f32::MAX)
+    }
+
+    fn vocab_size(&self) -> Option<usize> {
+        Some(self.words)
+    }
+}
+
+/// Write a cost dictionary in the mapped format: the header, then the `u64` offset of every
+/// word in the file, then every word as a `u32` byte length, its UTF-8 bytes and its `f32`
+/// cost. All numbers are little-endian and the words are sorted by their bytes, the order
+/// of the binary search of `MappedDictionary::get`.
+pub(crate) fn write_mapped<W: Write, S>(
+    mut writer: W,
+    cost_dict: &CostDict<S>,
+) -> Result<(), SplitError> {
+    let mut words: Vec<(&String, &f32)> = cost_dict.0.iter().collect();
+    words.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(&cost_dict.1.to_le_bytes())?;
+    writer.write_all(&(words.len() as u64).to_le_bytes())?;
+    let mut offset = (HEADER_LEN + words.len() * 8) as u64;
+    for (word, _) in &words {
+        writer.write_all(&offset.to_le_bytes())?;
+        offset += 8 + word.len() as u64;
+    }
+    for (word, cost) in words {
+        writer.write_all(&(word.len() as u32).to_le_bytes())?;
+        writer.write_all(word.as_bytes())?;
+        writer.write_all(&cost.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
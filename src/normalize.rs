@@ -0,0 +1,74 @@
+/// Write every letter of the Latin-1 Supplement and Latin Extended-A blocks without its
+/// accent, e.g. "creme brulee" for "crème brûlée", for `LanguageModel::normalize`.
+/// Every character stays a single character, so the split keeps the accents of the input.
+pub fn strip_accents(text: &str) -> String {
+    text.chars().map(|c| base_letter(c).unwrap_or(c)).collect()
+}
+
+/// Write every run of more than two repeated characters as two of them, e.g. "good cool"
+/// for "goooood cooool", for `LanguageModel::normalize`. Elongated words then match the
+/// dictionary, but the split is written as normalized.
+pub fn collapse_elongations(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut previous = None;
+    let mut repeats = 0;
+    for c in text.chars() {
+        if previous == Some(c) {
+            repeats += 1;
+        } else {
+            previous = Some(c);
+            repeats = 1;
+        }
+        if repeats <= 2 {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// The letter without its accent, for the accented letters of Latin-1 and Latin Extended-A
+/// that decompose to a single ASCII letter
+fn base_letter(c: char) -> Option<char> {
+    let base = match c {
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => 'A',
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => 'C',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'Ď' => 'D',
+        'ď' => 'd',
+        'È'..='Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => 'E',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => 'G',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'Ĥ' => 'H',
+        'ĥ' => 'h',
+        'Ì'..='Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => 'I',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+        'Ĵ' => 'J',
+        'ĵ' => 'j',
+        'Ķ' => 'K',
+        'ķ' => 'k',
+        'Ĺ' | 'Ļ' | 'Ľ' => 'L',
+        'ĺ' | 'ļ' | 'ľ' => 'l',
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => 'N',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'Ò'..='Ö' | 'Ō' | 'Ŏ' | 'Ő' => 'O',
+        'ò'..='ö' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'Ŕ' | 'Ŗ' | 'Ř' => 'R',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => 'S',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'Ţ' | 'Ť' => 'T',
+        'ţ' | 'ť' => 't',
+        'Ù'..='Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => 'U',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'Ŵ' => 'W',
+        'ŵ' => 'w',
+        'Ý' | 'Ŷ' | 'Ÿ' => 'Y',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => return None,
+    };
+    Some(base)
+}
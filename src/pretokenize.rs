@@ -0,0 +1,195 @@
+use crate::language_model::is_apostrophe;
+
+/// A piece of a text cut by a `PreTokenizer`, see `LanguageModel::split_with`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreToken<'a> {
+    /// Glued words, split with the dictionary
+    Split(&'a str),
+    /// A word written as it is, e.g. a number or an acronym
+    Keep(&'a str),
+    /// Written as it is between the words, e.g. whitespace, instead of a space
+    Separator(&'a str),
+}
+
+/// Cut a text into the pieces `LanguageModel::split_with` splits, before any dictionary
+/// lookup. A character left out of every piece is dropped, e.g. the underscores of an
+/// identifier.
+pub trait PreTokenizer {
+    /// The pieces of `text`, in order
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>>;
+}
+
+/// The runs between ASCII whitespace are split and the whitespace is kept, like
+/// `LanguageModel::split`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Whitespace;
+
+/// Only the runs of letters, with the apostrophes of the contractions, are split, the other
+/// characters between whitespace are words of their own, e.g. "v 2" for "v2"
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Alphabetic;
+
+/// The identifiers of source code, like `LanguageModel::split_identifier`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identifier;
+
+/// Hashtags and handles without their sigil, like `LanguageModel::split_hashtag`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Hashtag;
+
+impl PreTokenizer for Whitespace {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+        whitespace_runs(text, |run, pieces| pieces.push(PreToken::Split(run)))
+    }
+}
+
+impl PreTokenizer for Alphabetic {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+        whitespace_runs(text, |run, pieces| {
+            let mut start = 0;
+            while start < run.len() {
+                let letters = run[start..].starts_with(is_letter);
+                let end = run[start..]
+                    .find(|c: char| is_letter(c) != letters)
+                    .map_or(run.len(), |end| start + end);
+                let piece = &run[start..end];
+                pieces.push(if letters {
+                    PreToken::Split(piece)
+                } else {
+                    PreToken::Keep(piece)
+                });
+                start = end;
+            }
+        })
+    }
+}
+
+impl PreTokenizer for Identifier {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+        whitespace_runs(text, |run, pieces| pieces.extend(social_pieces(run, true)))
+    }
+}
+
+impl PreTokenizer for Hashtag {
+    fn pre_tokenize<'a>(&self, text: &'a str) -> Vec<PreToken<'a>> {
+        whitespace_runs(text, |run, pieces| {
+            pieces.extend(social_pieces(strip_sigil(run).1, false))
+        })
+    }
+}
+
+/// The pieces of the runs of a text between ASCII whitespace, cut by `run_pieces`, and the
+/// whitespace between them
+fn whitespace_runs<'a>(
+    text: &'a str,
+    mut run_pieces: impl FnMut(&'a str, &mut Vec<PreToken<'a>>),
+) -> Vec<PreToken<'a>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let space = text[start..].starts_with(|c: char| c.is_ascii_whitespace());
+        let end = text[start..]
+            .find(|c: char| c.is_ascii_whitespace() != space)
+            .map_or(text.len(), |end| start + end);
+        if space {
+            pieces.push(PreToken::Separator(&text[start..end]));
+        } else {
+            run_pieces(&text[start..end], &mut pieces);
+        }
+        start = end;
+    }
+    pieces
+}
+
+/// Whether a character belongs to a word, a letter or the apostrophe of a contraction
+fn is_letter(c: char) -> bool {
+    c.is_alphabetic() || is_apostrophe(c)
+}
+
+/// The `#` or `@` of a hashtag or a handle, empty if there is none, and the rest of it
+pub(crate) fn strip_sigil(run: &str) -> (&str, &str) {
+    match run.strip_prefix(['#', '@']) {
+        Some(body) if !body.is_empty() => (&run[..1], body),
+        _ => ("", run),
+    }
+}
+
+/// The pieces of a hashtag without its sigil, or of an identifier: the runs of letters are
+/// split, cut first where the casing changes for an identifier, an acronym of an identifier,
+/// the digits and the other characters are kept and the underscores are dropped
+pub(crate) fn social_pieces(body: &str, identifier: bool) -> Vec<PreToken<'_>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while start < body.len() {
+        let class = SocialClass::of(body[start..].chars().next().unwrap());
+        let end = body[start..]
+            .find(|c| SocialClass::of(c) != class)
+            .map_or(body.len(), |end| start + end);
+        let piece = &body[start..end];
+        match class {
+            SocialClass::Letters if identifier => {
+                for chunk in case_chunks(piece) {
+                    if chunk.chars().nth(1).is_some() && !chunk.chars().any(char::is_lowercase) {
+                        pieces.push(PreToken::Keep(chunk));
+                    } else {
+                        pieces.push(PreToken::Split(chunk));
+                    }
+                }
+            }
+            SocialClass::Letters => pieces.push(PreToken::Split(piece)),
+            SocialClass::Underscores => {}
+            SocialClass::Digits | SocialClass::Other => pieces.push(PreToken::Keep(piece)),
+        }
+        start = end;
+    }
+    pieces
+}
+
+/// The pieces of a hashtag or an identifier `split_hashtag` and `split_identifier` split
+/// apart
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SocialClass {
+    /// Letters and apostrophes, split with the dictionary
+    Letters,
+    Digits,
+    /// Dropped, they only separate the words of a handle
+    Underscores,
+    /// Emoji, punctuation and symbols, kept as they are
+    Other,
+}
+
+impl SocialClass {
+    fn of(c: char) -> Self {
+        match c {
+            c if is_letter(c) => SocialClass::Letters,
+            c if c.is_numeric() => SocialClass::Digits,
+            '_' => SocialClass::Underscores,
+            _ => SocialClass::Other,
+        }
+    }
+}
+
+/// The chunks of a run of letters between the changes of casing of an identifier: before an
+/// uppercase letter following a lowercase one, e.g. "parse" and "Response" in
+/// "parseResponse", and before the last letter of an acronym followed by a lowercase one,
+/// e.g. "HTTP" and "Response" in "HTTPResponse"
+fn case_chunks(letters: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = letters.char_indices().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (index, c) = chars[i];
+        let previous = chars[i - 1].1;
+        let next_lowercase = chars
+            .get(i + 1)
+            .is_some_and(|(_, next)| next.is_lowercase());
+        if c.is_uppercase()
+            && (previous.is_lowercase() || (previous.is_uppercase() && next_lowercase))
+        {
+            chunks.push(&letters[start..index]);
+            start = index;
+        }
+    }
+    chunks.push(&letters[start..]);
+    chunks
+}
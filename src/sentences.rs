@@ -0,0 +1,60 @@
+/// Heuristics used to find sentence boundaries in a list of split words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SentenceRules {
+    /// A word equal to, or ending with, one of these closes the current sentence.
+    pub terminators: Vec<String>,
+    /// A dictionary word starting with an uppercase letter opens a new sentence,
+    /// e.g. "thecatsatThedogran" is read as two sentences.
+    pub capital_starts_sentence: bool,
+}
+
+impl Default for SentenceRules {
+    fn default() -> Self {
+        SentenceRules {
+            terminators: vec![".".to_string(), "!".to_string(), "?".to_string()],
+            capital_starts_sentence: true,
+        }
+    }
+}
+
+/// Group split words into sentences
+/// # Arguments
+/// * `words` - The words in the order they were split
+/// * `rules` - When a sentence ends
+/// * `is_known` - Whether a word is in the dictionary
+/// # Returns
+/// A Vec of sentences, none of them empty
+pub(crate) fn group_sentences<F>(
+    words: Vec<String>,
+    rules: &SentenceRules,
+    is_known: F,
+) -> Vec<Vec<String>>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut sentences: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for word in words {
+        let starts_with_capital = word.chars().next().is_some_and(|c| c.is_uppercase());
+        if rules.capital_starts_sentence
+            && !current.is_empty()
+            && starts_with_capital
+            && is_known(&word)
+        {
+            sentences.push(std::mem::take(&mut current));
+        }
+        let ends_sentence = rules
+            .terminators
+            .iter()
+            .any(|terminator| word.ends_with(terminator.as_str()));
+        current.push(word);
+        if ends_sentence {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
@@ -0,0 +1,69 @@
+use crate::error::SplitError;
+use crate::language_model::CostDict;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Identifies a compiled model, followed by the format version
+const MAGIC: &[u8; 4] = b"RSPL";
+const VERSION: u8 = 1;
+
+/// Write a cost dictionary in the compiled model format:
+/// the magic and version, `max_word` as an `i32`, the number of words as a `u64`,
+/// then every word as a `u32` byte length, its UTF-8 bytes and its `f32` cost.
+/// All numbers are little-endian and the words are sorted, so the output is reproducible.
+pub(crate) fn write_dict<W: Write, S>(
+    mut writer: W,
+    cost_dict: &CostDict<S>,
+) -> Result<(), SplitError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(&cost_dict.1.to_le_bytes())?;
+    writer.write_all(&(cost_dict.0.len() as u64).to_le_bytes())?;
+    let mut words: Vec<(&String, &f32)> = cost_dict.0.iter().collect();
+    words.sort_by(|a, b| a.0.cmp(b.0));
+    for (word, cost) in words {
+        writer.write_all(&(word.len() as u32).to_le_bytes())?;
+        writer.write_all(word.as_bytes())?;
+        writer.write_all(&cost.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a cost dictionary written by `write_dict`
+pub(crate) fn read_dict<R: Read>(mut reader: R) -> Result<CostDict, SplitError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(SplitError::InvalidModel("missing magic number".to_string()));
+    }
+    let version = read_array::<_, 1>(&mut reader)?[0];
+    if version != VERSION {
+        return Err(SplitError::InvalidModel(format!(
+            "unsupported version {}",
+            version
+        )));
+    }
+    let max_word = i32::from_le_bytes(read_array(&mut reader)?);
+    let count = u64::from_le_bytes(read_array(&mut reader)?);
+    let mut dict = HashMap::default();
+    for _ in 0..count {
+        let length = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+        let mut word = Vec::new();
+        (&mut reader).take(length as u64).read_to_end(&mut word)?;
+        if word.len() != length {
+            return Err(SplitError::InvalidModel("truncated word".to_string()));
+        }
+        let word = String::from_utf8(word)
+            .map_err(|_| SplitError::InvalidModel("word is not UTF-8".to_string()))?;
+        let cost = f32::from_le_bytes(read_array(&mut reader)?);
+        dict.insert(word, cost);
+    }
+    Ok((dict, max_word))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], SplitError> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
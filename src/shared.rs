@@ -0,0 +1,126 @@
+use crate::hasher::DictHasher;
+use crate::language_model::{LanguageModel, Trace};
+use std::hash::BuildHasher;
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+
+/// A loaded model behind an `Arc`, see `LanguageModel::into_shared`.
+///
+/// Cloning the handle is one reference count, every clone reads the same dictionary, and
+/// the splits take `&self`, so the threads of a worker pool split concurrently without a
+/// lock. The model can no longer be changed. The `&self` methods of `LanguageModel`, e.g.
+/// `split_ref`, are reached through `Deref`.
+///
+/// `LanguageModel::split` itself keeps `&mut self`, as it loads the dictionary on first
+/// use and updates the result cache of `LanguageModel::cache_capacity` on every split. The
+/// splits of a `SharedModel` neither read nor fill that cache, whatever its capacity: it
+/// would need a lock, which this type is there to avoid.
+pub struct SharedModel<S = DictHasher> {
+    model: Arc<LanguageModel<S>>,
+}
+
+impl<S> Clone for SharedModel<S> {
+    fn clone(&self) -> Self {
+        SharedModel {
+            model: Arc::clone(&self.model),
+        }
+    }
+}
+
+impl<S> Deref for SharedModel<S> {
+    type Target = LanguageModel<S>;
+
+    fn deref(&self) -> &LanguageModel<S> {
+        &self.model
+    }
+}
+
+impl<S: BuildHasher + Clone> From<LanguageModel<S>> for SharedModel<S> {
+    fn from(model: LanguageModel<S>) -> Self {
+        model.into_shared()
+    }
+}
+
+impl<S: BuildHasher + Clone> SharedModel<S> {
+    pub(crate) fn new(model: LanguageModel<S>) -> Self {
+        SharedModel {
+            model: Arc::new(model),
+        }
+    }
+
+    /// Split a text like `LanguageModel::split`, without the result cache, see `SharedModel`
+    /// # Arguments
+    /// * `text` - The text to be split, a `String` or a `&str`
+    pub fn split(&self, text: impl AsRef<str>) -> String {
+        let text = text.as_ref();
+        if self.model.is_too_long(text) {
+            return text.to_string();
+        }
+        self.model.split_traced(text, &mut Trace::new())
+    }
+
+    /// The words of a split, like `LanguageModel::split_words`
+    pub fn split_words(&self, text: impl AsRef<str>) -> Vec<String> {
+        self.model.words_of(text.as_ref())
+    }
+
+    /// The byte offsets of the words of a split, like `LanguageModel::split_spans`
+    pub fn split_spans(&self, text: impl AsRef<str>) -> Vec<(usize, usize)> {
+        self.model.word_spans(text.as_ref())
+    }
+}
+
+/// A shared model that can be replaced while it is used, e.g. by a reloaded one after the
+/// corpus file of a long-running service was updated.
+///
+/// `load` takes the current model out as a `SharedModel`, the lock only guards the clone of
+/// its `Arc`: the splits started with the old model finish with it, the later ones use the
+/// model of `store`, and the old dictionary is freed with its last split.
+///
+/// # Examples
+/// ```
+/// use rsplitter::{LanguageModel, ModelHandle};
+/// let handle = ModelHandle::new(LanguageModel::new(String::new()));
+/// let old = handle.load();
+/// let mut fresh = LanguageModel::new(String::new());
+/// fresh.add_words(&["rustacean"], 1.0).unwrap();
+/// handle.store(fresh);
+/// assert_eq!(old.split("rustaceans"), "rust ace an s");
+/// assert_eq!(handle.split("rustaceans"), "rustacean s");
+/// ```
+pub struct ModelHandle<S = DictHasher> {
+    current: RwLock<SharedModel<S>>,
+}
+
+impl<S: BuildHasher + Clone> ModelHandle<S> {
+    /// A handle on a model, loaded first if it is not
+    pub fn new(model: LanguageModel<S>) -> Self {
+        ModelHandle {
+            current: RwLock::new(model.into_shared()),
+        }
+    }
+
+    /// The current model, kept by the caller for as long as it needs it
+    pub fn load(&self) -> SharedModel<S> {
+        self.current
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Replace the current model, loaded first if it is not, outside of the lock
+    /// # Returns
+    /// The previous model
+    pub fn store(&self, model: LanguageModel<S>) -> SharedModel<S> {
+        let model = model.into_shared();
+        std::mem::replace(
+            &mut *self.current.write().unwrap_or_else(|err| err.into_inner()),
+            model,
+        )
+    }
+
+    /// Split a text with the current model, like `SharedModel::split`
+    pub fn split(&self, text: impl AsRef<str>) -> String {
+        self.load().split(text)
+    }
+}
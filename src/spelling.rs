@@ -0,0 +1,115 @@
+use crate::hasher::DictHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// The cheapest dictionary word within one edit (insertion, deletion or substitution) of `word`.
+/// The whole dictionary is scanned, ties on cost are broken alphabetically.
+pub(crate) fn nearest_word<S: BuildHasher>(
+    dict: &HashMap<String, f32, S>,
+    word: &str,
+) -> Option<String> {
+    let length = word.chars().count();
+    dict.iter()
+        .filter(|(candidate, _)| candidate.chars().count().abs_diff(length) <= 1)
+        .filter(|(candidate, _)| within_one_edit(candidate, word))
+        .min_by(|a, b| a.1.total_cmp(b.1).then_with(|| a.0.cmp(b.0)))
+        .map(|(candidate, _)| candidate.to_string())
+}
+
+/// Whether the Levenshtein distance between `a` and `b` is at most 1
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        // One substitution at most: everything after the first mismatch must match
+        prefix == short.len() || short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        // One insertion: skipping the first mismatch of the longer word must realign them
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+/// The dictionary words by their single-character deletions, to find the words within one
+/// edit of a substring with a few hash lookups instead of a scan of the dictionary.
+/// A word within one edit of the substring is the substring, one of its deletions, or shares
+/// a deletion with it; the words sharing a deletion are checked, as some are two edits away.
+pub(crate) struct DeletionIndex {
+    /// The words and their costs
+    words: Vec<(String, f32)>,
+    /// The indices in `words` of the words with a deletion, by deletion
+    deletions: HashMap<String, Vec<u32>, DictHasher>,
+}
+
+impl DeletionIndex {
+    pub(crate) fn new<S: BuildHasher>(dict: &HashMap<String, f32, S>) -> Self {
+        // Sorted, so that the index does not depend on the order of the hash map
+        let mut words: Vec<(String, f32)> = dict
+            .iter()
+            .map(|(word, cost)| (word.clone(), *cost))
+            .collect();
+        words.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut deletions: HashMap<String, Vec<u32>, DictHasher> = HashMap::default();
+        for (idx, (word, _)) in words.iter().enumerate() {
+            for deletion in single_deletions(word) {
+                deletions.entry(deletion).or_default().push(idx as u32);
+            }
+        }
+        DeletionIndex { words, deletions }
+    }
+
+    /// The cost of the cheapest dictionary word one edit away from `word`, an edit being an
+    /// insertion, a deletion, a substitution or a transposition of adjacent characters
+    pub(crate) fn cheapest_within_one_edit<S: BuildHasher>(
+        &self,
+        dict: &HashMap<String, f32, S>,
+        word: &str,
+    ) -> Option<f32> {
+        let mut costs: Vec<f32> = Vec::new();
+        let mut indices: Vec<u32> = self.deletions.get(word).cloned().unwrap_or_default();
+        for deletion in single_deletions(word) {
+            costs.extend(dict.get(&deletion));
+            indices.extend(self.deletions.get(&deletion).into_iter().flatten());
+        }
+        costs.extend(indices.into_iter().filter_map(|idx| {
+            let (candidate, cost) = &self.words[idx as usize];
+            (within_one_edit(candidate, word) || transposed(candidate, word)).then_some(*cost)
+        }));
+        costs.into_iter().min_by(f32::total_cmp)
+    }
+
+    /// An approximation of the memory held by the index, in bytes
+    pub(crate) fn memory_estimate(&self) -> usize {
+        let words: usize = self.words.iter().map(|(word, _)| word.capacity()).sum();
+        let deletions: usize = self
+            .deletions
+            .iter()
+            .map(|(deletion, indices)| deletion.capacity() + indices.capacity() * 4)
+            .sum();
+        self.words.capacity() * std::mem::size_of::<(String, f32)>()
+            + words
+            + self.deletions.capacity() * (std::mem::size_of::<(String, Vec<u32>)>() + 1)
+            + deletions
+    }
+}
+
+/// The words made by deleting one character of `word`
+fn single_deletions(word: &str) -> impl Iterator<Item = String> + '_ {
+    word.char_indices()
+        .map(move |(idx, c)| format!("{}{}", &word[..idx], &word[idx + c.len_utf8()..]))
+}
+
+/// Whether `b` is `a` with two adjacent characters swapped
+fn transposed(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len() != b.len() {
+        return false;
+    }
+    let mismatches: Vec<usize> = (0..a.len()).filter(|&i| a[i] != b[i]).collect();
+    matches!(mismatches[..], [i, j] if j == i + 1 && a[i] == b[j] && a[j] == b[i])
+}
@@ -0,0 +1,167 @@
+use crate::hasher::DictHasher;
+use crate::language_model::LanguageModel;
+use std::collections::VecDeque;
+use std::hash::BuildHasher;
+use std::iter::FusedIterator;
+
+/// The words of a text as slices of it, split a run at a time as they are reached, see
+/// `LanguageModel::split_iter`.
+///
+/// A run between whitespace is split at once when its first word is asked for, as its
+/// words are only known once the dynamic program reaches its end; its words are then
+/// yielded from a buffer, and the runs after it are left alone until they are reached.
+pub struct SplitIter<'a, 't, S = DictHasher> {
+    model: &'a LanguageModel<S>,
+    text: &'t str,
+    /// The byte offset of the next run to split, past the end of the text after the last one
+    next_run: usize,
+    /// The byte offsets of the words of the current run not yet yielded
+    spans: VecDeque<(usize, usize)>,
+}
+
+impl<'a, 't, S: BuildHasher + Clone> SplitIter<'a, 't, S> {
+    pub(crate) fn new(model: &'a LanguageModel<S>, text: &'t str) -> Self {
+        let mut iter = SplitIter {
+            model,
+            text,
+            next_run: 0,
+            spans: VecDeque::new(),
+        };
+        if model.is_too_long(text) {
+            // The text is a single word, like in `split_ref`
+            iter.spans.extend(model.word_spans(text));
+            iter.next_run = text.len() + 1;
+        }
+        iter
+    }
+}
+
+impl<'t, S: BuildHasher + Clone> Iterator for SplitIter<'_, 't, S> {
+    type Item = &'t str;
+
+    fn next(&mut self) -> Option<&'t str> {
+        loop {
+            if let Some((start, end)) = self.spans.pop_front() {
+                return Some(&self.text[start..end]);
+            }
+            if self.next_run > self.text.len() {
+                return None;
+            }
+            let start = self.next_run;
+            let end = self.text[start..]
+                .find(|c: char| c.is_ascii_whitespace())
+                .map_or(self.text.len(), |end| start + end);
+            self.model
+                .run_spans(&self.text[start..end], start, &mut self.spans);
+            // The whitespace is a single byte
+            self.next_run = end + 1;
+        }
+    }
+}
+
+impl<S: BuildHasher + Clone> FusedIterator for SplitIter<'_, '_, S> {}
+
+/// How many of the longest candidate words a run without whitespace is buffered for before
+/// its first words are split off
+const WINDOW_WORDS: usize = 8;
+
+/// The words of a stream of characters, split as they arrive, see
+/// `LanguageModel::split_stream`.
+///
+/// The characters are buffered up to the next whitespace, which ends a run of the text like
+/// in `split`. A run longer than the window, several times the longest candidate word, is
+/// split as far as it goes: the words that end more than a longest word before the end of
+/// the buffer are yielded, as more text can no longer change them, and the other ones are
+/// split again with the characters that follow. The buffer never holds much more than the
+/// window, whatever the length of the text.
+pub struct SplitStream<'a, I, S = DictHasher> {
+    model: &'a LanguageModel<S>,
+    chars: I,
+    /// The characters of the current run not yet split
+    buffer: String,
+    buffered_chars: usize,
+    /// The longest candidate word, in characters
+    longest: usize,
+    words: VecDeque<String>,
+    done: bool,
+}
+
+impl<'a, I: Iterator<Item = char>, S: BuildHasher + Clone> SplitStream<'a, I, S> {
+    pub(crate) fn new(model: &'a LanguageModel<S>, chars: I) -> Self {
+        SplitStream {
+            model,
+            chars,
+            buffer: String::new(),
+            buffered_chars: 0,
+            longest: model.longest_candidate().max(1),
+            words: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Split the whole buffer, at the end of a run
+    fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            self.words.extend(self.model.words_of(&self.buffer));
+            self.buffer.clear();
+            self.buffered_chars = 0;
+        }
+    }
+
+    /// Split off the words of the buffer that the characters to come cannot change
+    fn flush_settled(&mut self) {
+        let settled = self.buffered_chars - self.longest;
+        let mut chars = 0;
+        let mut cut = 0;
+        for (_, end) in self.model.word_spans(&self.buffer) {
+            // From the end of the previous word, with the characters dropped between them
+            let length = self.buffer[cut..end].chars().count();
+            if chars + length > settled {
+                break;
+            }
+            chars += length;
+            cut = end;
+        }
+        if cut == 0 {
+            // No word ends early enough, e.g. in a long unknown word: cut it anyway
+            chars = settled;
+            cut = self
+                .buffer
+                .char_indices()
+                .nth(settled)
+                .map_or(self.buffer.len(), |(index, _)| index);
+        }
+        self.words.extend(self.model.words_of(&self.buffer[..cut]));
+        self.buffer.drain(..cut);
+        self.buffered_chars -= chars;
+    }
+}
+
+impl<I: Iterator<Item = char>, S: BuildHasher + Clone> Iterator for SplitStream<'_, I, S> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let Some(word) = self.words.pop_front() {
+                return Some(word);
+            }
+            if self.done {
+                return None;
+            }
+            match self.chars.next() {
+                Some(c) if c.is_ascii_whitespace() => self.flush(),
+                Some(c) => {
+                    self.buffer.push(c);
+                    self.buffered_chars += 1;
+                    if self.buffered_chars >= self.longest * WINDOW_WORDS {
+                        self.flush_settled();
+                    }
+                }
+                None => {
+                    self.flush();
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::hasher::DictHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// A trie over the reversed dictionary words.
+/// The candidates ending at a position of the text are found by walking the text backwards
+/// once, instead of hashing every candidate substring.
+/// The children of every node are one sorted range of `edges`, looked up by binary search,
+/// so a node takes a few bytes instead of a map of its own.
+#[derive(Default)]
+pub(crate) struct Trie {
+    nodes: Vec<Node>,
+    /// The character and node of every child, sorted by character within each node
+    edges: Vec<(char, u32)>,
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    /// The children of the node are `edges[first_edge..first_edge + edge_count]`
+    first_edge: u32,
+    edge_count: u32,
+    /// The cost of the word ending at this node, `f32::MAX` when no word ends here
+    cost: f32,
+}
+
+impl Trie {
+    pub(crate) fn new<S: BuildHasher>(dict: &HashMap<String, f32, S>) -> Self {
+        // Built with a map per node first, then laid out node by node
+        let mut children: Vec<HashMap<char, u32, DictHasher>> = vec![HashMap::default()];
+        let mut costs: Vec<f32> = vec![f32::MAX];
+        for (word, cost) in dict {
+            let mut node = 0;
+            for c in word.chars().rev() {
+                node = match children[node].get(&c) {
+                    Some(&child) => child as usize,
+                    None => {
+                        children.push(HashMap::default());
+                        costs.push(f32::MAX);
+                        let child = children.len() - 1;
+                        children[node].insert(c, child as u32);
+                        child
+                    }
+                };
+            }
+            costs[node] = *cost;
+        }
+        let mut trie = Trie {
+            nodes: Vec::with_capacity(children.len()),
+            edges: Vec::with_capacity(children.len().saturating_sub(1)),
+        };
+        for (node_children, cost) in children.into_iter().zip(costs) {
+            let first_edge = trie.edges.len();
+            trie.edges.extend(node_children);
+            trie.edges[first_edge..].sort_unstable_by_key(|(c, _)| *c);
+            trie.nodes.push(Node {
+                first_edge: first_edge as u32,
+                edge_count: (trie.edges.len() - first_edge) as u32,
+                cost,
+            });
+        }
+        trie
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.edges.shrink_to_fit();
+    }
+
+    /// An approximation of the memory held by the trie, in bytes
+    pub(crate) fn memory_estimate(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self.edges.capacity() * std::mem::size_of::<(char, u32)>()
+    }
+
+    /// The child of `node` through `c`, if any
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let Node {
+            first_edge,
+            edge_count,
+            ..
+        } = self.nodes[node];
+        let edges = &self.edges[first_edge as usize..(first_edge + edge_count) as usize];
+        edges
+            .binary_search_by_key(&c, |(edge, _)| *edge)
+            .ok()
+            .map(|index| edges[index].1 as usize)
+    }
+
+    /// Costs of the words ending at the end of `text`, the k-th one being its last k + 1
+    /// characters, for `window` candidates; unknown candidates cost `f32::MAX`
+    pub(crate) fn suffix_costs(&self, text: &str, window: usize) -> Vec<f32> {
+        let mut costs = vec![f32::MAX; window];
+        let mut node = 0;
+        for (k, c) in text.chars().rev().take(window).enumerate() {
+            // `text` is lowercased like the dictionary words
+            match self.child(node, c) {
+                Some(child) => node = child,
+                None => break,
+            }
+            costs[k] = self.nodes[node].cost;
+        }
+        costs
+    }
+
+    /// `suffix_costs` for an ASCII text, walked over its bytes without decoding characters
+    pub(crate) fn ascii_suffix_costs(&self, text: &[u8], window: usize) -> Vec<f32> {
+        let mut costs = vec![f32::MAX; window];
+        let mut node = 0;
+        for (k, b) in text.iter().rev().take(window).enumerate() {
+            match self.child(node, *b as char) {
+                Some(child) => node = child,
+                None => break,
+            }
+            costs[k] = self.nodes[node].cost;
+        }
+        costs
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::SplitError;
+
+/// The magic number of a gzip member, then the deflate compression method
+const MAGIC: [u8; 3] = [0x1f, 0x8b, 8];
+/// The flags of the optional header fields
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// The data of a gzip file of a single member, e.g. the `wordninja_words.txt.gz` word list
+/// of wordninja, checked against its CRC and its length
+pub(crate) fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, SplitError> {
+    let invalid = |reason: &str| SplitError::InvalidModel(format!("gzip: {}", reason));
+    if bytes.len() < 18 || bytes[..3] != MAGIC {
+        return Err(invalid("missing magic number"));
+    }
+    let flags = bytes[3];
+    let mut at = 10;
+    if flags & FEXTRA != 0 {
+        let length = bytes
+            .get(at..at + 2)
+            .map(|length| u16::from_le_bytes([length[0], length[1]]) as usize)
+            .ok_or_else(|| invalid("truncated header"))?;
+        at += 2 + length;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = bytes
+                .get(at..)
+                .and_then(|rest| rest.iter().position(|&byte| byte == 0))
+                .ok_or_else(|| invalid("truncated header"))?;
+            at += end + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        at += 2;
+    }
+    let trailer = bytes.len() - 8;
+    if at > trailer {
+        return Err(invalid("truncated header"));
+    }
+    let data = miniz_oxide::inflate::decompress_to_vec(&bytes[at..trailer])
+        .map_err(|err| invalid(&format!("cannot inflate: {:?}", err.status)))?;
+    let crc = u32::from_le_bytes(bytes[trailer..trailer + 4].try_into().unwrap());
+    let length = u32::from_le_bytes(bytes[trailer + 4..].try_into().unwrap());
+    if crc32(&data) != crc || data.len() as u32 != length {
+        return Err(invalid("corrupted data"));
+    }
+    Ok(data)
+}
+
+/// A gzip file of the data, with no optional header field
+pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+    let compressed = miniz_oxide::deflate::compress_to_vec(data, 9);
+    let mut bytes = Vec::with_capacity(compressed.len() + 18);
+    bytes.extend_from_slice(&MAGIC);
+    // No flag, no modification time, the best compression and an unknown system
+    bytes.extend_from_slice(&[0, 0, 0, 0, 0, 2, 255]);
+    bytes.extend_from_slice(&compressed);
+    bytes.extend_from_slice(&crc32(data).to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes
+}
+
+/// The CRC-32 of gzip, bit by bit: the word lists are read once, when a model is built
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,152 @@
+/// How a character takes part in a word, an approximation of the Word_Break property of
+/// Unicode's UAX #29 derived from the character classes available in `std`
+#[cfg(not(feature = "graphemes"))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WordClass {
+    /// Letters, which form words with each other and with digits
+    Letter,
+    /// Digits, which form numbers with each other and words with letters
+    Numeric,
+    /// Katakana, which form words with each other only
+    Katakana,
+    /// Ideographs and Hiragana, every one of them is a word on its own
+    Ideographic,
+    /// Joins letters, e.g. the colon of some Swedish abbreviations
+    MidLetter,
+    /// Joins numbers, e.g. "1,000"
+    MidNum,
+    /// Joins letters or numbers, e.g. "can't" or "3.14"
+    MidNumLet,
+    /// Joins anything, e.g. "snake_case"
+    ExtendNumLet,
+    /// Never part of a word, e.g. whitespace or most punctuation
+    Other,
+    /// Part of the character before, e.g. a combining accent, a joiner or a soft hyphen
+    Extend,
+}
+
+#[cfg(not(feature = "graphemes"))]
+fn word_class(c: char) -> WordClass {
+    match c {
+        '\u{300}'..='\u{36F}'
+        | '\u{AD}'
+        | '\u{483}'..='\u{489}'
+        | '\u{591}'..='\u{5BD}'
+        | '\u{600}'..='\u{605}'
+        | '\u{61C}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200C}'..='\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{206F}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{FEFF}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0001}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}' => WordClass::Extend,
+        '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+            WordClass::Katakana
+        }
+        '\u{3040}'..='\u{309F}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FFFF}' => WordClass::Ideographic,
+        ':' | '\u{B7}' | '\u{387}' | '\u{5F4}' | '\u{2027}' | '\u{FE13}' | '\u{FE55}'
+        | '\u{FF1A}' => WordClass::MidLetter,
+        ',' | ';' | '\u{37E}' | '\u{589}' | '\u{60C}' | '\u{60D}' | '\u{66C}' | '\u{7F8}'
+        | '\u{2044}' | '\u{FE10}' | '\u{FE14}' | '\u{FE50}' | '\u{FE54}' | '\u{FF0C}'
+        | '\u{FF1B}' => WordClass::MidNum,
+        '.' | '\'' | '\u{2018}' | '\u{2019}' | '\u{2024}' | '\u{FE52}' | '\u{FF07}'
+        | '\u{FF0E}' => WordClass::MidNumLet,
+        '_' | '\u{203F}' | '\u{2040}' | '\u{2054}' | '\u{FE33}' | '\u{FE34}' | '\u{FE4D}'
+        | '\u{FE4E}' | '\u{FE4F}' | '\u{FF3F}' => WordClass::ExtendNumLet,
+        c if c.is_numeric() => WordClass::Numeric,
+        c if c.is_alphabetic() => WordClass::Letter,
+        _ => WordClass::Other,
+    }
+}
+
+/// Whether two consecutive classes belong to the same word, without looking further
+#[cfg(not(feature = "graphemes"))]
+fn joins(before: WordClass, after: WordClass) -> bool {
+    use WordClass::*;
+    matches!(
+        (before, after),
+        (Letter | Numeric, Letter | Numeric)
+            | (Katakana, Katakana)
+            | (Letter | Numeric | Katakana | ExtendNumLet, ExtendNumLet)
+            | (ExtendNumLet, Letter | Numeric | Katakana)
+    )
+}
+
+/// The words of a text by the word boundaries of Unicode's UAX #29, dropping whitespace and
+/// punctuation between them: `unicode_words` of the `unicode-segmentation` crate with the
+/// `graphemes` feature
+#[cfg(feature = "graphemes")]
+pub(crate) fn unicode_words(text: &str) -> Vec<&str> {
+    unicode_segmentation::UnicodeSegmentation::unicode_words(text).collect()
+}
+
+/// The words of a text by the word boundaries of Unicode's UAX #29, dropping whitespace and
+/// punctuation between them, like `unicode_words` of the `unicode-segmentation` crate used
+/// with the `graphemes` feature.
+/// The Word_Break property is approximated from the `std` character classes: letters and
+/// digits build words together, a `MidLetter`, `MidNum` or `MidNumLet` character only joins
+/// the letters or digits on both of its sides, Katakana build words of their own and every
+/// ideograph or Hiragana is a word. The common combining marks, joiners and format
+/// characters stay with the character before them, which decides the boundaries. Emoji
+/// sequences and Hebrew-specific rules are not covered.
+#[cfg(not(feature = "graphemes"))]
+pub(crate) fn unicode_words(text: &str) -> Vec<&str> {
+    use WordClass::*;
+    // An Extend character is skipped unless it starts the text or follows whitespace
+    let mut classes: Vec<(usize, WordClass)> = Vec::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for (idx, c) in text.char_indices() {
+        let class = word_class(c);
+        let attached = class == Extend && previous.is_some_and(|p| !p.is_whitespace());
+        if !attached {
+            classes.push((idx, class));
+        }
+        previous = Some(c);
+    }
+    let class = |i: Option<usize>| i.and_then(|i| classes.get(i)).map(|(_, class)| *class);
+    // Whether there is no boundary between the characters `i - 1` and `i`
+    let no_boundary = |i: usize| {
+        let (before, after) = (classes[i - 1].1, classes[i].1);
+        let (before_before, after_after) = (class(i.checked_sub(2)), class(Some(i + 1)));
+        joins(before, after)
+            || (matches!(after, MidLetter | MidNumLet)
+                && before == Letter
+                && after_after == Some(Letter))
+            || (matches!(before, MidLetter | MidNumLet)
+                && before_before == Some(Letter)
+                && after == Letter)
+            || (matches!(after, MidNum | MidNumLet)
+                && before == Numeric
+                && after_after == Some(Numeric))
+            || (matches!(before, MidNum | MidNumLet)
+                && before_before == Some(Numeric)
+                && after == Numeric)
+    };
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for i in 1..classes.len() {
+        if !no_boundary(i) {
+            segments.push(&text[start..classes[i].0]);
+            start = classes[i].0;
+        }
+    }
+    if !text.is_empty() {
+        segments.push(&text[start..]);
+    }
+    // Like `unicode_words`, a word has at least one letter or digit
+    segments
+        .into_iter()
+        .filter(|segment| segment.chars().any(char::is_alphanumeric))
+        .collect()
+}
@@ -0,0 +1,143 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str], input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rsplitter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let written = child.stdin.take().unwrap().write_all(input.as_bytes());
+    // A run that fails before reading its input, e.g. on a bad option, closes the pipe first
+    if let Err(err) = written {
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+    child.wait_with_output().unwrap()
+}
+
+#[cfg(feature = "bundled-corpus")]
+#[test]
+fn test_stdin_lines_are_split() {
+    let output = run(&[], "rustisgreat\nbankofjordan\n\nthequickbrownfox");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "rust is great\nbank of jordan\n\nthe quick brown fox\n"
+    );
+}
+
+#[test]
+fn test_custom_corpus() {
+    let corpus = std::env::temp_dir().join(format!("rsplitter_cli_{}.txt", std::process::id()));
+    std::fs::write(&corpus, "the\ncat\nsat\n").unwrap();
+    let output = run(&["--corpus", corpus.to_str().unwrap()], "thecatsat\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cat sat\n");
+}
+
+#[test]
+fn test_repeated_corpus() {
+    let dir = std::env::temp_dir();
+    let first = dir.join(format!("rsplitter_cli_first_{}.txt", std::process::id()));
+    let second = dir.join(format!("rsplitter_cli_second_{}.txt", std::process::id()));
+    std::fs::write(&first, "the\ncat\nsat\n").unwrap();
+    std::fs::write(&second, "on\nmat\n").unwrap();
+    let args = [
+        "--corpus",
+        first.to_str().unwrap(),
+        "--corpus",
+        second.to_str().unwrap(),
+    ];
+    let output = run(&args, "thecatsatonthemat\n");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "the cat sat on the mat\n"
+    );
+}
+
+#[test]
+fn test_bad_arguments() {
+    let output = run(&["--corpus"], "");
+    assert_eq!(output.status.code(), Some(2));
+    let output = run(&["--corpus", "/does/not/exist"], "");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[cfg(feature = "bundled-corpus")]
+#[test]
+fn test_text_arguments() {
+    let output = run(&["rustisgreat", "bankofjordan"], "ignored\n");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "rust is great\nbank of jordan\n"
+    );
+    // Arguments after -- are texts, even --help
+    let output = run(&["--", "--help"], "");
+    assert!(output.status.success());
+    assert!(!String::from_utf8(output.stdout).unwrap().contains("Usage"));
+}
+
+#[cfg(feature = "bundled-corpus")]
+#[test]
+fn test_files() {
+    let dir = std::env::temp_dir();
+    let first = dir.join(format!("rsplitter_cli_input_{}.txt", std::process::id()));
+    std::fs::write(&first, "rustisgreat\nbankofjordan\n").unwrap();
+    let output = run(&["--file", first.to_str().unwrap()], "ignored\n");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "rust is great\nbank of jordan\n"
+    );
+    let output = run(&["--file", "/does/not/exist"], "");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[cfg(feature = "bundled-corpus")]
+#[test]
+fn test_json_output() {
+    let output = run(&["--json", "rustis \"great\""], "");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"text\":\"rustis \\\"great\\\"\",\"split\":\"rust is \\\" great \\\"\",\
+         \"words\":[\"rust\",\"is\",\"\\\"\",\"great\",\"\\\"\"]}\n"
+    );
+    let output = run(&["--json", "--lines"], "rustis\ngreat\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.starts_with("{\"text\":\"rustis\",\"split\":\"rust is\""));
+    let output = run(&["--json"], "rustis\ngreat\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\"text\":\"rustis\\ngreat\\n\",\"split\":\"rust is\\ngreat\\n\""));
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_config_file() {
+    let dir = std::env::temp_dir();
+    let corpus = dir.join(format!("rsplitter_cli_config_{}.txt", std::process::id()));
+    let config = dir.join(format!("rsplitter_cli_config_{}.toml", std::process::id()));
+    std::fs::write(&corpus, "parse\nresponse\nthe\ncat\n").unwrap();
+    std::fs::write(
+        &config,
+        format!(
+            "corpus_paths = [{:?}]\npre_tokenizer = \"Identifier\"\noutput = \"JsonLines\"\n",
+            corpus.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    let output = run(
+        &["--config", config.to_str().unwrap()],
+        "parseHTTPResponse\n",
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"text\":\"parseHTTPResponse\",\"split\":\"parse HTTP Response\",\"words\":[\"parse\",\"HTTP\",\"Response\"]}\n"
+    );
+    std::fs::write(&config, "pre_tokenizer = \"Camel\"\n").unwrap();
+    let output = run(&["--config", config.to_str().unwrap()], "thecat\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("cannot read the configuration"));
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "bundled-corpus")]
+use rsplitter::{reload_default_corpus, set_corpus, split, SplitError};
+use std::sync::Mutex;
+
+/// The tests change the dictionary of the whole process, they take turns
+static DEFAULT_CORPUS: Mutex<()> = Mutex::new(());
+
+fn write_corpus(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("rsplitter_{}_{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[test]
+fn test_reload_default_corpus() {
+    let _turn = DEFAULT_CORPUS.lock().unwrap();
+    assert_eq!(split(String::from("bankofjordan")), "bank of jordan");
+    reload_default_corpus(&write_corpus("reload.txt", "ban\nkof\njordan\n")).unwrap();
+    assert_eq!(split(String::from("bankofjordan")), "ban kof jordan");
+    // Other threads switch too
+    let other = std::thread::spawn(|| split(String::from("bankofjordan")));
+    assert_eq!(other.join().unwrap(), "ban kof jordan");
+
+    assert!(matches!(
+        reload_default_corpus(&write_corpus("reload_empty.txt", "\n")),
+        Err(SplitError::EmptyCorpus)
+    ));
+    assert_eq!(split(String::from("bankofjordan")), "ban kof jordan");
+    reload_default_corpus("").unwrap();
+    assert_eq!(split(String::from("bankofjordan")), "bank of jordan");
+}
+
+#[test]
+fn test_set_corpus() {
+    let _turn = DEFAULT_CORPUS.lock().unwrap();
+    set_corpus(words(&["# comment", "ban", " kof ", "jordan"])).unwrap();
+    assert_eq!(split(String::from("bankofjordan")), "ban kof jordan");
+    assert!(matches!(
+        set_corpus(words(&["", " "])),
+        Err(SplitError::EmptyCorpus)
+    ));
+    assert_eq!(split(String::from("bankofjordan")), "ban kof jordan");
+
+    // Readers keep splitting while the corpus changes under them
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            std::thread::spawn(|| {
+                for _ in 0..50 {
+                    let result = split(String::from("bankofjordan"));
+                    assert!(result == "ban kof jordan" || result == "bank of jordan");
+                }
+            })
+        })
+        .collect();
+    for _ in 0..5 {
+        set_corpus(words(&["bank", "of", "jordan"])).unwrap();
+        set_corpus(words(&["ban", "kof", "jordan"])).unwrap();
+    }
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    reload_default_corpus("").unwrap();
+}
@@ -0,0 +1,47 @@
+/*
+ * Smoke test of the C bindings:
+ *   cargo build
+ *   cc -Iinclude tests/ffi/smoke.c -Ltarget/debug -lrsplitter -o target/smoke
+ *   LD_LIBRARY_PATH=target/debug ./target/smoke
+ */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "rsplitter.h"
+
+int main(void) {
+    char *split = rsplitter_split("rustisgreat");
+    assert(split != NULL && strcmp(split, "rust is great") == 0);
+    rsplitter_free(split);
+
+    assert(rsplitter_split(NULL) == NULL);
+    assert(rsplitter_split("\xff\xfe") == NULL);
+    rsplitter_free(NULL);
+
+    LanguageModel *model = rsplitter_model_new("");
+    assert(model != NULL);
+    split = rsplitter_model_split(model, "thequickbrownfox");
+    assert(split != NULL && strcmp(split, "the quick brown fox") == 0);
+    rsplitter_free(split);
+    assert(rsplitter_model_split(model, NULL) == NULL);
+    assert(rsplitter_model_split(NULL, "text") == NULL);
+    rsplitter_model_free(model);
+
+    const char corpus[] = "ban\nkof\njordan\n";
+    model = rsplitter_model_from_bytes((const uint8_t *)corpus, strlen(corpus));
+    assert(model != NULL);
+    split = rsplitter_model_split(model, "bankofjordan");
+    assert(split != NULL && strcmp(split, "ban kof jordan") == 0);
+    rsplitter_free(split);
+    rsplitter_model_free(model);
+    assert(rsplitter_model_from_bytes(NULL, 0) == NULL);
+    assert(rsplitter_model_from_bytes((const uint8_t *)"\n", 1) == NULL);
+
+    assert(rsplitter_model_new("/does/not/exist") == NULL);
+    assert(rsplitter_model_new(NULL) == NULL);
+    rsplitter_model_free(NULL);
+
+    puts("ok");
+    return 0;
+}
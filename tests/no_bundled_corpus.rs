@@ -0,0 +1,29 @@
+#![cfg(not(feature = "bundled-corpus"))]
+use rsplitter::{LanguageModel, SplitError};
+
+fn write_corpus(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("rsplitter_{}_{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_no_bundled_corpus() {
+    let mut lm = LanguageModel::new(String::new());
+    assert!(matches!(lm.try_load(), Err(SplitError::NoCorpus)));
+    assert!(matches!(
+        lm.try_split(String::from("rustis")),
+        Err(SplitError::NoCorpus)
+    ));
+    assert!(matches!(
+        lm.add_words(&["rust"], 1.0),
+        Err(SplitError::NoCorpus)
+    ));
+    // Without a corpus, the splits that cannot fail find no word instead of panicking
+    assert_eq!(lm.split("rustis"), "r u s t i s");
+    assert_eq!(lm.vocab_size(), 0);
+    let mut lm = LanguageModel::new(write_corpus("no_bundled.txt", "rust\nis\ngreat"));
+    assert_eq!(lm.split("rustisgreat"), "rust is great");
+    let mut lm = LanguageModel::from_bytes(b"bank\nof\njordan").unwrap();
+    assert_eq!(lm.split("bankofjordan"), "bank of jordan");
+}